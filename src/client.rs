@@ -1,11 +1,14 @@
 use licheszter::{client::Licheszter, config::challenges::ChallengeOptions};
 use futures_util::StreamExt;
 use licheszter::models::board::Event;
+use licheszter::models::challenge::ChallengeDeclineReason;
 
 
 
 use std::env;
 mod game;
+#[cfg(test)]
+mod game_tests;
 
 static BOTS: [&str; 3] = ["GarboBot", "Jibbby", "halcyonbot"];
 
@@ -40,7 +43,7 @@ async fn attempt_challenge(client:& Licheszter) {
     
 }
 
-async fn handle_event(event: Event, client: &Licheszter) -> u8{
+async fn handle_event(event: Event, client: &Licheszter, resources: &game::GameResources) -> u8{
     match event {
         Event::GameStart { game } => {
 
@@ -48,7 +51,7 @@ async fn handle_event(event: Event, client: &Licheszter) -> u8{
                     against players: {:?}", 
                     game.id, game.opponent.username);
 
-            let game = game::Game::new(game.id);
+            let game = game::Game::new(game.id, resources);
 
             //TODO: handle in seperate thread
             game.game_handler().await;
@@ -65,6 +68,14 @@ async fn handle_event(event: Event, client: &Licheszter) -> u8{
                 return;
             }*/
             
+            if let Some(reason) = game::unplayable_reason(&challenge.variant.key, challenge.initial_fen.as_deref()) {
+                println!("Declining challenge {}, {}", challenge.id, reason);
+                if let Err(e) = client.challenge_decline(&challenge.id, Some(ChallengeDeclineReason::Variant)).await {
+                    eprintln!("Failed to decline challenge: {}", e);
+                }
+                return 1;
+            }
+
             client.challenge_accept(&challenge.id).await.unwrap();
             return 1;
         },
@@ -95,6 +106,9 @@ pub async fn li_bot() {
         .with_authentication(key)
         .build();
 
+    //loaded once, every game shares them
+    let resources = game::GameResources::load();
+
     
     loop{
         attempt_challenge(&client).await;
@@ -103,7 +117,7 @@ pub async fn li_bot() {
         while let Some(result) = events.next().await {
             match result {
                 Ok(event) => {
-                    if handle_event(event, &client).await == 0 {
+                    if handle_event(event, &client, &resources).await == 0 {
                         break;
                     }
                 },
@@ -112,4 +126,300 @@ pub async fn li_bot() {
         }
 
     }
-}
\ No newline at end of file
+}
+
+const PGN_USAGE:&str = "usage: pgn <games.pgn>";
+
+//reads every game of a pgn file and reports the games that can not be read, with the line of the problem
+pub fn check_pgn(args:&[String]) {
+
+    game::engine::state_bitboard::bit_boards::populate_rook_moves();
+    game::engine::state_bitboard::bit_boards::populate_bishop_moves();
+
+    let [path] = args else {
+        eprintln!("Expected one file, got {}\n{}", args.len(), PGN_USAGE);
+        return;
+    };
+
+    let text = match std::fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", path, e);
+            return;
+        }
+    };
+
+    let games = game::pgn::reader::read_pgn(&text, true);
+    let mut failed = 0;
+    for (i, game) in games.iter().enumerate() {
+        match game {
+            Ok(game) => println!("Game {}: {} - {} {}, {} moves, last move {}, final position {}",
+                i + 1,
+                game.tag("White").unwrap_or("?"),
+                game.tag("Black").unwrap_or("?"),
+                game.result,
+                game.moves.len(),
+                game.moves.last().map_or("-", |last_move| last_move.san.as_str()),
+                game.positions().last().unwrap().to_fen(),
+            ),
+            Err(e) => {
+                failed += 1;
+                eprintln!("Game {}: {}", i + 1, e);
+            }
+        }
+    }
+    println!("Read {} of {} games from {}", games.len() - failed, games.len(), path);
+}
+
+const BOOK_USAGE:&str = "usage: book <output.bin> <games.pgn>... [--results 1-0,0-1,1/2-1/2] [--min-rating N] [--min-count N] [--max-ply N]";
+
+//builds a polyglot book from the games of pgn files
+pub fn build_book(args:&[String]) {
+
+    game::engine::state_bitboard::bit_boards::populate_rook_moves();
+    game::engine::state_bitboard::bit_boards::populate_bishop_moves();
+
+    let mut filter = game::book::builder::BookFilter::default();
+    let mut paths = Vec::<&String>::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--results" | "--min-rating" | "--min-count" | "--max-ply" => args.next(),
+            _ => {
+                paths.push(arg);
+                continue;
+            }
+        };
+        let parsed = match (arg.as_str(), value) {
+            ("--results", Some(results)) => {
+                filter.results = results.split(',').map(|result| result.to_string()).collect();
+                true
+            }
+            ("--min-rating", Some(rating)) => rating.parse().map(|rating| filter.min_rating = Some(rating)).is_ok(),
+            ("--min-count", Some(count)) => count.parse().map(|count| filter.min_count = count).is_ok(),
+            ("--max-ply", Some(max_ply)) => max_ply.parse().map(|max_ply| filter.max_ply = max_ply).is_ok(),
+            _ => false,
+        };
+        if !parsed {
+            eprintln!("Invalid value for {}\n{}", arg, BOOK_USAGE);
+            return;
+        }
+    }
+    let [output, pgn_paths @ ..] = paths.as_slice() else {
+        eprintln!("{}", BOOK_USAGE);
+        return;
+    };
+    if pgn_paths.is_empty() {
+        eprintln!("{}", BOOK_USAGE);
+        return;
+    }
+
+    let mut builder = game::book::builder::BookBuilder::new(game::book::PolyglotKeys::default(), filter);
+    let mut games_read = 0;
+    for path in pgn_paths {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) => {
+                eprintln!("Failed to read {}: {}", path, e);
+                continue;
+            }
+        };
+        for game in game::pgn::reader::read_pgn(&text, false) {
+            match game {
+                Ok(game) => {
+                    games_read += 1;
+                    builder.add_game(&game);
+                }
+                Err(e) => eprintln!("Skipping game in {}, {}", path, e),
+            }
+        }
+    }
+
+    let data = builder.to_bytes();
+    match std::fs::write(output, &data) {
+        Ok(_) => println!("Wrote {} entries from {} of {} games to {}", data.len() / 16, builder.games_used(), games_read, output),
+        Err(e) => eprintln!("Failed to write {}: {}", output, e),
+    }
+}
+
+//plays through the uci protocol on standard input and output
+pub fn uci() {
+
+    game::engine::state_bitboard::bit_boards::populate_rook_moves();
+    game::engine::state_bitboard::bit_boards::populate_bishop_moves();
+    game::uci::run();
+}
+
+const TUNE_USAGE:&str = "usage: tune <positions.txt> <output.txt> [iterations]";
+
+//tunes the evaluation parameters on a file of positions labelled with results, starting from the parameters of
+//EVAL_PARAMS or the built in ones, and writes the tuned parameters after every iteration
+pub fn tune_eval(args:&[String]) {
+
+    game::engine::state_bitboard::bit_boards::populate_rook_moves();
+    game::engine::state_bitboard::bit_boards::populate_bishop_moves();
+    dotenvy::dotenv().ok();
+
+    let (positions_path, output, iterations) = match args {
+        [positions_path, output] => (positions_path, output, usize::MAX),
+        [positions_path, output, iterations] => match iterations.parse() {
+            Ok(iterations) => (positions_path, output, iterations),
+            Err(_) => {
+                eprintln!("Invalid number of iterations\n{}", TUNE_USAGE);
+                return;
+            }
+        },
+        _ => {
+            eprintln!("{}", TUNE_USAGE);
+            return;
+        }
+    };
+
+    let params = match game::engine::eval::params::EvalParams::from_env() {
+        Ok(params) => params,
+        Err(game::engine::eval::params::ParamsError::NotConfigured(_)) => Default::default(),
+        Err(e) => {
+            eprintln!("Can not tune, {}", e);
+            return;
+        }
+    };
+
+    let text = match std::fs::read_to_string(positions_path) {
+        Ok(text) => text,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", positions_path, e);
+            return;
+        }
+    };
+    let (positions, errors) = game::engine::eval::tuner::read_positions(&text);
+    for (line, e) in &errors {
+        eprintln!("Skipping line {} of {}, {}", line, positions_path, e);
+    }
+    if positions.is_empty() {
+        eprintln!("No positions to tune on in {}", positions_path);
+        return;
+    }
+
+    let position_count = positions.len();
+    let mut tuner = game::engine::eval::tuner::Tuner::new(positions, params);
+    println!("Tuning on {} positions, k {:.3}, error {:.6}", position_count, tuner.k(), tuner.error());
+    for iteration in 1..=iterations {
+        let changed = tuner.iterate();
+        println!("Iteration {}: {} parameters changed, error {:.6}", iteration, changed, tuner.error());
+        let text = format!("# tuned on {} positions, k {:.3}, error {:.6}\n{}", position_count, tuner.k(), tuner.error(), tuner.params());
+        if let Err(e) = std::fs::write(output, text) {
+            eprintln!("Failed to write {}: {}", output, e);
+            return;
+        }
+        if changed == 0 {
+            break;
+        }
+    }
+    println!("Wrote the tuned parameters to {}", output);
+}
+
+const SELFPLAY_USAGE:&str = "usage: selfplay <output.txt> [--games N] [--threads N] [--depth N] [--nodes N] [--random-plies N] [--seed N]";
+
+//plays games of the engine against itself and writes their quiet positions with scores and results for the tuner,
+//with the evaluation parameters of EVAL_PARAMS and the network of NNUE_FILE if they are set
+pub fn self_play(args:&[String]) {
+
+    game::engine::state_bitboard::bit_boards::populate_rook_moves();
+    game::engine::state_bitboard::bit_boards::populate_bishop_moves();
+    dotenvy::dotenv().ok();
+
+    let mut config = game::selfplay::SelfPlayConfig::default();
+    let mut paths = Vec::<&String>::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let value = match arg.as_str() {
+            "--games" | "--threads" | "--depth" | "--nodes" | "--random-plies" | "--seed" => args.next(),
+            _ => {
+                paths.push(arg);
+                continue;
+            }
+        };
+        let parsed = match (arg.as_str(), value) {
+            ("--games", Some(games)) => games.parse().map(|games| config.games = games).is_ok(),
+            ("--threads", Some(threads)) => threads.parse().map(|threads| config.threads = threads).is_ok(),
+            ("--depth", Some(depth)) => depth.parse().map(|depth| config.depth = depth).is_ok(),
+            ("--nodes", Some(nodes)) => nodes.parse().map(|nodes| config.nodes = Some(nodes)).is_ok(),
+            ("--random-plies", Some(plies)) => plies.parse().map(|plies| config.random_plies = plies).is_ok(),
+            ("--seed", Some(seed)) => seed.parse().map(|seed| config.seed = seed).is_ok(),
+            _ => false,
+        };
+        if !parsed {
+            eprintln!("Invalid value for {}\n{}", arg, SELFPLAY_USAGE);
+            return;
+        }
+    }
+    let [output] = paths.as_slice() else {
+        eprintln!("{}", SELFPLAY_USAGE);
+        return;
+    };
+
+    let params = match game::engine::eval::params::EvalParams::from_env() {
+        Ok(params) => params,
+        Err(game::engine::eval::params::ParamsError::NotConfigured(_)) => Default::default(),
+        Err(e) => {
+            eprintln!("Can not play, {}", e);
+            return;
+        }
+    };
+    let network = match game::engine::eval::nnue::Network::from_env() {
+        Ok(network) => Some(std::sync::Arc::new(network)),
+        Err(game::engine::eval::nnue::NnueError::NotConfigured(_)) => None,
+        Err(e) => {
+            eprintln!("Can not play, {}", e);
+            return;
+        }
+    };
+
+    let mut file = match std::fs::File::create(output) {
+        Ok(file) => std::io::BufWriter::new(file),
+        Err(e) => {
+            eprintln!("Failed to create {}: {}", output, e);
+            return;
+        }
+    };
+    println!("Playing {} games on {} threads", config.games, config.threads);
+    match game::selfplay::generate(&config, std::sync::Arc::new(params), network, &mut file) {
+        Ok(stats) => println!("Wrote {} positions from {} games to {}, +{} ={} -{}",
+            stats.positions, stats.games, output, stats.white_wins, stats.draws, stats.black_wins),
+        Err(e) => eprintln!("Failed to write {}: {}", output, e),
+    }
+}
+
+const EVAL_USAGE:&str = "usage: eval <fen>";
+
+//prints every term of the evaluation of a position, with the evaluation parameters of EVAL_PARAMS or the built in ones
+pub fn eval_position(args:&[String]) {
+
+    game::engine::state_bitboard::bit_boards::populate_rook_moves();
+    game::engine::state_bitboard::bit_boards::populate_bishop_moves();
+    dotenvy::dotenv().ok();
+
+    if args.is_empty() {
+        eprintln!("{}", EVAL_USAGE);
+        return;
+    }
+    let fen = args.join(" ");
+
+    let params = match game::engine::eval::params::EvalParams::from_env() {
+        Ok(params) => params,
+        Err(game::engine::eval::params::ParamsError::NotConfigured(_)) => Default::default(),
+        Err(e) => {
+            eprintln!("Can not evaluate, {}", e);
+            return;
+        }
+    };
+    let state = match game::engine::state_bitboard::BitBoardState::from_fen(&fen) {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("Invalid fen {}, {}", fen, e);
+            return;
+        }
+    };
+    let mut pawn_table = game::engine::eval::pawns::PawnTable::default();
+    println!("{}", game::engine::eval::trace(&state, &params, &mut pawn_table));
+}
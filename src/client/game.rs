@@ -1,21 +1,57 @@
 
 use licheszter::models::chat::ChatRoom;
 use std::env;
+use std::sync::Arc;
 use std::time::Duration;
 
 use futures::StreamExt;
-use licheszter::{client::Licheszter, models::game::GameStatus};
+use licheszter::{client::Licheszter, config::openings::LichessOpeningsOptions, models::game::{Color, GameFull, GameStatus, VariantMode}};
 use licheszter::models::board::{BoardState};
 
 pub(crate) mod engine;
-use engine::state_bitboard::{BitBoardState, BoardStateNumbers};
-use engine::board;
+pub(crate) mod book;
+pub(crate) mod pgn;
+pub(crate) mod uci;
+pub(crate) mod selfplay;
+#[cfg(test)]
+mod book_tests;
+#[cfg(test)]
+mod pgn_tests;
+#[cfg(test)]
+mod uci_tests;
+#[cfg(test)]
+mod selfplay_tests;
+use book::{BookError, OpeningBook};
+use pgn::{engine_comment, PgnGame};
+use engine::state_bitboard::BitBoardState;
+use engine::board::{ChessMove, MoveParseError};
+use engine::tablebase::{Tablebase, TablebaseError};
+use engine::eval::params::{EvalParams, ParamsError};
+use engine::eval::nnue::{Network, NnueError};
 
 use crate::client::game::engine::GetMoveResult;
-use crate::client::game::engine::move_string::lan_move;
+use crate::client::game::engine::move_string::{lan_move, uci_move, CastleNotation};
+use crate::client::game::engine::san::{parse_san, san_move, SanError};
 
 pub struct Game{
-    game_id: String
+    game_id: String,
+    resources: GameResources,
+}
+
+//everything the games of the bot share, loaded once when the bot starts
+#[derive(Clone)]
+pub struct GameResources{
+    book: Option<Arc<OpeningBook>>,
+    tablebase: Option<Arc<Tablebase>>,
+    params: Arc<EvalParams>,
+    network: Option<Arc<Network>>,
+}
+
+impl GameResources{
+    //the book, tablebase, evaluation parameters and network configured in the environment
+    pub fn load() -> Self{
+        Self { book: load_book().map(Arc::new), tablebase: load_tablebase(), params: load_eval_params(), network: load_network() }
+    }
 }
 
 const BOT_NAME:&str = "sonkolbot";
@@ -24,16 +60,18 @@ const SEARCH_DEPTH:i64 = 10;
 const MAX_DEPTH:usize = 20;
 const MAX_TIME:Option<u128> = Some(3000);
 const STARTING_POS:&str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+const OPENING_PLIES:usize = 30; //number of moves sent to the opening explorer to name the opening
 /*
 game handler for a specific game
 */
 
 
-fn log_search(move_result: GetMoveResult){
+fn log_search(move_result: GetMoveResult, bb_state:&BitBoardState){
     let chess_move = move_result.chess_move();
     let uci_move = lan_move(*chess_move);
-    println!("Move: {:>1} | eval: {:>6} | depth: {:>1} | max depth: {:>1} |nodes: {:>9}",
+    println!("Move: {:>1} ({}) | eval: {:>6} | depth: {:>1} | max depth: {:>1} |nodes: {:>9}",
         uci_move,
+        san_move(bb_state, *chess_move).unwrap_or_else(|| "no move".to_string()),
         move_result.eval(),
         move_result.depth_reached(),
         move_result.max_depth_reached(),
@@ -41,15 +79,189 @@ fn log_search(move_result: GetMoveResult){
     );
 }
 
+//plays the moves from lichess that have not been applied to bb_state yet, game_history gets the hash of every new position
+//and the moves are recorded in the pgn.
+//returns the last move applied, with the uci string it was parsed from
+//stops at the first move that can not be parsed, the moves before it stay applied
+fn apply_new_moves<'a>(bb_state:&mut BitBoardState, game_history:&mut Vec<u64>, pgn:&mut PgnGame, moves:&'a str) -> Result<Option<(&'a str, ChessMove)>, MoveParseError>{
+    let mut last_move = None;
+    for uci_move in moves.split_whitespace().skip(game_history.len() - 1){
+        let chess_move = ChessMove::from_uci(uci_move, bb_state)?;
+        pgn.push_move(chess_move);
+        *bb_state = bb_state.perform_move(chess_move);
+        game_history.push(bb_state.hash());
+        last_move = Some((uci_move, chess_move));
+    }
+    Ok(last_move)
+}
+
+//pgn with the headers that are known when the game starts
+fn pgn_from_game_full(game:&GameFull, start:BitBoardState) -> PgnGame{
+    let mut pgn = PgnGame::new(start);
+    let date = game.created_at.date();
+    pgn.set_tag("Event", &format!("{} {:?} game", if game.rated {"Rated"} else {"Casual"}, game.speed));
+    pgn.set_tag("Site", &format!("https://lichess.org/{}", game.id));
+    pgn.set_tag("Date", &format!("{}.{:02}.{:02}", date.year(), u8::from(date.month()), date.day()));
+    pgn.set_tag("Round", "-");
+    pgn.set_tag("White", &game.white.name);
+    pgn.set_tag("Black", &game.black.name);
+    pgn.set_tag("WhiteElo", &game.white.rating.to_string());
+    pgn.set_tag("BlackElo", &game.black.rating.to_string());
+    //lichess gives the clock in milliseconds
+    let time_control = match &game.clock {
+        Some(clock) => format!("{}+{}", clock.initial / 1000, clock.increment / 1000),
+        None => "-".to_string(),
+    };
+    pgn.set_tag("TimeControl", &time_control);
+    if game.variant.key != VariantMode::Standard {
+        pgn.set_tag("Variant", &game.variant.name);
+    }
+    pgn
+}
+
+//why the engine can not play a game of the variant from the position, None when it can
+//the move generator only knows standard chess, with the kings castling from e1 and e8
+pub fn unplayable_reason(variant:&VariantMode, initial_fen:Option<&str>) -> Option<String>{
+    if *variant != VariantMode::Standard && *variant != VariantMode::FromPosition {
+        return Some(format!("the {variant:?} variant is not supported"));
+    }
+    match initial_fen {
+        Some(fen) if fen != "startpos" => BitBoardState::from_fen(fen).err().map(|e| format!("can not play from {fen}, {e}")),
+        _ => None,
+    }
+}
+
+//result and termination tags for a finished game, None while the game is still going
+fn game_result(status:&GameStatus, winner:Option<Color>) -> Option<(&'static str, &'static str)>{
+    let termination = match status {
+        GameStatus::Created | GameStatus::Started => return None,
+        GameStatus::Mate | GameStatus::Resign | GameStatus::Stalemate | GameStatus::Draw |
+        GameStatus::InsufficientMaterialClaim | GameStatus::VariantEnd => "Normal",
+        GameStatus::Timeout | GameStatus::OutOfTime => "Time forfeit",
+        GameStatus::Aborted | GameStatus::NoStart => "Abandoned",
+        GameStatus::Cheat => "Rules infraction",
+        GameStatus::UnknownFinish => "Unterminated",
+    };
+    let result = match (status, winner) {
+        (GameStatus::Aborted | GameStatus::NoStart | GameStatus::UnknownFinish, _) => "*",
+        (_, Some(Color::White)) => "1-0",
+        (_, Some(Color::Black)) => "0-1",
+        _ => "1/2-1/2",
+    };
+    Some((result, termination))
+}
+
+//asks the lichess opening explorer for the name of the opening, the deepest named position of the line is used
+async fn lookup_opening(client:&Licheszter, moves:&str) -> Option<(String, String)>{
+    let line:Vec<&str> = moves.split_whitespace().take(OPENING_PLIES).collect();
+    let options = LichessOpeningsOptions::new().play(line).moves(0).top_games(0);
+    match client.openings_lichess(Some(&options)).await {
+        Ok(explorer) => explorer.opening.map(|opening| (opening.eco, opening.name)),
+        Err(e) => {
+            eprintln!("Failed to look up opening: {}", e);
+            None
+        }
+    }
+}
+
+//writes the pgn to PGN_DIR/<game id>.pgn, PGN_DIR defaults to "games"
+fn save_pgn(game_id:&str, pgn:&PgnGame){
+    let dir = env::var("PGN_DIR").unwrap_or_else(|_| "games".to_string());
+    let path = std::path::Path::new(&dir).join(format!("{game_id}.pgn"));
+    let result = std::fs::create_dir_all(&dir).and_then(|_| std::fs::write(&path, pgn.to_pgn()));
+    match result {
+        Ok(_) => println!("Saved game to {}", path.display()),
+        Err(e) => eprintln!("Failed to save game to {}: {}", path.display(), e),
+    }
+}
+
+//the opening book configured in the environment, None if there is none or it can not be loaded
+fn load_book() -> Option<OpeningBook>{
+    dotenvy::dotenv().ok();
+    match OpeningBook::from_env() {
+        Ok(book) => {
+            println!("Loaded opening book with {} entries", book.num_entries());
+            Some(book)
+        }
+        Err(BookError::NotConfigured("BOOK_FILE")) => None,
+        Err(e) => {
+            eprintln!("Playing without opening book, {}", e);
+            None
+        }
+    }
+}
+
+//the syzygy tablebase configured in the environment, None if there is none or it can not be opened
+fn load_tablebase() -> Option<Arc<Tablebase>>{
+    dotenvy::dotenv().ok();
+    match Tablebase::from_env() {
+        Ok(tablebase) => {
+            println!("Found {} tablebase tables, up to {} pieces", tablebase.num_tables(), tablebase.max_pieces());
+            Some(Arc::new(tablebase))
+        }
+        Err(TablebaseError::NotConfigured(_)) => None,
+        Err(e) => {
+            eprintln!("Playing without tablebase, {}", e);
+            None
+        }
+    }
+}
+
+//the evaluation parameters configured in the environment, the built in parameters if there are none or they can not be read
+fn load_eval_params() -> Arc<EvalParams>{
+    dotenvy::dotenv().ok();
+    match EvalParams::from_env() {
+        Ok(params) => {
+            println!("Loaded evaluation parameters");
+            Arc::new(params)
+        }
+        Err(ParamsError::NotConfigured(_)) => Arc::new(EvalParams::default()),
+        Err(e) => {
+            eprintln!("Playing with the built in evaluation parameters, {}", e);
+            Arc::new(EvalParams::default())
+        }
+    }
+}
+
+//the network configured in the environment, the classical evaluation is used if there is none or it can not be read
+fn load_network() -> Option<Arc<Network>>{
+    dotenvy::dotenv().ok();
+    match Network::from_env() {
+        Ok(network) => {
+            println!("Loaded network with {} hidden neurons", network.hidden_size());
+            Some(Arc::new(network))
+        }
+        Err(NnueError::NotConfigured(_)) => None,
+        Err(e) => {
+            eprintln!("Playing with the classical evaluation, {}", e);
+            None
+        }
+    }
+}
+
 impl Game{
-    pub fn new(game_id: String) -> Self {
-        Self { game_id }
+    pub fn new(game_id: String, resources:&GameResources) -> Self {
+        Self { game_id, resources: resources.clone() }
     }
 
-    async fn play_move(&self, client:&Licheszter, bot: &mut engine::Engine, bb_state:&BitBoardState, game_history:&mut Vec<BoardStateNumbers>) {
-        let search_result = bot.get_move_bb(*bb_state, game_history);
-        let uci_move = lan_move(*search_result.chess_move());
-        log_search(search_result);
+    async fn play_move(&self, client:&Licheszter, bot: &mut engine::Engine, bb_state:&BitBoardState, game_history:&[u64], pgn:&mut PgnGame) {
+        let ply = game_history.len() - 1;
+        let chess_move = match self.resources.book.as_ref().and_then(|book| book.pick_move(bb_state, ply)) {
+            Some(book_move) => {
+                println!("Book move: {} ({})", lan_move(book_move), san_move(bb_state, book_move).unwrap_or_default());
+                pgn.annotate_next(book_move, "book".to_string());
+                book_move
+            }
+            None => {
+                println!("Searching position: {}", bb_state.to_fen());
+                let search_result = bot.get_move_bb(*bb_state, game_history);
+                let chess_move = *search_result.chess_move();
+                pgn.annotate_next(chess_move, engine_comment(&search_result));
+                log_search(search_result, bb_state);
+                chess_move
+            }
+        };
+        let uci_move = uci_move(chess_move, CastleNotation::Standard);
         
         for attempt in 0..3 {
             match client.bot_play_move(&self.game_id, &uci_move, false).await {
@@ -81,83 +293,94 @@ impl Game{
         let mut game_events = client.bot_game_connect(&self.game_id).await.unwrap();
 
         //assuming that previous line indicates that the game has started
-        let mut bot = engine::Engine::new(SEARCH_DEPTH, MAX_DEPTH, TABLE_SIZE, MAX_TIME);
-        let mut board_state = board::BoardState::new_from_fen(STARTING_POS);
-        let mut game_history = Vec::<BoardStateNumbers>::new();
+        let mut bot = engine::Engine::new(SEARCH_DEPTH, MAX_DEPTH, TABLE_SIZE, MAX_TIME, self.resources.params.clone());
+        bot.set_tablebase(self.resources.tablebase.clone());
+        bot.set_network(self.resources.network.clone());
+        let mut bb_state = BitBoardState::from_fen(STARTING_POS).unwrap();
+        let mut game_history = vec![bb_state.hash()];
+        let mut pgn = PgnGame::new(bb_state);
 
-        let mut bot_color = 1;
+        let mut bot_is_white = true;
 
         while let Some(result) = game_events.next().await {
             match result {
                 Ok(event)=>{
                     match event {
                         BoardState::GameState ( game_state ) => {
-                            match game_state.status {
-                                GameStatus::Mate | GameStatus::Resign | GameStatus::Timeout | GameStatus::Draw=> {
-                                    println!("Game ended with status: {:?}", game_state.status);
-                                    return;
-                                },
-                                _ => {}
-                            }
-
-                            //find the last move played
-                            let moves = game_state.moves.split_whitespace().collect::<Vec<&str>>();
-                            let last_move = *moves.last().unwrap();
-                            let chess_move = board::ChessMove::from_uci(last_move, &board_state);
+                            let last_move = apply_new_moves(&mut bb_state, &mut game_history, &mut pgn, &game_state.moves);
 
-                            //update local board
-                            board_state.perform_move(chess_move);
-
-                            //copy BoardState to BitBoardState and append to history
-                            let mut bb_state = BitBoardState::new();
-                            bb_state.setup_state(&board_state);
-                            game_history.push(bb_state.board_state_numbers());
+                            if let Some((result, termination)) = game_result(&game_state.status, game_state.winner) {
+                                println!("Game ended with status: {:?}", game_state.status);
+                                pgn.set_tag("Result", result);
+                                pgn.set_tag("Termination", termination);
+                                if let Some((eco, opening)) = lookup_opening(&client, &game_state.moves).await {
+                                    pgn.set_tag("ECO", &eco);
+                                    pgn.set_tag("Opening", &opening);
+                                }
+                                save_pgn(&self.game_id, &pgn);
+                                return;
+                            }
 
+                            let last_move = match last_move {
+                                Ok(last_move) => last_move,
+                                Err(e) => {
+                                    eprintln!("Could not follow the game, {e}. Position: {}", bb_state.to_fen());
+                                    continue;
+                                }
+                            };
 
-                            if moves.len() % 2 == bot_color {
+                            if bb_state.white_to_move() == bot_is_white {
                                 //it is bots turn, last move was opponents
-                                println!("Opponent played move: {}, interpreted as {}, raw = {:x}", last_move, lan_move(chess_move), chess_move.move_data());
-                                self.play_move(&client, &mut bot, &bb_state, &mut game_history).await;
-                            }
-                            else{
+                                if let Some((uci_move, chess_move)) = last_move {
+                                    println!("Opponent played move: {}, interpreted as {}, raw = {:x}", uci_move, lan_move(chess_move), chess_move.move_data());
+                                    println!("Position after move: {}", bb_state.to_fen());
+                                }
+                                self.play_move(&client, &mut bot, &bb_state, &game_history, &mut pgn).await;
                             }
                         },
                         BoardState::GameFull(game_state) => {
+                            let initial_fen = if game_state.initial_fen == "startpos" {STARTING_POS} else {&game_state.initial_fen};
+                            let start = match unplayable_reason(&game_state.variant.key, Some(initial_fen)) {
+                                None => BitBoardState::from_fen(initial_fen).map_err(|e| e.to_string()),
+                                Some(reason) => Err(reason),
+                            };
+                            bb_state = match start {
+                                Ok(start) => start,
+                                Err(reason) => {
+                                    eprintln!("Leaving the game, {reason}");
+                                    //aborting only works before the first moves, resigning after
+                                    if client.bot_game_abort(&self.game_id).await.is_err() {
+                                        client.bot_game_resign(&self.game_id).await.ok();
+                                    }
+                                    return;
+                                }
+                            };
+                            game_history = vec![bb_state.hash()];
+                            pgn = pgn_from_game_full(&game_state, bb_state);
+                            bot_is_white = game_state.white.name == BOT_NAME;
 
-                            let mut bb_state = BitBoardState::new();
-                            bb_state.setup_state(&board_state);
-
-                            game_history.push(bb_state.board_state_numbers());
-
-                            if game_state.white.name == BOT_NAME {
-                                bot_color = 0;
-                                self.play_move(&client, &mut bot, &bb_state, &mut game_history).await;
+                            if let Err(e) = apply_new_moves(&mut bb_state, &mut game_history, &mut pgn, &game_state.state.moves) {
+                                eprintln!("Could not follow the game, {e}. Position: {}", bb_state.to_fen());
+                                continue;
                             }
-                            else if !game_state.state.moves.is_empty(){
-
-                                // !DUPLICATE CODE DISGUSTING
-                                //find the last move played
-                                let moves = game_state.state.moves.split_whitespace().collect::<Vec<&str>>();
-                                let last_move = *moves.last().unwrap();
-                                let chess_move = board::ChessMove::from_uci(last_move, &board_state);
-
-                                //update local board
-                                board_state.perform_move(chess_move);
-
-                                //copy BoardState to BitBoardState and append to history
-                                let mut bb_state = BitBoardState::new();
-                                bb_state.setup_state(&board_state);
-                                game_history.push(bb_state.board_state_numbers());
-                                self.play_move(&client, &mut bot, &bb_state, &mut game_history).await;
+
+                            if bb_state.white_to_move() == bot_is_white {
+                                self.play_move(&client, &mut bot, &bb_state, &game_history, &mut pgn).await;
                             }
                         },
                         BoardState::ChatLine(chat)=>{
                             if chat.username == BOT_NAME {
                                 continue;
                             }
-                            
 
-                            client.bot_chat_write(&self.game_id, ChatRoom::Player, &chat.text).await.unwrap();
+                            //messages that look like a move are answered with how the bot reads them in the current position
+                            let reply = match parse_san(&chat.text, &bb_state) {
+                                Ok(chess_move) => format!("{} ({})", san_move(&bb_state, chess_move).unwrap_or_default(), lan_move(chess_move)),
+                                Err(SanError::Empty | SanError::InvalidSyntax(_)) => chat.text,
+                                Err(e) => e.to_string(),
+                            };
+
+                            client.bot_chat_write(&self.game_id, ChatRoom::Player, &reply).await.unwrap();
                         }
                         _ => {}
                     }
@@ -169,6 +392,9 @@ impl Game{
             }
         }
 
+        //the stream ended without the game finishing, keep what was played
+        save_pgn(&self.game_id, &pgn);
+
 
     }
 }
\ No newline at end of file
@@ -0,0 +1,215 @@
+use std::{env, fmt};
+
+use super::engine::board::{ChessMove, CASTLE_SQUARES, PROMOTE_TO_QUEEN};
+use super::engine::move_string::string_square;
+use super::engine::state_bitboard::{BitBoardState, BLACK, WHITE};
+
+pub mod builder;
+#[cfg(test)]
+mod builder_tests;
+pub mod random64;
+
+/*
+polyglot opening book, the bot plays moves from the book until it is out of book or the game is deeper than the
+maximum book depth, after that the moves are searched as usual.
+positions are looked up with the standard polyglot zobrist keys, the Random64 array in random64.rs.
+
+configured with the environment or the .env file:
+BOOK_FILE      path of the .bin book, no book is used if it is not set
+BOOK_SELECTION "weighted" picks a random move by weight, "best" the move with the highest weight, weighted by default
+BOOK_MAX_PLY   number of plies of the game the book is used for, DEFAULT_MAX_PLY by default
+*/
+
+const PIECE_KEYS_OFFSET:usize = 0;
+const CASTLE_KEYS_OFFSET:usize = 768;
+const EN_PASSANT_KEYS_OFFSET:usize = 772;
+const WHITE_TO_MOVE_KEY:usize = 780;
+
+const ENTRY_SIZE:usize = 16;
+const DEFAULT_MAX_PLY:usize = 20;
+
+#[derive(Debug)]
+pub enum BookError{
+    Io(String, std::io::Error),
+    InvalidBook(String),
+    NotConfigured(&'static str),
+}
+
+impl fmt::Display for BookError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::Io(path, e) => write!(f, "could not read {path}: {e}"),
+            BookError::InvalidBook(reason) => write!(f, "invalid book, {reason}"),
+            BookError::NotConfigured(variable) => write!(f, "{variable} is not set"),
+        }
+    }
+}
+
+impl std::error::Error for BookError{}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BookSelection{
+    Weighted,
+    Best,
+}
+
+pub struct PolyglotKeys{
+    keys: &'static [u64; 781],
+}
+
+impl Default for PolyglotKeys{
+    fn default() -> Self {
+        Self { keys: &random64::RANDOM64 }
+    }
+}
+
+impl PolyglotKeys{
+    //the key of the position in polyglot books
+    pub fn key(&self, state:&BitBoardState) -> u64{
+        let mut key = 0;
+        let piece_bb = state.piece_bb();
+        for color in [BLACK, WHITE]{
+            for (piece, &bb) in piece_bb[color].iter().enumerate(){
+                let mut pieces = bb;
+                while pieces != 0 {
+                    let square = pieces.trailing_zeros() as usize;
+                    pieces &= pieces - 1;
+                    //polyglot orders the pieces black pawn, white pawn, black knight, ...
+                    key ^= self.keys[PIECE_KEYS_OFFSET + 64*(2*piece + color) + square];
+                }
+            }
+        }
+        for (i, right) in state.castle_rights().into_iter().enumerate(){
+            if right {
+                key ^= self.keys[CASTLE_KEYS_OFFSET + i];
+            }
+        }
+        if let Some(square) = state.en_passant_capture_square() {
+            key ^= self.keys[EN_PASSANT_KEYS_OFFSET + square%8];
+        }
+        if state.white_to_move() {
+            key ^= self.keys[WHITE_TO_MOVE_KEY];
+        }
+        key
+    }
+}
+
+struct BookEntry{
+    key: u64,
+    raw_move: u16,
+    weight: u16,
+}
+
+pub struct OpeningBook{
+    keys: PolyglotKeys,
+    entries: Vec<BookEntry>, //sorted by key
+    selection: BookSelection,
+    max_ply: usize,
+}
+
+impl OpeningBook{
+    //data is the content of a .bin book, entries of 16 bytes with big endian key, move, weight and learn fields
+    pub fn new(keys:PolyglotKeys, data:&[u8], selection:BookSelection, max_ply:usize) -> Result<Self, BookError>{
+        if !data.len().is_multiple_of(ENTRY_SIZE) {
+            return Err(BookError::InvalidBook(format!("size of {} bytes is not a multiple of {ENTRY_SIZE}", data.len())));
+        }
+        let mut entries:Vec<BookEntry> = data.chunks_exact(ENTRY_SIZE)
+            .map(|entry| BookEntry {
+                key: u64::from_be_bytes(entry[0..8].try_into().unwrap()),
+                raw_move: u16::from_be_bytes(entry[8..10].try_into().unwrap()),
+                weight: u16::from_be_bytes(entry[10..12].try_into().unwrap()),
+            })
+            .collect();
+        //books are sorted already, this only costs time for books that are not
+        if !entries.is_sorted_by_key(|entry| entry.key) {
+            entries.sort_by_key(|entry| entry.key);
+        }
+        Ok(Self {
+            keys,
+            entries,
+            selection,
+            max_ply,
+        })
+    }
+
+    //loads the book configured with the environment, see the top of the file
+    pub fn from_env() -> Result<Self, BookError>{
+        let book_path = env::var("BOOK_FILE").map_err(|_| BookError::NotConfigured("BOOK_FILE"))?;
+        let selection = match env::var("BOOK_SELECTION").as_deref() {
+            Ok("best") => BookSelection::Best,
+            _ => BookSelection::Weighted,
+        };
+        let max_ply = env::var("BOOK_MAX_PLY").ok().and_then(|max_ply| max_ply.parse().ok()).unwrap_or(DEFAULT_MAX_PLY);
+
+        let data = std::fs::read(&book_path).map_err(|e| BookError::Io(book_path, e))?;
+        Self::new(PolyglotKeys::default(), &data, selection, max_ply)
+    }
+
+    pub fn num_entries(&self) -> usize{
+        self.entries.len()
+    }
+
+    //the legal book moves of the position with their weights, moves that are not legal are left out
+    pub fn moves(&self, state:&BitBoardState) -> Vec<(ChessMove, u16)>{
+        let key = self.keys.key(state);
+        let first = self.entries.partition_point(|entry| entry.key < key);
+        self.entries[first..].iter()
+            .take_while(|entry| entry.key == key)
+            .filter_map(|entry| decode_move(entry.raw_move, state).map(|chess_move| (chess_move, entry.weight)))
+            .collect()
+    }
+
+    //the book move to play after ply plies of the game, None when out of book
+    pub fn pick_move(&self, state:&BitBoardState, ply:usize) -> Option<ChessMove>{
+        if ply >= self.max_ply {
+            return None;
+        }
+        //moves with a weight of 0 are in the book, but should not be played
+        let moves:Vec<(ChessMove, u16)> = self.moves(state).into_iter().filter(|(_, weight)| *weight > 0).collect();
+        match self.selection {
+            BookSelection::Best => moves.iter().max_by_key(|(_, weight)| *weight).map(|(chess_move, _)| *chess_move),
+            BookSelection::Weighted => {
+                let total:u32 = moves.iter().map(|(_, weight)| *weight as u32).sum();
+                if total == 0 {
+                    return None;
+                }
+                let mut pick = rand::random_range(0..total);
+                for (chess_move, weight) in moves{
+                    if pick < weight as u32 {
+                        return Some(chess_move);
+                    }
+                    pick -= weight as u32;
+                }
+                None
+            }
+        }
+    }
+}
+
+//inverse of decode_move
+fn encode_move(chess_move:ChessMove) -> u16{
+    let flag = chess_move.flag();
+    let (origin, target) = match CASTLE_SQUARES.iter().find(|castle| castle.0 == flag) {
+        Some(&(_, king_origin, _, rook_square)) => (king_origin, rook_square),
+        None => (chess_move.origin(), chess_move.target()),
+    };
+    let promotion = if flag <= PROMOTE_TO_QUEEN {flag as u16 + 1} else {0};
+    promotion << 12 | (origin as u16) << 6 | target as u16
+}
+
+//polyglot moves store the target file and row, origin file and row and promotion piece in groups of 3 bits
+//castles are stored as the king capturing its own rook, which from_uci understands
+fn decode_move(raw_move:u16, state:&BitBoardState) -> Option<ChessMove>{
+    let target = (raw_move & 0x3F) as u8;
+    let origin = ((raw_move >> 6) & 0x3F) as u8;
+    let promotion = match (raw_move >> 12) & 0x7 {
+        0 => "",
+        1 => "n",
+        2 => "b",
+        3 => "r",
+        4 => "q",
+        _ => return None,
+    };
+    let uci_move = format!("{}{}{}", string_square(origin), string_square(target), promotion);
+    ChessMove::from_uci(&uci_move, state).ok()
+}
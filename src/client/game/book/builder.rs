@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+
+use crate::client::game::book::{encode_move, PolyglotKeys, ENTRY_SIZE};
+use crate::client::game::pgn::reader::ParsedGame;
+
+/*
+builds a polyglot book from games, every move of the first max_ply plies of a game is counted for the position it
+was played in, with the score it got: 2 for a win, 1 for a draw and 0 for a loss of the side that played it.
+the weight of a book move is its total score, so moves that did well are picked more often.
+*/
+
+pub struct BookFilter{
+    pub results: Vec<String>, //results of the games that are used, like "1-0"
+    pub min_rating: Option<u16>, //both players need a WhiteElo and BlackElo of at least this
+    pub min_count: u32, //moves played less often than this are left out of the book
+    pub max_ply: usize,
+}
+
+impl Default for BookFilter{
+    //every finished game, up to move 15
+    fn default() -> Self{
+        Self {
+            results: vec!["1-0".to_string(), "0-1".to_string(), "1/2-1/2".to_string()],
+            min_rating: None,
+            min_count: 1,
+            max_ply: 30,
+        }
+    }
+}
+
+impl BookFilter{
+    fn accepts(&self, game:&ParsedGame) -> bool{
+        if !self.results.contains(&game.result) {
+            return false;
+        }
+        let Some(min_rating) = self.min_rating else {
+            return true;
+        };
+        ["WhiteElo", "BlackElo"].iter().all(|tag| {
+            game.tag(tag).and_then(|rating| rating.parse::<u16>().ok()).is_some_and(|rating| rating >= min_rating)
+        })
+    }
+}
+
+#[derive(Default)]
+struct MoveStats{
+    count: u32,
+    score: u32,
+}
+
+pub struct BookBuilder{
+    keys: PolyglotKeys,
+    filter: BookFilter,
+    moves: HashMap<(u64, u16), MoveStats>, //polyglot key of the position and move to how the move did
+    games_used: usize,
+}
+
+impl BookBuilder{
+    pub fn new(keys:PolyglotKeys, filter:BookFilter) -> Self{
+        Self {
+            keys,
+            filter,
+            moves: HashMap::new(),
+            games_used: 0,
+        }
+    }
+
+    //counts the moves of the game, returns false if the filter leaves the game out
+    pub fn add_game(&mut self, game:&ParsedGame) -> bool{
+        if !self.filter.accepts(game) {
+            return false;
+        }
+        //score of the result for white, black gets 2 minus it
+        let white_score = match game.result.as_str() {
+            "1-0" => 2,
+            "0-1" => 0,
+            _ => 1,
+        };
+        let positions = game.positions();
+        for (parsed_move, position) in game.moves.iter().zip(positions.iter()).take(self.filter.max_ply){
+            let key = self.keys.key(position);
+            let stats = self.moves.entry((key, encode_move(parsed_move.chess_move))).or_default();
+            stats.count += 1;
+            stats.score += if position.white_to_move() {white_score} else {2 - white_score};
+        }
+        self.games_used += 1;
+        true
+    }
+
+    pub fn games_used(&self) -> usize{
+        self.games_used
+    }
+
+    //the book as the content of a .bin file, entries sorted by key and then by weight, best first
+    pub fn to_bytes(&self) -> Vec<u8>{
+        let mut entries:Vec<(u64, u16, u32)> = self.moves.iter()
+            .filter(|(_, stats)| stats.count >= self.filter.min_count)
+            .map(|(&(key, raw_move), stats)| (key, raw_move, stats.score))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(&b.0).then(b.2.cmp(&a.2)).then(a.1.cmp(&b.1)));
+
+        let mut data = Vec::<u8>::with_capacity(entries.len()*ENTRY_SIZE);
+        for position in entries.chunk_by(|a, b| a.0 == b.0){
+            //weights only have 16 bits, the moves of a position with a higher score are scaled down together
+            let max_score = position[0].2 as u64;
+            for &(key, raw_move, score) in position{
+                let weight = if max_score > u16::MAX as u64 {score as u64 * u16::MAX as u64 / max_score} else {score as u64};
+                data.extend(key.to_be_bytes());
+                data.extend(raw_move.to_be_bytes());
+                data.extend((weight as u16).to_be_bytes());
+                data.extend(0u32.to_be_bytes()); //learn
+            }
+        }
+        data
+    }
+}
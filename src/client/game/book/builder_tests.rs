@@ -0,0 +1,89 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::book::builder::{BookBuilder, BookFilter};
+    use crate::client::game::book::{BookSelection, OpeningBook, PolyglotKeys};
+    use crate::client::game::engine::board::W_CASTLE_KING;
+    use crate::client::game::engine::move_string::lan_move;
+    use crate::client::game::engine::state_bitboard::BitBoardState;
+    use crate::client::game::pgn::reader::{read_pgn, ParsedGame};
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    const STARTING_POS:&str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    const GAMES:&str = concat!(
+        "[WhiteElo \"2100\"]\n[BlackElo \"1900\"]\n[Result \"1-0\"]\n\n",
+        "1. e4 e5 2. Nf3 Nc6 3. Bc4 Nf6 4. O-O Bc5 1-0\n\n",
+        "[WhiteElo \"2200\"]\n[BlackElo \"2000\"]\n[Result \"1-0\"]\n\n",
+        "1. e4 c5 2. Nf3 1-0\n\n",
+        "[WhiteElo \"1500\"]\n[BlackElo \"2300\"]\n[Result \"1/2-1/2\"]\n\n",
+        "1. d4 d5 1/2-1/2\n\n",
+        "[WhiteElo \"2400\"]\n[BlackElo \"2400\"]\n[Result \"0-1\"]\n\n",
+        "1. e4 e5 2. Nf3 Nc6 0-1\n\n",
+        "[Result \"*\"]\n\n",
+        "1. a4 *\n",
+    );
+
+    fn games() -> Vec<ParsedGame>{
+        read_pgn(GAMES, false).into_iter().map(|game| game.unwrap()).collect()
+    }
+
+    fn build(filter:BookFilter) -> OpeningBook{
+        let mut builder = BookBuilder::new(PolyglotKeys::default(), filter);
+        for game in games(){
+            builder.add_game(&game);
+        }
+        OpeningBook::new(PolyglotKeys::default(), &builder.to_bytes(), BookSelection::Best, 100).unwrap()
+    }
+
+    fn book_moves(book:&OpeningBook, uci_moves:&str) -> Vec<(String, u16)>{
+        let mut state = BitBoardState::from_fen(STARTING_POS).unwrap();
+        for uci_move in uci_moves.split_whitespace(){
+            state = state.perform_move(crate::client::game::engine::board::ChessMove::from_uci(uci_move, &state).unwrap());
+        }
+        book.moves(&state).iter().map(|(chess_move, weight)| (lan_move(*chess_move), *weight)).collect()
+    }
+
+    fn moves(expected:&[(&str, u16)]) -> Vec<(String, u16)>{
+        expected.iter().map(|(uci_move, weight)| (uci_move.to_string(), *weight)).collect()
+    }
+
+    #[test]
+    fn weights_are_scores(){
+        setup_sliding_magics();
+        let mut builder = BookBuilder::new(PolyglotKeys::default(), BookFilter::default());
+        let used:Vec<bool> = games().iter().map(|game| builder.add_game(game)).collect();
+        assert_eq!(used, [true, true, true, true, false]);
+        assert_eq!(builder.games_used(), 4);
+
+        let book = build(BookFilter::default());
+        //e4 won twice and lost once, d4 drew
+        assert_eq!(book_moves(&book, ""), moves(&[("e2e4", 4), ("d2d4", 1)]));
+        assert_eq!(book_moves(&book, "e2e4"), moves(&[("e7e5", 2), ("c7c5", 0)]));
+        assert_eq!(book_moves(&book, "a2a4"), moves(&[]));
+
+        let castle = book.moves(&BitBoardState::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p3/2B1P3/5N2/PPPP1PPP/RNBQK2R w KQkq - 4 4").unwrap());
+        assert_eq!(castle.len(), 1);
+        assert_eq!(castle[0].0.flag(), W_CASTLE_KING);
+    }
+
+    #[test]
+    fn filters(){
+        setup_sliding_magics();
+        let decisive = build(BookFilter {results: vec!["1-0".to_string(), "0-1".to_string()], ..BookFilter::default()});
+        assert_eq!(book_moves(&decisive, ""), moves(&[("e2e4", 4)]));
+
+        let rated = build(BookFilter {min_rating: Some(1900), ..BookFilter::default()});
+        assert_eq!(book_moves(&rated, ""), moves(&[("e2e4", 4)]));
+        assert_eq!(book_moves(&rated, "e2e4"), moves(&[("e7e5", 2), ("c7c5", 0)]));
+
+        let common = build(BookFilter {min_count: 2, ..BookFilter::default()});
+        assert_eq!(book_moves(&common, ""), moves(&[("e2e4", 4)]));
+        assert_eq!(book_moves(&common, "e2e4"), moves(&[("e7e5", 2)]));
+        assert_eq!(book_moves(&common, "e2e4 e7e5 g1f3 b8c6 f1c4"), moves(&[]));
+
+        let short = build(BookFilter {max_ply: 1, ..BookFilter::default()});
+        assert_eq!(book_moves(&short, ""), moves(&[("e2e4", 4), ("d2d4", 1)]));
+        assert_eq!(book_moves(&short, "e2e4"), moves(&[]));
+    }
+}
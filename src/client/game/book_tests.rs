@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::book::random64::RANDOM64;
+    use crate::client::game::book::{BookError, BookSelection, OpeningBook, PolyglotKeys};
+    use crate::client::game::engine::board::{ChessMove, PROMOTE_TO_QUEEN, W_CASTLE_KING};
+    use crate::client::game::engine::state_bitboard::BitBoardState;
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    const STARTING_POS:&str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    //keys published with the polyglot book format, for the positions reached by the moves from the starting position
+    const KNOWN_KEYS:[(&str, u64); 9] = [
+        ("", 0x463b96181691fc9c),
+        ("e2e4", 0x823c9b50fd114196),
+        ("e2e4 d7d5", 0x0756b94461c50fb0),
+        ("e2e4 d7d5 e4e5", 0x662fafb965db29d4),
+        ("e2e4 d7d5 e4e5 f7f5", 0x22a48b5a8e47ff78),
+        ("e2e4 d7d5 e4e5 f7f5 e1e2", 0x652a607ca3f242c1),
+        ("e2e4 d7d5 e4e5 f7f5 e1e2 e8f7", 0x00fdd303c946bdd9),
+        ("a2a4 b7b5 h2h4 b5b4 c2c4", 0x3c8123ea7b067637),
+        ("a2a4 b7b5 h2h4 b5b4 c2c4 b4c3 a1a2", 0x5c3f9b829b279560),
+    ];
+
+    fn play(fen:&str, uci_moves:&str) -> BitBoardState{
+        let mut state = BitBoardState::from_fen(fen).unwrap();
+        for uci_move in uci_moves.split_whitespace(){
+            state = state.perform_move(ChessMove::from_uci(uci_move, &state).unwrap());
+        }
+        state
+    }
+
+    //polyglot encoding of a move given as origin, target and promotion index
+    fn entry(keys:&PolyglotKeys, state:&BitBoardState, origin:u16, target:u16, promotion:u16, weight:u16) -> Vec<u8>{
+        let raw_move = promotion << 12 | origin << 6 | target;
+        let mut entry = keys.key(state).to_be_bytes().to_vec();
+        entry.extend(raw_move.to_be_bytes());
+        entry.extend(weight.to_be_bytes());
+        entry.extend(0u32.to_be_bytes());
+        entry
+    }
+
+    #[test]
+    fn known_keys(){
+        setup_sliding_magics();
+        let keys = PolyglotKeys::default();
+        for (moves, expected) in KNOWN_KEYS{
+            assert_eq!(keys.key(&play(STARTING_POS, moves)), expected, "{moves}");
+        }
+    }
+
+    #[test]
+    fn keys(){
+        setup_sliding_magics();
+        let table = RANDOM64;
+        let keys = PolyglotKeys::default();
+
+        let start = play(STARTING_POS, "");
+        let after_e4 = play(STARTING_POS, "e2e4");
+        //white pawns are the second piece kind, no black pawn can capture on e3 so the en passant square is not hashed
+        assert_eq!(keys.key(&after_e4), keys.key(&start) ^ table[64 + 12] ^ table[64 + 28] ^ table[780]);
+        assert_eq!(keys.key(&play(STARTING_POS, "g1f3 g8f6 b1c3 b8c6")), keys.key(&play(STARTING_POS, "b1c3 b8c6 g1f3 g8f6")));
+
+        let en_passant = play(STARTING_POS, "e2e4 a7a6 e4e5 d7d5");
+        let no_en_passant = play(STARTING_POS, "e2e4 d7d6 e4e5 d6d5");
+        //black pawns are the first piece kind, the pawn on a6 instead of a7 makes up the other difference
+        assert_eq!(keys.key(&en_passant), keys.key(&no_en_passant) ^ table[772 + 3] ^ table[40] ^ table[48]);
+
+        let all_rights = play("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1", "");
+        let queen_side = play("r3k2r/8/8/8/8/8/8/R3K2R w Qq - 0 1", "");
+        assert_eq!(keys.key(&all_rights), keys.key(&queen_side) ^ table[768] ^ table[770]);
+    }
+
+    #[test]
+    fn book_moves(){
+        setup_sliding_magics();
+        let keys = PolyglotKeys::default();
+        let start = play(STARTING_POS, "");
+        let italian = play(STARTING_POS, "e2e4 e7e5 g1f3 b8c6 f1c4 g8f6");
+        let promotion = play("8/P7/8/8/8/8/k7/4K3 w - - 0 1", "");
+
+        let mut data = Vec::<u8>::new();
+        data.extend(entry(&keys, &italian, 4, 7, 0, 5)); //castles as the king taking the rook
+        data.extend(entry(&keys, &start, 12, 28, 0, 3));
+        data.extend(entry(&keys, &start, 11, 27, 0, 1));
+        data.extend(entry(&keys, &start, 6, 21, 0, 0));
+        data.extend(entry(&keys, &start, 12, 36, 0, 100)); //illegal, e2e5
+        data.extend(entry(&keys, &promotion, 48, 56, 4, 1));
+
+        let book = OpeningBook::new(keys, &data, BookSelection::Best, 4).unwrap();
+        assert_eq!(book.num_entries(), 6);
+        let start_moves:Vec<(String, u16)> = book.moves(&start).iter()
+            .map(|(chess_move, weight)| (crate::client::game::engine::move_string::lan_move(*chess_move), *weight))
+            .collect();
+        assert_eq!(start_moves, [("e2e4".to_string(), 3), ("d2d4".to_string(), 1), ("g1f3".to_string(), 0)]);
+        assert_eq!(book.pick_move(&start, 0).map(|chess_move| chess_move.target()), Some(28));
+        assert_eq!(book.pick_move(&start, 4), None);
+        assert_eq!(book.pick_move(&play(STARTING_POS, "a2a3"), 1), None);
+        assert_eq!(book.pick_move(&italian, 3).map(|chess_move| chess_move.flag()), Some(W_CASTLE_KING));
+        assert_eq!(book.pick_move(&promotion, 0).map(|chess_move| chess_move.flag()), Some(PROMOTE_TO_QUEEN));
+    }
+
+    #[test]
+    fn weighted_selection(){
+        setup_sliding_magics();
+        let keys = PolyglotKeys::default();
+        let start = play(STARTING_POS, "");
+        let mut data = Vec::<u8>::new();
+        data.extend(entry(&keys, &start, 12, 28, 0, 3));
+        data.extend(entry(&keys, &start, 11, 27, 0, 1));
+        data.extend(entry(&keys, &start, 6, 21, 0, 0));
+        let book = OpeningBook::new(keys, &data, BookSelection::Weighted, 20).unwrap();
+
+        let mut counts = [0; 64];
+        for _ in 0..400{
+            counts[book.pick_move(&start, 0).unwrap().target() as usize] += 1;
+        }
+        //the weights are 3 to 1, the move with weight 0 is never played
+        assert_eq!(counts[21], 0);
+        assert_eq!(counts[27] + counts[28], 400);
+        assert!(counts[28] > 2*counts[27] && counts[27] > 40);
+    }
+
+    #[test]
+    fn invalid_book(){
+        let keys = PolyglotKeys::default();
+        assert!(matches!(OpeningBook::new(keys, &[0; 20], BookSelection::Best, 20), Err(BookError::InvalidBook(_))));
+    }
+}
@@ -3,25 +3,50 @@
 pub(crate) mod board;
 pub(crate) mod state_bitboard;
 pub(crate) mod move_string;
+pub(crate) mod fen;
+pub(crate) mod san;
+pub(crate) mod score;
+pub(crate) mod tablebase;
 mod search;
-mod eval;
+pub(crate) mod eval;
 
 use std::collections::HashMap;
 use std::hash::BuildHasherDefault;
+use std::sync::Arc;
 use std::time::SystemTime;
 
 extern crate fxhash;
-use eval::{capture_score, evaluate, is_capture, promising_move};
+use eval::{capture_score, evaluate, is_capture, is_check, promising_move};
+use eval::pawns::PawnTable;
+use eval::params::EvalParams;
+use eval::nnue::{AccumulatorStack, Network};
 use fxhash::FxHasher;
 
 use board::{ChessMove, GameState};
+use score::{mate_in, Score};
+use search::PositionHistory;
 use state_bitboard::{BitBoardState, BoardStateNumbers};
-
-use crate::client::game::engine::eval::{game_state, is_check};
+use tablebase::{wdl_score, Tablebase};
 
 
+#[cfg(test)]
+pub(crate) mod test_setup;
 #[cfg(test)]
 mod state_bitboard_tests;
+#[cfg(test)]
+mod search_tests;
+#[cfg(test)]
+mod fen_tests;
+#[cfg(test)]
+mod move_string_tests;
+#[cfg(test)]
+mod san_tests;
+#[cfg(test)]
+mod score_tests;
+#[cfg(test)]
+mod tablebase_tests;
+#[cfg(test)]
+mod eval_tests;
 
 
 pub struct GetMoveResult{
@@ -30,11 +55,12 @@ pub struct GetMoveResult{
     eval: i32,
     depth_reached: u32,
     max_depth_reached: usize,
+    time: u128, //milliseconds spent on the search
 }
 
 impl GetMoveResult{
-    pub fn new(chess_move:ChessMove, searched_positions:usize, eval:i32, depth_reached: u32, max_depth_reached: usize) -> Self{
-        Self { chess_move: chess_move, searched_positions: searched_positions, eval: eval, depth_reached: depth_reached, max_depth_reached: max_depth_reached }
+    pub fn new(chess_move:ChessMove, searched_positions:usize, eval:i32, depth_reached: u32, max_depth_reached: usize, time: u128) -> Self{
+        Self { chess_move: chess_move, searched_positions: searched_positions, eval: eval, depth_reached: depth_reached, max_depth_reached: max_depth_reached, time }
     }
 
     pub fn chess_move(&self) -> &ChessMove{
@@ -44,8 +70,9 @@ impl GetMoveResult{
     pub fn num_pos(&self) -> usize{
         self.searched_positions
     }
-    pub fn eval(&self) -> i32{
-        self.eval
+
+    pub fn eval(&self) -> Score{
+        Score::from_search(self.eval)
     }
 
     pub fn depth_reached(&self) -> u32{
@@ -55,6 +82,10 @@ impl GetMoveResult{
     pub fn max_depth_reached(&self) -> usize{
         return self.max_depth_reached;
     }
+
+    pub fn time(&self) -> u128{
+        self.time
+    }
 }
 
 pub struct Engine{
@@ -68,13 +99,19 @@ pub struct Engine{
     average_best_move_index_placement: u64,
     search_stopped: bool,
     max_time: Option<u128>,
+    max_nodes: Option<usize>, //no deeper iteration is started once this many positions are searched
     max_depth_reached: usize,
+    tablebase: Option<Arc<Tablebase>>, //endgame tables, shared between engines as the tables are loaded once
+    params: Arc<EvalParams>, //weights and piece square tables of the evaluation
+    pawn_table: PawnTable, //pawn structures evaluated before
+    network: Option<Arc<Network>>, //evaluates instead of the classical evaluation when there is one
+    accumulators: AccumulatorStack, //hidden layer of the network for every ply of the search
 }
 
 
 impl Engine{
 
-    pub fn new(search_depth: i64, max_depth: usize, table_size: usize, max_time: Option<u128>) -> Self{
+    pub fn new(search_depth: i64, max_depth: usize, table_size: usize, max_time: Option<u128>, params: Arc<EvalParams>) -> Self{
         Self{
             search_depth: search_depth,
             max_depth: max_depth,
@@ -86,12 +123,44 @@ impl Engine{
             average_best_move_index_placement: 0,
             search_stopped: false,
             max_time: max_time,
+            max_nodes: None,
             max_depth_reached: 0,
-            
+            tablebase: None,
+            params,
+            pawn_table: PawnTable::default(),
+            network: None,
+            accumulators: AccumulatorStack::default(),
         }
     }
+
+    pub fn set_tablebase(&mut self, tablebase:Option<Arc<Tablebase>>){
+        self.tablebase = tablebase;
+    }
+
+    //iterative deepening stops at search_depth, or when max_time milliseconds have passed
+    pub fn set_limits(&mut self, search_depth:i64, max_time:Option<u128>){
+        self.search_depth = search_depth;
+        self.max_time = max_time;
+    }
+
+    //a soft node limit, the iteration that passes it is finished so searches with the same limit give the same move
+    pub fn set_node_limit(&mut self, max_nodes:Option<usize>){
+        self.max_nodes = max_nodes;
+    }
+
+    pub fn set_eval_params(&mut self, params:Arc<EvalParams>){
+        self.params = params;
+        //the pawn structures in the table were evaluated with the old parameters
+        self.pawn_table = PawnTable::default();
+    }
+
+    //switches between the network and the classical evaluation
+    pub fn set_network(&mut self, network:Option<Arc<Network>>){
+        self.network = network;
+    }
     
-    pub fn get_move_bb(&mut self, board_state:BitBoardState, match_history:&mut Vec<BoardStateNumbers>) -> GetMoveResult{
+    //game_history holds the hashes of every position in the game, up to and including board_state
+    pub fn get_move_bb(&mut self, board_state:BitBoardState, game_history:&[u64]) -> GetMoveResult{
 
 
         self.num_pos = 0;
@@ -101,12 +170,19 @@ impl Engine{
         self.start_time = SystemTime::now();
         self.max_depth_reached = 0;
 
+        //with few enough pieces the tablebase knows the best move, no search is needed
+        if let Some(tablebase) = &self.tablebase
+            && let Some(root_probe) = tablebase.probe_root(&board_state) {
+            let eval = if board_state.white_to_move() {wdl_score(root_probe.wdl, 0)} else {-wdl_score(root_probe.wdl, 0)};
+            return GetMoveResult::new(root_probe.chess_move, 0, eval, 0, 0, self.start_time.elapsed().unwrap().as_millis());
+        }
 
         let mut bit_board_state = board_state;
         let mut best_move:ChessMove = ChessMove::new_empty();
         let mut best_eval:i32 = 0;
         let mut depth = 0;
         let mut use_extensions = false;
+        let mut history = PositionHistory::new(&game_history[..game_history.len().saturating_sub(1)]);
         
         for i in 2..self.search_depth+1{
             
@@ -116,18 +192,23 @@ impl Engine{
 
             depth = i as u32;
 
-            let last_board_state = match_history.pop().unwrap();
-            let search_result = self.search(&mut bit_board_state, i, i32::MIN, i32::MAX, 0, true, match_history, use_extensions);
-            match_history.push(last_board_state);
+            //the first iteration always finishes, a search stopped before it would have no move to play
+            let max_time = self.max_time;
+            if i == 2 {
+                self.max_time = None;
+            }
+            let search_result = self.search(&mut bit_board_state, i, i32::MIN, i32::MAX, 0, true, &mut history, use_extensions);
+            self.max_time = max_time;
 
             if self.search_stopped {
                 break;
             }
             best_move = search_result.1;
-            if search_result.0 < 30000 && search_result.0 > -30000 {//if depth stopped before calculating the evaluation of the best move, use the previous
-                best_eval = search_result.0;
+            best_eval = search_result.0;
+
+            if self.max_nodes.is_some_and(|max_nodes| self.num_pos >= max_nodes) {
+                break;
             }
-            
         }
 
         return GetMoveResult::new(
@@ -135,24 +216,39 @@ impl Engine{
             self.num_pos,
             best_eval,
             depth,
-            self.max_depth_reached
+            self.max_depth_reached,
+            self.start_time.elapsed().unwrap().as_millis()
         );
     }
 
-    fn evaluate(&mut self, bit_board_state:&BitBoardState) -> i32{
+    //ply is the distance from the root, the accumulator of the network at ply has to be of the position
+    fn evaluate(&mut self, bit_board_state:&BitBoardState, ply:usize) -> i32{
         self.num_pos += 1;
-        return evaluate(bit_board_state);
+        if let Some(network) = &self.network {
+            return self.accumulators.evaluate(network, ply, bit_board_state.white_to_move());
+        }
+        return evaluate(bit_board_state, &self.params, &mut self.pawn_table);
+    }
+
+    //every node updates the accumulator of its ply from the one of its parent
+    fn update_accumulator(&mut self, bit_board_state:&BitBoardState, ply:usize){
+        if let Some(network) = &self.network {
+            self.accumulators.update(network, bit_board_state, ply);
+        }
     }
 
     //finishes the search by looking at any captures in a position, and subsequent "capture-backs" on the same square
     //all nodes are evaluated, a node is evaluated as the min/max of its children and itself (works on the assumption that there is a non capturing move)
-    fn capture_search(&mut self, bit_board_state:&mut BitBoardState, mut alpha:i32, mut beta:i32, capture_depth:usize, opt_capture_square:Option<u8>) -> i32{
+    //ply is the distance from the root, for scoring mates
+    fn capture_search(&mut self, bit_board_state:&mut BitBoardState, mut alpha:i32, mut beta:i32, capture_depth:usize, ply:usize, opt_capture_square:Option<u8>) -> i32{
+
+        self.update_accumulator(bit_board_state, ply);
 
         //Not directly related to piece count but should work
         let game_state = bit_board_state.game_state();
         match game_state{
-            GameState::Black => {return -1000}
-            GameState::White => {return 1000}
+            GameState::Black => {return -mate_in(ply)}
+            GameState::White => {return mate_in(ply)}
             GameState::Draw => {return 0}
             GameState::Playing => {}
         }
@@ -170,7 +266,7 @@ impl Engine{
             });
         }
 
-        let this_eval = self.evaluate(bit_board_state);
+        let this_eval = self.evaluate(bit_board_state, ply);
         //if there are no more captures available, return the evaluation
         if moves.size() == 0 {
             return this_eval;
@@ -189,14 +285,8 @@ impl Engine{
         for i in 0..moves.size(){
             let &capture = moves.get_mut(i);
 
-            let mut result = self.capture_search(&mut bit_board_state.perform_move(capture), alpha, beta, capture_depth+1, Some(capture.target()));
+            let result = self.capture_search(&mut bit_board_state.perform_move(capture), alpha, beta, capture_depth+1, ply+1, Some(capture.target()));
 
-            if result >= 900 {
-                result -= 1;
-            }
-            if result <= -900{
-                result += 1;
-            }
             if result > max {
                 max = result;
             }
@@ -221,13 +311,15 @@ impl Engine{
         }
     }
 
-    fn search(&mut self, bit_board_state:&mut BitBoardState, depth:i64, mut alpha:i32, mut beta:i32, true_depth:usize, _first: bool, match_history:&mut Vec<BoardStateNumbers>, use_extensions: bool) -> (i32, ChessMove){
+    fn search(&mut self, bit_board_state:&mut BitBoardState, depth:i64, mut alpha:i32, mut beta:i32, true_depth:usize, first: bool, history:&mut PositionHistory, use_extensions: bool) -> (i32, ChessMove){
 
-        // ! cancel search once depth is reached
+        // ! cancel search once depth is reached, the capture search updates the accumulator of the leaf
         if depth <= 0 || true_depth >= self.max_depth{
-            return (self.capture_search(bit_board_state, alpha, beta, 0, None), ChessMove::new_empty());
+            return (self.capture_search(bit_board_state, alpha, beta, 0, true_depth, None), ChessMove::new_empty());
         }
 
+        self.update_accumulator(bit_board_state, true_depth);
+
         // ! Statistics
         if true_depth > self.max_depth_reached{
             self.max_depth_reached = true_depth;
@@ -236,21 +328,33 @@ impl Engine{
         let mut best_move_placement: f64 = 0.0;
 
         // ! check for mate, stalemate or if still playing, uses number of legal moves to determine state
-        match game_state(bit_board_state, match_history){
-            GameState::Black => {return (-10000, ChessMove::new_empty())}
-            GameState::White => {return (10000, ChessMove::new_empty())}
+        match bit_board_state.game_state(){
+            GameState::Black => {return (-mate_in(true_depth), ChessMove::new_empty())}
+            GameState::White => {return (mate_in(true_depth), ChessMove::new_empty())}
             GameState::Draw => {return (0, ChessMove::new_empty())}
             GameState::Playing => {}
         }
-    
 
+        // ! repeated positions are draws, the root is not checked as it must allways return a move
+        if !first && history.is_repetition(bit_board_state.hash(), bit_board_state.half_move_clock()){
+            return (0, ChessMove::new_empty());
+        }
+
+        // ! tablebase results, only right after a capture or pawn move as the tables assume the fifty move counter is 0
+        if !first && bit_board_state.half_move_clock() == 0
+            && let Some(wdl) = self.tablebase.as_ref().and_then(|tablebase| tablebase.probe_wdl(bit_board_state)) {
+            let score = wdl_score(wdl, true_depth);
+            return (if bit_board_state.white_to_move() {score} else {-score}, ChessMove::new_empty());
+        }
 
         // ! retrive moves in position
         let mut moves = bit_board_state.gen_moves_legal().moves_vec();
         let board_state_numbers = bit_board_state.board_state_numbers();
-        let previous_best_moves = self.table.get(&board_state_numbers);
+        //mates in the table are counted from the position, not from the root
+        let previous_best_moves = self.table.get(&board_state_numbers)
+            .map(|best_moves| best_moves.iter().map(|&(best_move, eval)| (best_move, score::from_table(eval, true_depth))).collect::<Vec<_>>());
         for i in 0..moves.len(){
-            promising_move(bit_board_state, &mut moves[i], previous_best_moves);
+            promising_move(bit_board_state, &mut moves[i], previous_best_moves.as_ref(), &self.params);
         }
 
         self.table.insert(board_state_numbers, Vec::<(ChessMove, i32)>::new());
@@ -274,6 +378,8 @@ impl Engine{
         let mut cur_move_index = 0;
         let total_moves = moves.len();
 
+        // ! every push must be matched by the pop after the loop, do not return from inside the loop
+        history.push(bit_board_state.hash());
         for &chess_move in moves.iter(){
 
             // ! extensions and reductions
@@ -296,7 +402,7 @@ impl Engine{
 
 
             // ! recursive search call
-            let mut result = self.search(&mut bit_board_state.perform_move(chess_move), depth-1+extension, alpha, beta, true_depth +1, false, match_history, use_extensions);    
+            let result = self.search(&mut bit_board_state.perform_move(chess_move), depth-1+extension, alpha, beta, true_depth +1, false, history, use_extensions);    
             
 
             // ! check for time limit exceeded
@@ -308,13 +414,6 @@ impl Engine{
                 }
             }
 
-            // ! makes sure that the bot choses the fastest checkmate available
-            if result.0 >= 1000 {
-                result.0 -= 1;
-            }else if result.0 <= -1000{
-                result.0 += 1;
-            }
-
             // ! update best move/eval
             // ! DRY
             if result.0 >= max{
@@ -323,7 +422,7 @@ impl Engine{
                     max = result.0;
                     max_move = chess_move;
                     best_move_placement = move_placement as f64/move_count;
-                    self.table.get_mut(&board_state_numbers).unwrap().push((max_move, max));
+                    self.table.get_mut(&board_state_numbers).unwrap().push((max_move, score::to_table(max, true_depth)));
 
                 }
                 
@@ -333,7 +432,7 @@ impl Engine{
                     min = result.0;
                     min_move = chess_move;
                     best_move_placement = move_placement as f64/move_count;
-                    self.table.get_mut(&board_state_numbers).unwrap().push((min_move, min));
+                    self.table.get_mut(&board_state_numbers).unwrap().push((min_move, score::to_table(min, true_depth)));
 
                 }
 
@@ -357,11 +456,11 @@ impl Engine{
             move_placement += 1;
             cur_move_index += 1;
         }
+        history.pop();
 
         self.average_best_move_index_placement += 1;
         self.average_best_move_placement += (best_move_placement as f64 - self.average_best_move_placement)/self.average_best_move_index_placement as f64;
 
-        match_history.pop();
         if bit_board_state.white_to_move(){
             return (max, max_move);
         }else{
@@ -384,7 +483,13 @@ impl Clone for Engine{
             average_best_move_index_placement: 0,
             search_stopped: false,
             max_time: self.max_time,
+            max_nodes: self.max_nodes,
             max_depth_reached: 0,
+            tablebase: self.tablebase.clone(),
+            params: self.params.clone(),
+            pawn_table: PawnTable::default(),
+            network: self.network.clone(),
+            accumulators: AccumulatorStack::default(),
         }
     }
 }
\ No newline at end of file
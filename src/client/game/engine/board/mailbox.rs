@@ -0,0 +1,316 @@
+use super::{ChessMove, ChessMoveList, B_CASTLE_KING, B_CASTLE_QUEEN, BLACK_EN_PASSANT, DOUBLE_PAWN_MOVE, NO_FLAG, PROMOTE_TO_BISHOP, PROMOTE_TO_KNIGHT, PROMOTE_TO_QUEEN, PROMOTE_TO_ROOK, WHITE_EN_PASSANT, W_CASTLE_KING, W_CASTLE_QUEEN};
+use crate::client::game::engine::fen::{parse_fen, FenError};
+use crate::client::game::engine::move_string::string_square;
+use crate::client::game::engine::state_bitboard::{bit_boards::pop_lsb, BLACK, KING, WHITE};
+
+/*
+mailbox representation of a position, the engine itself only uses BitBoardState.
+it is kept around as an independent implementation of the rules to check BitBoardState against in tests
+*/
+
+//Piece codes
+const PIECE_PAWN: u8 = 0b00000001;
+const PIECE_KNIGHT: u8 = 0b00000010;
+const PIECE_BISHOP: u8 = 0b00000011;
+const PIECE_ROOK: u8 = 0b00000100;
+const PIECE_QUEEN: u8 = 0b00000101;
+const PIECE_KING: u8 = 0b00000110;
+
+
+const PIECE_WHITE: u8 = 0b10000000;
+const PIECE_BLACK: u8 = 0b01000000;
+
+//Castle Rights
+const WHITE_CAN_CASTLE_KING: u8 = 0b00000001;
+const WHITE_CAN_CASTLE_QUEEN: u8 = 0b00000010;
+const BLACK_CAN_CASTLE_KING: u8 = 0b00000100;
+const BLACK_CAN_CASTLE_QUEEN: u8 = 0b00001000;
+
+//To move define
+const WHITE_TO_MOVE: bool = true;
+const BLACK_TO_MOVE: bool = false;
+
+//en passant
+const NO_EN_PASSANT_SQUARE:u8 = 0x80;
+
+
+
+
+pub struct BoardState{
+    pieces: [u8; 64],
+    /*
+    Indexes:
+    a1 = 0
+    a2 = 1
+    a3 = 2
+    .
+    .
+    .
+    h8 = 63
+    */
+
+
+    white_to_move: bool,
+    en_passant_square: u8,
+    //index of en passant square
+
+
+    castle_rights: u8,
+    half_move_clock: u8,
+    full_move_number: u16,
+    is_in_check: Option<bool>,
+    legal_moves: Option<ChessMoveList>,
+    white_king: usize,
+    black_king: usize
+
+}
+
+impl BoardState{
+
+    pub fn new_from_fen(fen:&str) -> Result<Self, FenError>{
+        let fen = parse_fen(fen)?;
+
+        const PIECE_CODES:[u8; 6] = [PIECE_PAWN, PIECE_KNIGHT, PIECE_BISHOP, PIECE_ROOK, PIECE_QUEEN, PIECE_KING];
+        let mut pieces:[u8; 64] = [0; 64];
+        for (color, color_code) in [(BLACK, PIECE_BLACK), (WHITE, PIECE_WHITE)]{
+            for (piece, piece_code) in PIECE_CODES.iter().enumerate(){
+                let mut piece_bb = fen.piece_bb[color][piece];
+                while piece_bb != 0 {
+                    pieces[pop_lsb(&mut piece_bb)] = piece_code | color_code;
+                }
+            }
+        }
+
+        let mut castle_rights:u8 = 0x00;
+        castle_rights |= if fen.castle_w_k {WHITE_CAN_CASTLE_KING} else {0};
+        castle_rights |= if fen.castle_w_q {WHITE_CAN_CASTLE_QUEEN} else {0};
+        castle_rights |= if fen.castle_b_k {BLACK_CAN_CASTLE_KING} else {0};
+        castle_rights |= if fen.castle_b_q {BLACK_CAN_CASTLE_QUEEN} else {0};
+
+        return Ok(Self { 
+            pieces: pieces,
+            white_to_move: if fen.white_to_move {WHITE_TO_MOVE} else {BLACK_TO_MOVE},
+            en_passant_square: fen.en_passant_square.unwrap_or(NO_EN_PASSANT_SQUARE), 
+            castle_rights: castle_rights,
+            half_move_clock: fen.half_move_clock,
+            full_move_number: fen.full_move_number,
+            is_in_check: None,
+            legal_moves: None,
+            white_king: fen.piece_bb[WHITE][KING].trailing_zeros() as usize,
+            black_king: fen.piece_bb[BLACK][KING].trailing_zeros() as usize
+        })
+    }
+
+    //castle rights in the order white king side, white queen side, black king side, black queen side, like BitBoardState
+    pub fn castle_rights(&self) -> [bool; 4]{
+        [WHITE_CAN_CASTLE_KING, WHITE_CAN_CASTLE_QUEEN, BLACK_CAN_CASTLE_KING, BLACK_CAN_CASTLE_QUEEN].map(|right| self.castle_rights & right != 0)
+    }
+
+    pub fn perform_move(&mut self, chess_move:ChessMove){
+        //self.move_history.push(chess_move);
+        //self.piece_history.push(self.pieces);
+
+        let flag = chess_move.flag();
+        let origin = chess_move.origin();
+        let target = chess_move.target();
+
+        self.legal_moves = None;
+        self.is_in_check = None;
+
+        //captures and pawn moves reset the half move clock, castling never captures
+        let is_castle = flag == W_CASTLE_KING || flag == W_CASTLE_QUEEN || flag == B_CASTLE_KING || flag == B_CASTLE_QUEEN;
+        let resets_half_move_clock = !is_castle && (
+            self.pieces[origin as usize] & 0b00111111 == PIECE_PAWN || self.pieces[target as usize] != 0
+        );
+
+
+        match flag {
+            NO_FLAG => {
+                self.pieces[target as usize] = self.pieces[origin as usize];
+                self.pieces[origin as usize] = 0;
+                if self.pieces[target as usize] == PIECE_WHITE | PIECE_KING{
+                    self.white_king = target as usize;
+                }else if self.pieces[target as usize] == PIECE_BLACK | PIECE_KING{
+                    self.black_king = target as usize;
+                }
+            }
+            DOUBLE_PAWN_MOVE => {
+                self.pieces[target as usize] = self.pieces[origin as usize];
+                self.pieces[origin as usize] = 0;
+                self.en_passant_square = if self.white_to_move {origin+8} else {origin-8};
+            }
+            W_CASTLE_KING => {
+                self.pieces[4] = 0;
+                self.pieces[7] = 0;
+                self.pieces[5] = PIECE_WHITE | PIECE_ROOK;
+                self.pieces[6] = PIECE_WHITE | PIECE_KING;
+                self.castle_rights &= 0xFC;
+                self.white_king = 6;
+            }
+            W_CASTLE_QUEEN => {
+                self.pieces[4] = 0;
+                self.pieces[0] = 0;
+                self.pieces[3] = PIECE_WHITE | PIECE_ROOK;
+                self.pieces[2] = PIECE_WHITE | PIECE_KING;
+                self.castle_rights &= 0xFC;
+                self.white_king = 2;
+            }
+            B_CASTLE_KING => {
+                self.pieces[60] = 0;
+                self.pieces[63] = 0;
+                self.pieces[61] = PIECE_BLACK | PIECE_ROOK;
+                self.pieces[62] = PIECE_BLACK | PIECE_KING;
+                self.castle_rights &= 0x03;
+                self.black_king = 62;
+            }
+            B_CASTLE_QUEEN => {
+                self.pieces[60] = 0;
+                self.pieces[56] = 0;
+                self.pieces[59] = PIECE_BLACK | PIECE_ROOK;
+                self.pieces[58] = PIECE_BLACK | PIECE_KING;
+                self.castle_rights &= 0x03;
+                self.black_king = 58;
+            }
+            PROMOTE_TO_BISHOP => {
+                self.pieces[origin as usize] = 0;
+                self.pieces[target as usize] =  if self.white_to_move {PIECE_WHITE} else {PIECE_BLACK} | PIECE_BISHOP;
+            }
+            PROMOTE_TO_KNIGHT => {
+                self.pieces[origin as usize] = 0;
+                self.pieces[target as usize] =  if self.white_to_move {PIECE_WHITE} else {PIECE_BLACK} | PIECE_KNIGHT;
+            }
+            PROMOTE_TO_QUEEN => {
+                self.pieces[origin as usize] = 0;
+                self.pieces[target as usize] =  if self.white_to_move {PIECE_WHITE} else {PIECE_BLACK} | PIECE_QUEEN;
+            }
+            PROMOTE_TO_ROOK => {
+                self.pieces[origin as usize] = 0;
+                self.pieces[target as usize] =  if self.white_to_move {PIECE_WHITE} else {PIECE_BLACK} | PIECE_ROOK;
+            }
+            BLACK_EN_PASSANT => {
+                self.pieces[target as usize] = self.pieces[origin as usize];
+                self.pieces[origin as usize] = 0;
+                self.pieces[(target + 8) as usize] = 0;
+
+            }
+            WHITE_EN_PASSANT => {
+                self.pieces[target as usize] = self.pieces[origin as usize];
+                self.pieces[origin as usize] = 0;
+                self.pieces[(target - 8) as usize] = 0;
+
+            }
+            _ => {println!("INVALID MOVE FLAG")}
+        }
+
+        //remove castle rights if one of the involved pieces is 
+        //castle moves are encoded with square 0 as origin and target, so they are skipped here
+        if !is_castle {
+            for square in [origin, target]{
+                match square {
+                    0 => {self.castle_rights &= 0b1101}
+                    7 => {self.castle_rights &= 0b1110}
+                    56 => {self.castle_rights &= 0b0111}
+                    63 => {self.castle_rights &= 0b1011}
+                    4 => {self.castle_rights &= 0b1100}
+                    60 => {self.castle_rights &= 0b0011}
+                    _ => {}
+                }
+            }
+        }
+
+        //If the en passsant square was not set this halfmove, then we should remove it, as it is old
+        if flag != DOUBLE_PAWN_MOVE {
+            self.en_passant_square = NO_EN_PASSANT_SQUARE;
+        }
+        if !self.white_to_move {
+            self.full_move_number += 1;
+        }
+        self.white_to_move = !self.white_to_move;
+        self.half_move_clock = if resets_half_move_clock {0} else {self.half_move_clock.saturating_add(1)};
+
+    }
+
+
+    pub fn pieces(&self) -> &[u8; 64]{
+        return &self.pieces;
+    }
+
+    pub fn white_to_move(&self) -> bool{self.white_to_move}
+    pub fn en_passant_square(&self) -> u8{self.en_passant_square}
+    pub fn half_move_clock(&self) -> u8{self.half_move_clock}
+    pub fn full_move_number(&self) -> u16{self.full_move_number}
+
+    pub fn to_fen(&self) -> String{
+        let mut fen = String::with_capacity(90);
+
+        for rank in (0..8).rev(){
+            let mut empty_squares = 0;
+            for file in 0..8{
+                let piece = self.pieces[rank*8 + file];
+                if piece == 0 {
+                    empty_squares += 1;
+                    continue;
+                }
+                if empty_squares != 0 {
+                    fen.push(char::from(b'0' + empty_squares));
+                    empty_squares = 0;
+                }
+                let piece_char = match piece & 0b00111111 {
+                    PIECE_PAWN => 'p',
+                    PIECE_KNIGHT => 'n',
+                    PIECE_BISHOP => 'b',
+                    PIECE_ROOK => 'r',
+                    PIECE_QUEEN => 'q',
+                    _ => 'k',
+                };
+                fen.push(if piece & PIECE_WHITE != 0 {piece_char.to_ascii_uppercase()} else {piece_char});
+            }
+            if empty_squares != 0 {
+                fen.push(char::from(b'0' + empty_squares));
+            }
+            if rank != 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(if self.white_to_move {" w "} else {" b "});
+
+        if self.castle_rights == 0 {
+            fen.push('-');
+        }
+        for (right, right_char) in [(WHITE_CAN_CASTLE_KING, 'K'), (WHITE_CAN_CASTLE_QUEEN, 'Q'), (BLACK_CAN_CASTLE_KING, 'k'), (BLACK_CAN_CASTLE_QUEEN, 'q')]{
+            if self.castle_rights & right != 0 {
+                fen.push(right_char);
+            }
+        }
+
+        if self.en_passant_square < 64 {
+            fen.push_str(&format!(" {} ", string_square(self.en_passant_square)));
+        }else{
+            fen.push_str(" - ");
+        }
+
+        fen.push_str(&format!("{} {}", self.half_move_clock, self.full_move_number));
+        fen
+    }
+}
+
+impl Clone for BoardState{
+    fn clone(&self) -> Self {
+        Self { 
+            pieces: self.pieces,
+            white_to_move: self.white_to_move, 
+            en_passant_square: self.en_passant_square, 
+            castle_rights: self.castle_rights, 
+            half_move_clock: self.half_move_clock,
+            full_move_number: self.full_move_number,
+            is_in_check: self.is_in_check,
+            legal_moves: self.legal_moves,
+            white_king: self.white_king,
+            black_king: self.black_king
+        }
+    }
+}
+
+
+
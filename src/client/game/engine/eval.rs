@@ -1,22 +1,105 @@
-use crate::client::game::engine::board::{self, GameState};
+use crate::client::game::engine::board;
 /**
  * eval contains all functions meant to statically evaluate a function, mainly trough the function "evaluate"
  * all static evaluation should remain stateless
  * 
  */
-use crate::client::game::engine::state_bitboard::{BISHOP, BoardStateNumbers, KNIGHT, PAWN, QUEEN, ROOK};
+use crate::client::game::engine::state_bitboard::{BISHOP, KNIGHT, PAWN, QUEEN, ROOK};
 
-use super::board::{ChessMove, BLACK_EN_PASSANT, B_CASTLE_KING, B_CASTLE_QUEEN, WHITE_EN_PASSANT, W_CASTLE_KING, W_CASTLE_QUEEN};
-use super::state_bitboard::bit_boards::{file_of, pop_lsb, rank_of, BOARD_CENTER, KING_PAWNS_OPTIMAL, NEIGHBOUR_FILES, RANKS, RANK_1, RANK_8, SEC_TIER_BISHOP, SEC_TIER_PAWN, TOP_TIER_BISHOP, TOP_TIER_PAWN};
+use super::board::{ChessMove, BLACK_EN_PASSANT, CASTLE_SQUARES, B_CASTLE_KING, B_CASTLE_QUEEN, WHITE_EN_PASSANT, W_CASTLE_KING, W_CASTLE_QUEEN};
+use super::state_bitboard::bit_boards::{file_of, pop_lsb, rank_of, BishopMoves, RookMoves, KING_MOVES, KNIGHT_MOVES, NEIGHBOUR_FILES};
 use super::state_bitboard::{bit_boards, BitBoardState, BLACK, KING, WHITE};
 
+use std::ops::{Add, AddAssign, Mul, Sub};
+
+pub mod pst;
+pub mod pawns;
+pub mod params;
+pub mod nnue;
+pub mod tuner;
+pub mod trace;
+#[cfg(test)]
+mod pst_tests;
+#[cfg(test)]
+mod params_tests;
+#[cfg(test)]
+mod nnue_tests;
+#[cfg(test)]
+mod tuner_tests;
+#[cfg(test)]
+mod pawns_tests;
+#[cfg(test)]
+mod trace_tests;
+
+use params::EvalParams;
+use pawns::{pawn_attacks, passed_pawn_score, PawnTable};
+use trace::EvalTrace;
+
+//a middlegame and an endgame value, mixed by the phase of the game
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Tapered{
+    pub mg: i32,
+    pub eg: i32,
+}
+
+pub const fn tapered(mg:i32, eg:i32) -> Tapered{
+    Tapered { mg, eg }
+}
+
+impl Tapered{
+    //phase goes from 0 with all pieces on the board to MAX_PHASE when only kings and pawns are left
+    pub fn taper(self, phase:i32) -> i32{
+        (self.mg*(MAX_PHASE - phase) + self.eg*phase)/MAX_PHASE
+    }
+}
+
+impl Add for Tapered{
+    type Output = Tapered;
+    fn add(self, other:Tapered) -> Tapered{
+        tapered(self.mg + other.mg, self.eg + other.eg)
+    }
+}
+
+impl Sub for Tapered{
+    type Output = Tapered;
+    fn sub(self, other:Tapered) -> Tapered{
+        tapered(self.mg - other.mg, self.eg - other.eg)
+    }
+}
+
+impl AddAssign for Tapered{
+    fn add_assign(&mut self, other:Tapered){
+        *self = *self + other;
+    }
+}
+
+//a term counted n times
+impl Mul<i32> for Tapered{
+    type Output = Tapered;
+    fn mul(self, n:i32) -> Tapered{
+        tapered(self.mg*n, self.eg*n)
+    }
+}
+
+pub const MAX_PHASE:i32 = 24;
+
+//non pawn material of a side at the start, in phase points, the king danger shrinks with the material of the attacker
+const FULL_ATTACK_MATERIAL:i32 = 12;
+
 
 pub fn is_check(bit_board_state:&BitBoardState, chess_move: &ChessMove) -> bool{
     /*
     summed up, if we give the moving side an extra tempo, can it capture the king?
      */
 
+    let other = if bit_board_state.white_to_move() {0} else {1};
 
+    //castles have no origin and target squares, only the rook can give check from the square it lands on
+    if let Some(&(_, king_origin, king_target, rook_square)) = CASTLE_SQUARES.iter().find(|castle| castle.0 == chess_move.flag()) {
+        let rook_target = (king_origin + king_target)/2;
+        let piece_mask = bit_board_state.piece_mask() & !(1 << king_origin) & !(1 << rook_square) | 1 << king_target | 1 << rook_target;
+        return bit_boards::RookMoves::mov_map(rook_target as usize, piece_mask) & bit_board_state.piece_bb()[other][KING] != 0;
+    }
 
     let origin = chess_move.origin() as usize;
     let target = chess_move.target() as usize;
@@ -44,7 +127,6 @@ pub fn is_check(bit_board_state:&BitBoardState, chess_move: &ChessMove) -> bool{
         }
         _ => {}
     }
-    let other = if bit_board_state.white_to_move() {0} else {1};
     if psuedo_legal_follow_up_captures & bit_board_state.piece_bb()[other][KING] != 0 {
         return true;
     }else{
@@ -52,54 +134,51 @@ pub fn is_check(bit_board_state:&BitBoardState, chess_move: &ChessMove) -> bool{
     }
 }
 
-fn knight_placement_score(knights:u64) -> i32{
-    return u64::count_ones(knights & BOARD_CENTER) as i32;
-}
-
-fn pawn_placement_score(pawns:u64, color:usize) -> i32{
-    let mut score:i32 = 0;
-    score += u64::count_ones(pawns & TOP_TIER_PAWN[color]) as i32 * 2;
-    score += u64::count_ones(pawns & SEC_TIER_PAWN[color]) as i32;
-    return score;
-}
-
-fn pawn_structure_score(pawns:u64) -> i32{
-    let mut pawn_structure_score:i32 = 0;
-    
-    for i in 0..8{
-
-        if file_of(i) & pawns == 0 {//no pawns on file, no penalty
-            continue;
-        }else if NEIGHBOUR_FILES[i] & pawns == 0 {//no neighbour pawns -> isolated pawn -> penalty
-            pawn_structure_score -= 1;
+//the squares attacked by every piece type of one color, and its mobility
+//mobility counts the squares pieces attack that are neither taken by their own pieces nor attacked by enemy pawns
+fn attacks_and_mobility(pieces:&[u64; 6], color:usize, occupied:u64, enemy_pawn_attacks:u64, params:&EvalParams) -> ([u64; 6], Tapered){
+    let mut attacks = [0; 6];
+    let mut mobility = Tapered::default();
+    let own_pieces = pieces.iter().fold(0, |all, bb| all | bb);
+    let safe_squares = !own_pieces & !enemy_pawn_attacks;
+
+    attacks[PAWN] = pawn_attacks(pieces[PAWN], color);
+    attacks[KING] = KING_MOVES[u64::trailing_zeros(pieces[KING]) as usize];
+
+    for piece in [KNIGHT, BISHOP, ROOK, QUEEN]{
+        let mut remaining = pieces[piece];
+        while remaining != 0 {
+            let square = pop_lsb(&mut remaining);
+            let piece_attacks = match piece {
+                KNIGHT => KNIGHT_MOVES[square],
+                BISHOP => BishopMoves::mov_map(square, occupied),
+                ROOK => RookMoves::mov_map(square, occupied),
+                _ => BishopMoves::mov_map(square, occupied) | RookMoves::mov_map(square, occupied),
+            };
+            attacks[piece] |= piece_attacks;
+            mobility += params.mobility[piece]*u64::count_ones(piece_attacks & safe_squares) as i32;
         }
-        let pawns_on_file = u64::count_ones(file_of(i) & pawns);
-        pawn_structure_score += 1 - pawns_on_file as i32;
     }
-
-
-    return pawn_structure_score;
+    (attacks, mobility)
 }
 
-fn pawn_promotion_score(pawns: u64, color: usize) -> i32{
-
-    // ! does not take into account if a pawn is passed or not.
-    let mut pawn_promotion_score = 0;
-    let dir = -1 + 2*color as i32;//-1 for black, 1 for white 
-    pawn_promotion_score += u64::count_ones(RANKS[((dir*3).rem_euclid(8)) as usize] & pawns) as i32 * 1;
-    pawn_promotion_score += u64::count_ones(RANKS[((dir*4).rem_euclid(8)) as usize] & pawns) as i32 * 2;
-    pawn_promotion_score += u64::count_ones(RANKS[((dir*5).rem_euclid(8)) as usize] & pawns) as i32 * 3;
-    pawn_promotion_score += u64::count_ones(RANKS[((dir*6).rem_euclid(8)) as usize] & pawns) as i32 * 4;
-    pawn_promotion_score += u64::count_ones(RANKS[((dir*7).rem_euclid(8)) as usize] & pawns) as i32 * 5;
+//bonus for attacking enemy pieces with less valuable pieces, the enemy has to move them or lose material
+fn threat_score(attacks:&[u64; 6], other_pieces:&[u64; 6], params:&EvalParams) -> Tapered{
+    let pieces_attacked_by_pawns = attacks[PAWN] & (other_pieces[KNIGHT] | other_pieces[BISHOP] | other_pieces[ROOK] | other_pieces[QUEEN]);
+    let pieces_attacked_by_minors = (attacks[KNIGHT] | attacks[BISHOP]) & (other_pieces[ROOK] | other_pieces[QUEEN]);
+    let queens_attacked_by_rooks = attacks[ROOK] & other_pieces[QUEEN];
 
-    return pawn_promotion_score;
+    params.threat_by_pawn*u64::count_ones(pieces_attacked_by_pawns) as i32 +
+    params.threat_by_minor*u64::count_ones(pieces_attacked_by_minors) as i32 +
+    params.threat_by_rook*u64::count_ones(queens_attacked_by_rooks) as i32
 }
 
-fn bishop_placement_score(bishops:u64, color:usize) -> i32{
-    let mut score:i32 = 0;
-    score += u64::count_ones(bishops & TOP_TIER_BISHOP[color]) as i32 * 2;
-    score += u64::count_ones(bishops & SEC_TIER_BISHOP[color]) as i32;
-    return score;
+//number of pieces, the king left out, the enemy attacks and nothing defends
+fn hanging_pieces(pieces:&[u64; 6], attacks:&[u64; 6], other_attacks:&[u64; 6]) -> i32{
+    let defended = attacks.iter().fold(0, |all, bb| all | bb);
+    let attacked = other_attacks.iter().fold(0, |all, bb| all | bb);
+    let own_pieces = pieces[PAWN] | pieces[KNIGHT] | pieces[BISHOP] | pieces[ROOK] | pieces[QUEEN];
+    u64::count_ones(own_pieces & attacked & !defended) as i32
 }
 
 fn rook_score(rooks:u64, pawns:u64, _blockers:u64) -> i32{
@@ -130,15 +209,8 @@ fn rook_score(rooks:u64, pawns:u64, _blockers:u64) -> i32{
 //a slightly less static way of counting material
 //knights are worth more in closed position
 //bishops are worth more in open positions
-fn dynamic_piece_count(pieces:&[u64; 6], other_pieces:&[u64; 6]) -> i32{
-    let mut piece_count:i32 = 0;
-
-    //default value for pieces
-    const VALUE_PAWN:i32 = 100;
-    const VALUE_KNIGHT:i32 = 300;
-    const VALUE_BISHOP:i32 = 320;
-    const VALUES_ROOK:[i32; 10] = [500, 900, 1300, 1700, 2100, 2500, 2900, 3300, 3600, 3900];
-    const VALUE_QUEEN:i32 = 900;
+fn dynamic_piece_count(pieces:&[u64; 6], other_pieces:&[u64; 6], params:&EvalParams) -> Tapered{
+    let mut piece_count = Tapered::default();
 
     //number of piece type for self color
     let num_pawns = u64::count_ones(pieces[PAWN]) as i32;
@@ -151,56 +223,115 @@ fn dynamic_piece_count(pieces:&[u64; 6], other_pieces:&[u64; 6]) -> i32{
     let total_pawns = u64::count_ones(other_pieces[PAWN])as i32+num_pawns;
 
     //pawns
-    piece_count += num_pawns*VALUE_PAWN;
+    piece_count += params.pawn_value*num_pawns;
 
 
     //knights are worth more in a closed position(more pawns)
-    piece_count += num_knights*(VALUE_KNIGHT+total_pawns);
+    piece_count += (params.knight_value + params.knight_pawn_bonus*total_pawns)*num_knights;
 
 
     //bishops are worth more in an endgame(less pawns)
-    piece_count += num_bishops*(VALUE_BISHOP-total_pawns);
+    piece_count += (params.bishop_value + params.bishop_pawn_bonus*total_pawns)*num_bishops;
 
 
     //rooks: rooks decrease in value when having more, two are worth as much as a queen
-    piece_count += VALUES_ROOK[num_rooks as usize];
+    piece_count += params.rook_values[(num_rooks as usize).min(9)];
 
     
     //queens
-    piece_count += num_queens*VALUE_QUEEN;
+    piece_count += params.queen_value*num_queens;
 
     return piece_count;
 }
 
-//returns a king safety score
-//this score is only a penalty
-fn king_safety(pieces:&[u64; 6], _other_pieces:&[u64; 6], color:usize) -> i32{
-    let mut king_safety_score:i32 = 0;
+//attack unit king safety of the side owning "pieces", the enemy attacks on the squares around the king, open files
+//next to it, safe checks and storming pawns add up to a danger that is scaled down as the attacker loses material
+fn king_safety(pieces:&[u64; 6], other_pieces:&[u64; 6], color:usize, attacks:&[u64; 6], other_attacks:&[u64; 6], occupied:u64, params:&EvalParams) -> Tapered{
+    let mut score = Tapered::default();
+    let king = u64::trailing_zeros(pieces[KING]) as usize;
+    let king_rank = (king/8) as i32;
+    let king_files = file_of(king) | NEIGHBOUR_FILES[king % 8];
+    let forward = |bb:u64| if color == WHITE {bb << 8} else {bb >> 8};
+
+    //pawn shield, the rank in front of the king and the one in front of that
+    let first_rank = forward(rank_of(king)) & king_files;
+    score += params.king_shield[0]*u64::count_ones(pieces[PAWN] & first_rank) as i32;
+    score += params.king_shield[1]*u64::count_ones(pieces[PAWN] & forward(first_rank)) as i32;
+
+    //enemy pawns coming closer to the king
+    let mut storm = other_pieces[PAWN] & king_files;
+    while storm != 0 {
+        let pawn = pop_lsb(&mut storm);
+        let ranks_away = ((pawn/8) as i32 - king_rank) * if color == WHITE {1} else {-1};
+        if (1..4).contains(&ranks_away) {
+            score += params.pawn_storm[ranks_away as usize];
+        }
+    }
 
-    let king_pos = u64::trailing_zeros(pieces[KING]) as usize;
+    let mut units = 0;
+    let king_zone = KING_MOVES[king] | pieces[KING] | forward(KING_MOVES[king]);
+    for (piece_units, piece_attacks) in params.king_zone_attack_units.iter().zip(other_attacks){
+        units += piece_units*u64::count_ones(piece_attacks & king_zone) as i32;
+    }
 
-    // !nasty code
-    //? find a better way of achieving this, right now this does not really play along with concept of open files
+    let defended = attacks.iter().fold(0, |all, bb| all | bb);
+    let other_occupied = other_pieces.iter().fold(0, |all, bb| all | bb);
+    let safe = !defended & !other_occupied;
+    let bishop_checks = BishopMoves::mov_map(king, occupied) & safe;
+    let rook_checks = RookMoves::mov_map(king, occupied) & safe;
+    let check_squares = [0, KNIGHT_MOVES[king] & safe, bishop_checks, rook_checks, bishop_checks | rook_checks, 0];
+    for piece in KNIGHT..KING{
+        if other_attacks[piece] & check_squares[piece] != 0 {
+            units += params.safe_check_units[piece];
+        }
+    }
 
-    if rank_of(king_pos) == RANK_1 && color == WHITE{
-        king_safety_score += (u64::count_ones(KING_PAWNS_OPTIMAL[WHITE][king_pos] & pieces[PAWN])*2) as i32;
-        king_safety_score += u64::count_ones((KING_PAWNS_OPTIMAL[WHITE][king_pos] << 8) & pieces[PAWN]) as i32;
-    }else if rank_of(king_pos) == RANK_8 && color == BLACK{
-        king_safety_score += (u64::count_ones(KING_PAWNS_OPTIMAL[BLACK][king_pos - 56] & pieces[PAWN])*2) as i32;
-        king_safety_score += u64::count_ones((KING_PAWNS_OPTIMAL[BLACK][king_pos - 56] >> 8) & pieces[PAWN]) as i32;
+    for file in 0..8{
+        if file_of(file) & king_files == 0 || file_of(file) & pieces[PAWN] != 0 {
+            continue;
+        }
+        units += if file_of(file) & other_pieces[PAWN] == 0 {params.open_file_units} else {params.semi_open_file_units};
     }
 
-    //TODO: penalty for enemy pieces attacking nearby squares
+    let units = units.min(params.max_attack_units);
+    let attack_material = (u64::count_ones(other_pieces[KNIGHT] | other_pieces[BISHOP]) +
+        2*u64::count_ones(other_pieces[ROOK]) + 4*u64::count_ones(other_pieces[QUEEN])) as i32;
+    let danger = tapered(units*units/4, units)*attack_material.min(FULL_ATTACK_MATERIAL);
+    score.mg -= danger.mg/FULL_ATTACK_MATERIAL;
+    score.eg -= danger.eg/FULL_ATTACK_MATERIAL;
 
-    //TODO: penalty for pawn storm, if enemy pawns are close. Note that this penalty should be strictly less than that for a open file near the king
+    return score;
+}
 
-    
+//returns how much of an advantage the side owning "pieces" is able to convert, out of 16
+//without pawns a lone minor piece can never mate, and an extra minor piece is rarely enough to win
+fn winning_chances_scale(pieces:&[u64; 6], other_pieces:&[u64; 6]) -> i32{
+    if pieces[PAWN] != 0 {
+        return 16;
+    }
 
-    return king_safety_score;
+    let minors = u64::count_ones(pieces[KNIGHT] | pieces[BISHOP]) as i32;
+    let majors = u64::count_ones(pieces[ROOK] | pieces[QUEEN]) as i32;
+    if majors == 0 && minors <= 1 {
+        return 0;
+    }
+    if majors == 0 && pieces[BISHOP] == 0 && other_pieces[PAWN] == 0 {
+        return 0; //two knights can not force mate against a bare king
+    }
+
+    let material = |p:&[u64; 6]| -> i32 {
+        u64::count_ones(p[KNIGHT] | p[BISHOP]) as i32 * 3 +
+        u64::count_ones(p[ROOK]) as i32 * 5 +
+        u64::count_ones(p[QUEEN]) as i32 * 9
+    };
+    if material(pieces) - material(other_pieces) <= 3 {
+        return 4; //e.g. rook vs minor piece, or two minors vs one
+    }
+    16
 }
 
 //0 -> all pieces are on the board
-//256 -> all pieces are on the board
+//MAX_PHASE -> only kings and pawns are left
 fn endgame_factor(pieces:&[[u64; 6]; 2]) -> i32{
 
     let num_pawns = u64::count_ones(pieces[WHITE][PAWN] | pieces[BLACK][PAWN]) as i32;
@@ -222,7 +353,8 @@ fn endgame_factor(pieces:&[[u64; 6]; 2]) -> i32{
     phase -= num_rooks*ROOK_PHASE;
     phase -= num_queens*QUEEN_PHASE;
 
-    return phase;
+    //promotions can put more pieces on the board than there are at the start
+    return phase.max(0);
 }
 
 /**
@@ -246,7 +378,7 @@ pub fn is_capture(bit_board_state:&BitBoardState, m: &ChessMove) -> bool{
 }
 
 //attempts a very rough estimate on how good a move is
-pub fn promising_move(bit_board_state:&mut BitBoardState, chess_move: &mut ChessMove, best_moves_option:Option<&Vec<(ChessMove, i32)>>){
+pub fn promising_move(bit_board_state:&mut BitBoardState, chess_move: &mut ChessMove, best_moves_option:Option<&Vec<(ChessMove, i32)>>, params:&EvalParams){
     
     
     let mut promising_level = 0;
@@ -257,7 +389,8 @@ pub fn promising_move(bit_board_state:&mut BitBoardState, chess_move: &mut Chess
     let origin_value = bit_board_state.piece_value(origin);
     let target_value = bit_board_state.piece_value(target);
     
-    let color_value = if origin_value < 0 {-1} else {1};
+    //from the side to move, castles do not have the king on their origin square
+    let color_value = if bit_board_state.white_to_move() {1} else {-1};
 
 
     // ! this turns what should have been a constant operation into a linear one, allthough n is usually quite small here
@@ -265,7 +398,8 @@ pub fn promising_move(bit_board_state:&mut BitBoardState, chess_move: &mut Chess
         for good_move in best_moves {
             if *chess_move == good_move.0 {
                 let promising_level_ref = chess_move.promising_level_mut();
-                *promising_level_ref = 3000*color_value as i16 + good_move.1 as i16;
+                //mate scores do not fit in the promising level
+                *promising_level_ref = (3000*color_value + good_move.1).clamp(i16::MIN as i32, i16::MAX as i32) as i16;
                 return;
             }
         }
@@ -282,21 +416,12 @@ pub fn promising_move(bit_board_state:&mut BitBoardState, chess_move: &mut Chess
                  */
                 promising_level += -target_value*10; //add value of captured piece
                 promising_level -= origin_value*10 //subtract value of capturing piece
-            }else{ // for non captures
-
-                if origin_value == 10{
-                    promising_level += pawn_placement_score(1 << target, WHITE);
-                    promising_level -= pawn_placement_score(1 << origin, WHITE);
-                }else if origin_value == -10{
-                    promising_level += pawn_placement_score(1 << target, BLACK);
-                    promising_level -= pawn_placement_score(1 << origin, BLACK);
-                }else if origin_value == 30 || origin_value == -30{
-                    promising_level += knight_placement_score(1 << target);
-                    promising_level -= knight_placement_score(1 << origin);
-                }
-                else if origin_value == 35 || origin_value == -35{
-                    promising_level += bishop_placement_score(1 << target, 0);
-                    promising_level -= bishop_placement_score(1 << origin, 0);
+            }else{ // for non captures, how much better the square is in the middlegame, from the side moving
+                let color = if bit_board_state.white_to_move() {WHITE} else {BLACK};
+                let moving_pieces = bit_board_state.piece_bb()[color];
+                if let Some(piece) = moving_pieces.iter().position(|bb| bb & (1 << origin) != 0){
+                    let gain = params.pst.value(piece, color, target).mg - params.pst.value(piece, color, origin).mg;
+                    promising_level += gain*color_value/10;
                 }
             }
 
@@ -339,62 +464,57 @@ pub fn promising_move(bit_board_state:&mut BitBoardState, chess_move: &mut Chess
     *promising_level_ref = promising_level as i16;
 }
 
-pub fn game_state(bit_board_state:&mut BitBoardState, match_history:&mut Vec<BoardStateNumbers>) -> GameState {
-
-    let game_state = bit_board_state.game_state();
-    if game_state != GameState::Playing {
-        return game_state;
-    }
-
-    let board_state_numbers = bit_board_state.board_state_numbers();
-    match_history.push(board_state_numbers);
-    if match_history.iter().filter(|&n| *n == board_state_numbers).count() == 3{
-        match_history.pop();
-        return GameState::Draw;
-    }
-    GameState::Playing
+pub fn evaluate(bit_board_state:&BitBoardState, params:&EvalParams, pawn_table:&mut PawnTable) -> i32{
+    trace(bit_board_state, params, pawn_table).eval
 }
 
-pub fn evaluate(bit_board_state:&BitBoardState) -> i32{
+//every term of the evaluation for both colors, see trace.rs
+pub fn trace(bit_board_state:&BitBoardState, params:&EvalParams, pawn_table:&mut PawnTable) -> EvalTrace{
     let pieces = bit_board_state.piece_bb();
     let piece_mask:u64 = bit_board_state.piece_mask();
+    //both colors of a term, indexed like piece_bb
+    let both = |white:Tapered, black:Tapered| -> [Tapered; 2] {
+        let mut term = [Tapered::default(); 2];
+        term[WHITE] = white;
+        term[BLACK] = black;
+        term
+    };
+
+    let pawn_eval = pawn_table.probe([pieces[BLACK][PAWN], pieces[WHITE][PAWN]], params);
+    let (white_attacks, white_mobility) = attacks_and_mobility(&pieces[WHITE], WHITE, piece_mask, pawn_attacks(pieces[BLACK][PAWN], BLACK), params);
+    let (black_attacks, black_mobility) = attacks_and_mobility(&pieces[BLACK], BLACK, piece_mask, pawn_attacks(pieces[WHITE][PAWN], WHITE), params);
+
+    let mut trace = EvalTrace {
+        material: both(dynamic_piece_count(&pieces[WHITE], &pieces[BLACK], params),
+            dynamic_piece_count(&pieces[BLACK], &pieces[WHITE], params)),
+        placement: both(params.pst.score(&pieces[WHITE], WHITE),
+            params.pst.score(&pieces[BLACK], BLACK)),
+        pawn_structure: pawn_eval.scores,
+        passed_pawns: both(passed_pawn_score(pawn_eval.passed[WHITE], &pieces[WHITE], &pieces[BLACK], WHITE, bit_board_state.white_to_move(), params),
+            passed_pawn_score(pawn_eval.passed[BLACK], &pieces[BLACK], &pieces[WHITE], BLACK, !bit_board_state.white_to_move(), params)),
+        rooks: both(params.rook_file*rook_score(pieces[WHITE][ROOK], pieces[WHITE][PAWN], piece_mask),
+            params.rook_file*rook_score(pieces[BLACK][ROOK], pieces[BLACK][PAWN], piece_mask)),
+        mobility: both(white_mobility, black_mobility),
+        threats: both(threat_score(&white_attacks, &pieces[BLACK], params),
+            threat_score(&black_attacks, &pieces[WHITE], params)),
+        hanging: both(params.hanging*hanging_pieces(&pieces[BLACK], &black_attacks, &white_attacks),
+            params.hanging*hanging_pieces(&pieces[WHITE], &white_attacks, &black_attacks)),
+        king_safety: both(king_safety(&pieces[WHITE], &pieces[BLACK], WHITE, &white_attacks, &black_attacks, piece_mask, params),
+            king_safety(&pieces[BLACK], &pieces[WHITE], BLACK, &black_attacks, &white_attacks, piece_mask, params)),
+        phase: endgame_factor(&pieces),
+        scale: 16,
+        eval: 0,
+    };
+
+    let eval = trace.total().taper(trace.phase);
+
+    //pull the evaluation towards a draw when the side ahead lacks the material to win
+    if eval > 0 {
+        trace.scale = winning_chances_scale(&pieces[WHITE], &pieces[BLACK]);
+    }else{
+        trace.scale = winning_chances_scale(&pieces[BLACK], &pieces[WHITE]);
+    }
+    trace.eval = eval*trace.scale/16;
 
-
-    let endgame_factor = endgame_factor(&pieces);
-
-    let mut eval:i32 = 0;
-    //eval += fastrand::i32(-5..5);
-
-    eval += dynamic_piece_count(&pieces[WHITE], &pieces[BLACK]) -
-            dynamic_piece_count(&pieces[BLACK], &pieces[WHITE]);
-
-    eval += (pawn_placement_score(pieces[WHITE][PAWN], WHITE) - 
-            pawn_placement_score(pieces[BLACK][PAWN], BLACK))
-            *3;
-    
-    eval += ((pawn_promotion_score(pieces[WHITE][PAWN], WHITE) - 
-            pawn_promotion_score(pieces[BLACK][PAWN], BLACK))
-            *endgame_factor)/10;
-
-    eval += (pawn_structure_score(pieces[WHITE][PAWN]) -
-            pawn_structure_score(pieces[BLACK][PAWN]))
-            *35;
-
-    eval += (knight_placement_score(pieces[WHITE][KNIGHT]) -
-            knight_placement_score(pieces[BLACK][KNIGHT]))
-            *5;
-
-    eval += (bishop_placement_score(pieces[WHITE][BISHOP], WHITE) -
-            bishop_placement_score(pieces[BLACK][BISHOP], BLACK))
-            *15;
-
-    eval += (rook_score(pieces[WHITE][ROOK], pieces[WHITE][PAWN], piece_mask) -
-            rook_score(pieces[BLACK][ROOK], pieces[BLACK][PAWN], piece_mask)
-            )*20;
-
-    eval += (king_safety(&pieces[WHITE], &pieces[BLACK], WHITE) -
-            king_safety(&pieces[BLACK], &pieces[WHITE], BLACK))
-            *50;
-
-    return eval;
+    return trace;
 }
@@ -0,0 +1,266 @@
+use std::{env, fmt};
+
+use super::super::state_bitboard::{BitBoardState, BLACK, WHITE};
+use super::super::state_bitboard::bit_boards::pop_lsb;
+
+/*
+an efficiently updatable neural network, the optional replacement of the classical evaluation.
+
+the network is (768->N)x2->1: the input has a feature for every piece type of every color on every square, seen from
+both sides. from the side of white the features are indexed by [own or enemy color][piece][square], from the side of
+black the board is mirrored from rank 1 to rank 8 so both sides use the same weights. the hidden layer of both sides,
+the accumulator, is the sum of the weights of the features of the pieces on the board, so a move only has to subtract
+the weights of the pieces that left their squares and add the weights of the pieces that arrived. the output is the
+clipped relu of the accumulator of the side to move followed by the one of the other side, times the output weights.
+
+everything is integer: the accumulators are quantised by QA and the output weights by QB, so the evaluation is the
+output sum times SCALE/(QA*QB). the output sum is an i64, 2*MAX_HIDDEN_SIZE products of up to QA*32767 overflow an
+i32. on x86-64 with avx2 the output layer uses simd instructions, elsewhere the loops are plain enough for the
+compiler to vectorise.
+
+the network file is little endian: the magic "RCNN", the hidden size N as u32, the feature weights as 768*N i16, the
+feature biases as N i16, the output weights as 2N i16 and the output bias as i32.
+
+configured with the environment or the .env file:
+NNUE_FILE  path of the network file, the classical evaluation is used if it is not set
+*/
+
+const MAGIC:&[u8; 4] = b"RCNN";
+pub const FEATURES:usize = 768;
+pub const MAX_HIDDEN_SIZE:usize = 2048; //keeps a broken size in a file from allocating too much, the output sums are i64
+const QA:i32 = 255;
+const QB:i32 = 64;
+const SCALE:i32 = 400;
+
+#[derive(Debug)]
+pub enum NnueError{
+    Io(String, std::io::Error),
+    Invalid(String),
+    NotConfigured(&'static str),
+}
+
+impl fmt::Display for NnueError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NnueError::Io(path, e) => write!(f, "could not read {path}: {e}"),
+            NnueError::Invalid(reason) => write!(f, "invalid network, {reason}"),
+            NnueError::NotConfigured(variable) => write!(f, "{variable} is not set"),
+        }
+    }
+}
+
+impl std::error::Error for NnueError{}
+
+//the index of a piece of color on square as seen from the side of perspective
+pub fn feature_index(perspective:usize, color:usize, piece:usize, square:usize) -> usize{
+    let relative_color = if color == perspective {0} else {1};
+    let relative_square = if perspective == WHITE {square} else {square ^ 56};
+    relative_color*384 + piece*64 + relative_square
+}
+
+//the hidden layer from both sides, and the pieces it was computed for so the next position can be updated from it
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct Accumulator{
+    values: [Vec<i16>; 2], //[perspective][neuron]
+    pieces: [[u64; 6]; 2],
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Network{
+    hidden_size: usize,
+    feature_weights: Vec<i16>, //[feature][neuron]
+    feature_biases: Vec<i16>,
+    output_weights: Vec<i16>, //the neurons of the side to move, then the neurons of the other side
+    output_bias: i32,
+}
+
+impl Network{
+    pub fn new(hidden_size:usize, feature_weights:Vec<i16>, feature_biases:Vec<i16>, output_weights:Vec<i16>, output_bias:i32) -> Result<Self, NnueError>{
+        if hidden_size == 0 || hidden_size > MAX_HIDDEN_SIZE {
+            return Err(NnueError::Invalid(format!("hidden size {hidden_size} is not between 1 and {MAX_HIDDEN_SIZE}")));
+        }
+        if feature_weights.len() != FEATURES*hidden_size || feature_biases.len() != hidden_size || output_weights.len() != 2*hidden_size {
+            return Err(NnueError::Invalid(format!("the weights do not fit hidden size {hidden_size}")));
+        }
+        Ok(Self { hidden_size, feature_weights, feature_biases, output_weights, output_bias })
+    }
+
+    pub fn hidden_size(&self) -> usize{
+        self.hidden_size
+    }
+
+    pub fn from_bytes(bytes:&[u8]) -> Result<Self, NnueError>{
+        if bytes.len() < 8 || &bytes[0..4] != MAGIC {
+            return Err(NnueError::Invalid("the file does not start with RCNN".to_string()));
+        }
+        let hidden_size = u32::from_le_bytes(bytes[4..8].try_into().unwrap()) as usize;
+        if hidden_size == 0 || hidden_size > MAX_HIDDEN_SIZE {
+            return Err(NnueError::Invalid(format!("hidden size {hidden_size} is not between 1 and {MAX_HIDDEN_SIZE}")));
+        }
+        let expected = 8 + 2*(FEATURES*hidden_size + hidden_size + 2*hidden_size) + 4;
+        if bytes.len() != expected {
+            return Err(NnueError::Invalid(format!("{} bytes for hidden size {hidden_size}, expected {expected}", bytes.len())));
+        }
+
+        let mut offset = 8;
+        let mut read_i16s = |count:usize| -> Vec<i16> {
+            let values = bytes[offset..offset + 2*count].chunks_exact(2).map(|pair| i16::from_le_bytes([pair[0], pair[1]])).collect();
+            offset += 2*count;
+            values
+        };
+        let feature_weights = read_i16s(FEATURES*hidden_size);
+        let feature_biases = read_i16s(hidden_size);
+        let output_weights = read_i16s(2*hidden_size);
+        let output_bias = i32::from_le_bytes(bytes[expected - 4..].try_into().unwrap());
+        Self::new(hidden_size, feature_weights, feature_biases, output_weights, output_bias)
+    }
+
+    #[cfg(test)]
+    pub fn to_bytes(&self) -> Vec<u8>{
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend((self.hidden_size as u32).to_le_bytes());
+        for value in self.feature_weights.iter().chain(&self.feature_biases).chain(&self.output_weights){
+            bytes.extend(value.to_le_bytes());
+        }
+        bytes.extend(self.output_bias.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_file(path:&str) -> Result<Self, NnueError>{
+        let bytes = std::fs::read(path).map_err(|e| NnueError::Io(path.to_string(), e))?;
+        Self::from_bytes(&bytes)
+    }
+
+    //loads the file of NNUE_FILE, see the top of the file
+    pub fn from_env() -> Result<Self, NnueError>{
+        let path = env::var("NNUE_FILE").map_err(|_| NnueError::NotConfigured("NNUE_FILE"))?;
+        Self::from_file(&path)
+    }
+
+    fn feature_weights(&self, feature:usize) -> &[i16]{
+        &self.feature_weights[feature*self.hidden_size..(feature + 1)*self.hidden_size]
+    }
+
+    //the accumulator of a position from scratch
+    pub fn refresh(&self, state:&BitBoardState, accumulator:&mut Accumulator){
+        let pieces = state.piece_bb();
+        for perspective in [BLACK, WHITE]{
+            let values = &mut accumulator.values[perspective];
+            values.clear();
+            values.extend_from_slice(&self.feature_biases);
+            for (color, color_pieces) in pieces.iter().enumerate(){
+                for (piece, &bb) in color_pieces.iter().enumerate(){
+                    let mut remaining = bb;
+                    while remaining != 0 {
+                        let weights = self.feature_weights(feature_index(perspective, color, piece, pop_lsb(&mut remaining)));
+                        values.iter_mut().zip(weights).for_each(|(value, weight)| *value = value.wrapping_add(*weight));
+                    }
+                }
+            }
+        }
+        accumulator.pieces = pieces;
+    }
+
+    //the accumulator of a position from the accumulator of the position before the move, only the pieces that moved,
+    //were taken or promoted change it
+    pub fn update(&self, previous:&Accumulator, state:&BitBoardState, accumulator:&mut Accumulator){
+        let pieces = state.piece_bb();
+        for perspective in [BLACK, WHITE]{
+            let values = &mut accumulator.values[perspective];
+            values.clear();
+            values.extend_from_slice(&previous.values[perspective]);
+            for color in [BLACK, WHITE]{
+                for (piece, (&before, &after)) in previous.pieces[color].iter().zip(&pieces[color]).enumerate(){
+                    let changed = before ^ after;
+                    let mut removed = changed & before;
+                    while removed != 0 {
+                        let weights = self.feature_weights(feature_index(perspective, color, piece, pop_lsb(&mut removed)));
+                        values.iter_mut().zip(weights).for_each(|(value, weight)| *value = value.wrapping_sub(*weight));
+                    }
+                    let mut added = changed & after;
+                    while added != 0 {
+                        let weights = self.feature_weights(feature_index(perspective, color, piece, pop_lsb(&mut added)));
+                        values.iter_mut().zip(weights).for_each(|(value, weight)| *value = value.wrapping_add(*weight));
+                    }
+                }
+            }
+        }
+        accumulator.pieces = pieces;
+    }
+
+    //the evaluation in centipawns from whites side
+    pub fn evaluate(&self, accumulator:&Accumulator, white_to_move:bool) -> i32{
+        let (us, them) = if white_to_move {(WHITE, BLACK)} else {(BLACK, WHITE)};
+        let (us_weights, them_weights) = self.output_weights.split_at(self.hidden_size);
+        let sum = crelu_dot(&accumulator.values[us], us_weights) + crelu_dot(&accumulator.values[them], them_weights);
+        let eval = ((sum + self.output_bias as i64)*SCALE as i64/(QA*QB) as i64) as i32;
+        if white_to_move {eval} else {-eval}
+    }
+}
+
+//the sum of the clipped relu of the values times the weights
+pub fn crelu_dot(values:&[i16], weights:&[i16]) -> i64{
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx2") {
+        //safe since the cpu supports avx2
+        return unsafe {crelu_dot_avx2(values, weights)};
+    }
+    crelu_dot_scalar(values, weights)
+}
+
+pub fn crelu_dot_scalar(values:&[i16], weights:&[i16]) -> i64{
+    values.iter().zip(weights).map(|(&value, &weight)| ((value as i32).clamp(0, QA)*weight as i32) as i64).sum()
+}
+
+//sixteen neurons at a time, madd multiplies pairs of i16 and adds every two products into an i32, which can not
+//overflow for two products. the i32 sums are widened to i64 before they are added up
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+fn crelu_dot_avx2(values:&[i16], weights:&[i16]) -> i64{
+    use std::arch::x86_64::*;
+
+    let length = values.len().min(weights.len());
+    let chunks = length/16;
+    let zero = _mm256_setzero_si256();
+    let max = _mm256_set1_epi16(QA as i16);
+    let mut sum = _mm256_setzero_si256();
+    for chunk in 0..chunks{
+        //the loads stay inside both slices as chunk*16 + 16 <= length
+        let (value, weight) = unsafe {(
+            _mm256_loadu_si256(values.as_ptr().add(chunk*16) as *const __m256i),
+            _mm256_loadu_si256(weights.as_ptr().add(chunk*16) as *const __m256i),
+        )};
+        let clipped = _mm256_min_epi16(_mm256_max_epi16(value, zero), max);
+        let products = _mm256_madd_epi16(clipped, weight);
+        sum = _mm256_add_epi64(sum, _mm256_cvtepi32_epi64(_mm256_castsi256_si128(products)));
+        sum = _mm256_add_epi64(sum, _mm256_cvtepi32_epi64(_mm256_extracti128_si256::<1>(products)));
+    }
+    let mut lanes = [0i64; 4];
+    unsafe {_mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, sum)};
+    lanes.iter().sum::<i64>() + crelu_dot_scalar(&values[chunks*16..length], &weights[chunks*16..length])
+}
+
+//an accumulator for every ply of the search, each one updated from the one of the ply before
+#[derive(Clone, Default)]
+pub struct AccumulatorStack{
+    accumulators: Vec<Accumulator>,
+}
+
+impl AccumulatorStack{
+    //the accumulator of the position at ply, the accumulator of ply - 1 has to be of the position before
+    pub fn update(&mut self, network:&Network, state:&BitBoardState, ply:usize){
+        if self.accumulators.len() <= ply {
+            self.accumulators.resize(ply + 1, Accumulator::default());
+        }
+        if ply == 0 {
+            network.refresh(state, &mut self.accumulators[0]);
+        }else{
+            let (previous, current) = self.accumulators.split_at_mut(ply);
+            network.update(&previous[ply - 1], state, &mut current[0]);
+        }
+    }
+
+    pub fn evaluate(&self, network:&Network, ply:usize, white_to_move:bool) -> i32{
+        network.evaluate(&self.accumulators[ply], white_to_move)
+    }
+}
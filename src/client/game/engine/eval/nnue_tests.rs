@@ -0,0 +1,156 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use crate::client::game::engine::board::ChessMove;
+    use crate::client::game::engine::eval::nnue::{crelu_dot, crelu_dot_scalar, feature_index, Accumulator, Network, NnueError, FEATURES, MAX_HIDDEN_SIZE};
+    use crate::client::game::engine::eval::params::EvalParams;
+    use crate::client::game::engine::state_bitboard::{BitBoardState, BLACK, KING, PAWN, WHITE};
+    use crate::client::game::engine::Engine;
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    //numbers between -range and range from a fixed seed
+    fn random_values(count:usize, range:i32, seed:u64) -> Vec<i16>{
+        let mut state = seed;
+        (0..count).map(|_| {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) as i32 % (range + 1)) as i16
+        }).collect()
+    }
+
+    fn random_network(hidden_size:usize) -> Network{
+        Network::new(
+            hidden_size,
+            random_values(FEATURES*hidden_size, 60, 1),
+            random_values(hidden_size, 100, 2),
+            random_values(2*hidden_size, 64, 3),
+            500,
+        ).unwrap()
+    }
+
+    fn network_eval(network:&Network, fen:&str) -> i32{
+        let state = BitBoardState::from_fen(fen).unwrap();
+        let mut accumulator = Accumulator::default();
+        network.refresh(&state, &mut accumulator);
+        network.evaluate(&accumulator, state.white_to_move())
+    }
+
+    #[test]
+    fn features(){
+        //a white pawn on e2 seen by white is a black pawn on e7 seen by black
+        assert_eq!(feature_index(WHITE, WHITE, PAWN, 12), feature_index(BLACK, BLACK, PAWN, 52));
+        assert_eq!(feature_index(WHITE, BLACK, KING, 60), feature_index(BLACK, WHITE, KING, 4));
+        assert_ne!(feature_index(WHITE, WHITE, PAWN, 12), feature_index(WHITE, BLACK, PAWN, 12));
+        assert_eq!(feature_index(BLACK, WHITE, KING, 0), FEATURES - 8);
+    }
+
+    #[test]
+    fn file(){
+        let network = random_network(24);
+        let bytes = network.to_bytes();
+        assert_eq!(Network::from_bytes(&bytes).unwrap(), network);
+
+        assert!(matches!(Network::from_bytes(&bytes[..bytes.len() - 1]), Err(NnueError::Invalid(_))));
+        let mut wrong_magic = bytes.clone();
+        wrong_magic[0] = b'X';
+        assert!(matches!(Network::from_bytes(&wrong_magic), Err(NnueError::Invalid(_))));
+        let mut wrong_size = bytes.clone();
+        wrong_size[4..8].copy_from_slice(&0u32.to_le_bytes());
+        assert!(matches!(Network::from_bytes(&wrong_size), Err(NnueError::Invalid(_))));
+        assert!(matches!(Network::from_file("/nonexistent/net.bin"), Err(NnueError::Io(_, _))));
+        assert!(Network::new(8, vec![0; 10], vec![0; 8], vec![0; 16], 0).is_err());
+    }
+
+    //the simd output layer gives the same sums as the plain one, with values outside the clipping range and a length
+    //that is not a multiple of sixteen
+    #[test]
+    fn output_layer(){
+        for length in [1, 16, 37, 256]{
+            let values = random_values(length, 400, 4).iter().map(|value| value - 100).collect::<Vec<i16>>();
+            let weights = random_values(length, 127, 5);
+            assert_eq!(crelu_dot(&values, &weights), crelu_dot_scalar(&values, &weights));
+        }
+        assert_eq!(crelu_dot_scalar(&[-5, 100, 300], &[7, 2, 1]), 200 + 255);
+    }
+
+    //the largest sums of the largest network do not fit an i32
+    #[test]
+    fn output_layer_extremes(){
+        let values = vec![i16::MAX; MAX_HIDDEN_SIZE];
+        for weight in [i16::MAX, i16::MIN]{
+            let weights = vec![weight; MAX_HIDDEN_SIZE];
+            let expected = MAX_HIDDEN_SIZE as i64*255*weight as i64;
+            assert_eq!(crelu_dot_scalar(&values, &weights), expected);
+            assert_eq!(crelu_dot(&values, &weights), expected);
+        }
+
+        //every feature weight at the clipping limit, for the king of each side
+        let mut feature_weights = vec![0; FEATURES*MAX_HIDDEN_SIZE];
+        for perspective in [BLACK, WHITE]{
+            for color in [BLACK, WHITE]{
+                let square = if color == WHITE {4} else {60};
+                let feature = feature_index(perspective, color, KING, square);
+                feature_weights[feature*MAX_HIDDEN_SIZE..(feature + 1)*MAX_HIDDEN_SIZE].fill(255);
+            }
+        }
+        let network = Network::new(MAX_HIDDEN_SIZE, feature_weights, vec![0; MAX_HIDDEN_SIZE], vec![i16::MAX; 2*MAX_HIDDEN_SIZE], 0).unwrap();
+        let sum = 2*MAX_HIDDEN_SIZE as i64*255*i16::MAX as i64;
+        setup_sliding_magics();
+        assert_eq!(network_eval(&network, "4k3/8/8/8/8/8/8/4K3 w - - 0 1") as i64, sum*400/(255*64));
+    }
+
+    //updating the accumulator move by move gives the same accumulator as computing it from scratch, through captures,
+    //castles, en passant and promotions
+    #[test]
+    fn incremental_updates(){
+        setup_sliding_magics();
+        let network = random_network(32);
+        let games = [
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", vec!["e2e4", "d7d5", "e4d5", "g8f6", "g1f3", "f6d5", "f1c4", "e7e6", "e1g1", "f8e7"]),
+            ("4k3/8/8/8/3p4/8/4P3/4K3 w - - 0 1", vec!["e2e4", "d4e3", "e1d1", "e3e2", "d1c2", "e2e1q"]),
+            ("r3k2r/1P6/8/8/8/8/8/4K3 w kq - 0 1", vec!["b7a8n", "e8g8"]),
+        ];
+        for (fen, moves) in games{
+            let mut state = BitBoardState::from_fen(fen).unwrap();
+            let mut accumulator = Accumulator::default();
+            network.refresh(&state, &mut accumulator);
+            for uci_move in moves{
+                state = state.perform_move(ChessMove::from_uci(uci_move, &state).unwrap());
+                let mut updated = Accumulator::default();
+                network.update(&accumulator, &state, &mut updated);
+                let mut refreshed = Accumulator::default();
+                network.refresh(&state, &mut refreshed);
+                assert_eq!(updated, refreshed, "{uci_move}");
+                accumulator = updated;
+            }
+        }
+    }
+
+    //both sides use the same weights, a mirrored position with the colors swapped has the opposite evaluation
+    #[test]
+    fn mirrored(){
+        setup_sliding_magics();
+        let network = random_network(32);
+        let pairs = [
+            ("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3", "rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 3"),
+            ("8/5k2/8/3P4/8/1K6/8/8 b - - 0 1", "8/8/1k6/8/3p4/8/5K2/8 w - - 0 1"),
+        ];
+        for (fen, mirrored) in pairs{
+            assert_eq!(network_eval(&network, fen), -network_eval(&network, mirrored));
+        }
+    }
+
+    //with a network the search uses it and still finds a legal move
+    #[test]
+    fn search(){
+        setup_sliding_magics();
+        let mut engine = Engine::new(3, 8, 1000, None, Arc::new(EvalParams::default()));
+        engine.set_network(Some(Arc::new(random_network(16))));
+        let mut state = BitBoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let result = engine.get_move_bb(state, &[state.hash()]);
+        let legal = state.gen_moves_legal().moves_vec();
+        assert!(legal.contains(result.chess_move()));
+        assert!(result.num_pos() > 0);
+    }
+}
@@ -0,0 +1,261 @@
+use std::{env, fmt};
+
+use super::pst::PieceSquareTables;
+use super::{tapered, Tapered};
+
+/*
+the parameters of the evaluation: the values of the pieces, the weights of every term and the piece square tables, in
+centipawns unless said otherwise. the built in parameters can be replaced by a text file, like the one the tuner writes.
+
+the file has a block for every list of numbers it changes, lists that are left out keep their built in values. a block
+is the name of the list, followed by mg or eg for lists with a middlegame and an endgame value, and then the numbers of
+the list, like "passed_pawn eg 0 10 15 25 45 75 120 0". the piece square tables are blocks like "knight mg" with 64
+numbers, see pst.rs. anything after a # is a comment. Display writes every list in the same format.
+
+the uci interface can change single numbers while the engine runs, every number outside the piece square tables is an
+option named after its block, with the index for lists of more than one number, like "passed_pawn eg 6".
+
+configured with the environment or the .env file:
+EVAL_PARAMS  path of the parameter file, the built in parameters are used if it is not set
+*/
+
+#[derive(Debug)]
+pub enum ParamsError{
+    Io(String, std::io::Error),
+    Invalid(String),
+    NotConfigured(&'static str),
+}
+
+impl fmt::Display for ParamsError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParamsError::Io(path, e) => write!(f, "could not read {path}: {e}"),
+            ParamsError::Invalid(reason) => write!(f, "invalid evaluation parameters, {reason}"),
+            ParamsError::NotConfigured(variable) => write!(f, "{variable} is not set"),
+        }
+    }
+}
+
+impl std::error::Error for ParamsError{}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct EvalParams{
+    //material
+    pub pawn_value: Tapered,
+    pub knight_value: Tapered,
+    pub bishop_value: Tapered,
+    pub rook_values: [Tapered; 10], //all rooks of a side by their number, two are worth as much as a queen
+    pub queen_value: Tapered,
+    pub knight_pawn_bonus: Tapered, //per knight and pawn on the board, knights are worth more in closed positions
+    pub bishop_pawn_bonus: Tapered, //per bishop and pawn on the board, bishops are worth more in open positions
+
+    //pieces
+    pub rook_file: Tapered, //per rook on a file without own pawns, and for two rooks on the same file or rank
+    pub mobility: [Tapered; 6], //per square a piece can move to, by piece type
+    pub threat_by_pawn: Tapered, //per enemy piece attacked by a pawn
+    pub threat_by_minor: Tapered, //per enemy rook or queen attacked by a knight or bishop
+    pub threat_by_rook: Tapered, //per enemy queen attacked by a rook
+    pub hanging: Tapered, //per piece attacked by the enemy and not defended
+
+    //pawns
+    pub doubled_pawn: Tapered,
+    pub isolated_pawn: Tapered, //per pawn without pawns on the files next to it
+    pub backward_pawn: Tapered, //per pawn that can not be defended by pawns and can not advance without being taken
+    pub supported_pawn: Tapered, //per pawn defended by a pawn
+    pub phalanx_pawn: Tapered, //per pawn with a pawn next to it on the same rank
+    pub passed_pawn: [Tapered; 8], //by the rank from the side of the pawn
+    pub enemy_king_distance: Tapered, //per square between the kings and the square in front of a passed pawn,
+    pub own_king_distance: Tapered, //times the rank above the third
+    pub unstoppable_pawn: Tapered, //a passed pawn the enemy king can not catch when the enemy only has pawns left
+
+    //king safety
+    pub king_shield: [Tapered; 2], //per own pawn on the files around the king, one and two ranks in front of it
+    pub pawn_storm: [Tapered; 4], //per enemy pawn on the files around the king, by how many ranks it is away
+    //attack units, added up into the danger the king is in
+    pub king_zone_attack_units: [i32; 6], //per square around the king an enemy piece type attacks
+    pub safe_check_units: [i32; 6], //per piece type that can check from a square the defender does not control
+    pub semi_open_file_units: i32, //per file around the king without own pawns
+    pub open_file_units: i32, //per file around the king without any pawns
+    pub max_attack_units: i32, //the danger grows quadratically with the attack units, up to this many units
+
+    pub pst: PieceSquareTables,
+}
+
+impl Default for EvalParams{
+    fn default() -> Self{
+        Self {
+            pawn_value: tapered(100, 120),
+            knight_value: tapered(300, 290),
+            bishop_value: tapered(320, 330),
+            rook_values: [0, 500, 900, 1300, 1700, 2100, 2500, 2900, 3300, 3600].map(|value| tapered(value, value)),
+            queen_value: tapered(900, 920),
+            knight_pawn_bonus: tapered(1, 1),
+            bishop_pawn_bonus: tapered(-1, -1),
+
+            rook_file: tapered(20, 10),
+            mobility: [tapered(0, 0), tapered(4, 4), tapered(5, 5), tapered(2, 4), tapered(1, 2), tapered(0, 0)],
+            threat_by_pawn: tapered(40, 30),
+            threat_by_minor: tapered(30, 30),
+            threat_by_rook: tapered(30, 30),
+            hanging: tapered(20, 15),
+
+            doubled_pawn: tapered(-30, -40),
+            isolated_pawn: tapered(-30, -40),
+            backward_pawn: tapered(-10, -10),
+            supported_pawn: tapered(8, 10),
+            phalanx_pawn: tapered(6, 8),
+            passed_pawn: [tapered(0, 0), tapered(5, 10), tapered(5, 15), tapered(10, 25), tapered(20, 45), tapered(35, 75), tapered(60, 120), tapered(0, 0)],
+            enemy_king_distance: tapered(0, 5),
+            own_king_distance: tapered(0, -2),
+            unstoppable_pawn: tapered(0, 500),
+
+            king_shield: [tapered(12, 0), tapered(6, 0)],
+            pawn_storm: [tapered(0, 0), tapered(-15, 0), tapered(-10, 0), tapered(-5, 0)],
+            king_zone_attack_units: [1, 2, 2, 3, 5, 0],
+            safe_check_units: [0, 6, 4, 6, 8, 0],
+            semi_open_file_units: 3,
+            open_file_units: 5,
+            max_attack_units: 60,
+
+            pst: PieceSquareTables::default(),
+        }
+    }
+}
+
+//a list with a middlegame and an endgame value is two blocks of the file
+fn tapered_blocks<'a>(blocks:&mut Vec<(String, Vec<&'a mut i32>)>, name:&str, values:impl IntoIterator<Item = &'a mut Tapered>){
+    let (mg, eg):(Vec<&mut i32>, Vec<&mut i32>) = values.into_iter().map(|Tapered { mg, eg }| (mg, eg)).unzip();
+    blocks.push((format!("{name} mg"), mg));
+    blocks.push((format!("{name} eg"), eg));
+}
+
+impl EvalParams{
+    //every list of numbers with its name in the file, in the order they are written
+    pub fn blocks_mut(&mut self) -> Vec<(String, Vec<&mut i32>)>{
+        self.blocks_with_tables_mut(true)
+    }
+
+    fn blocks_with_tables_mut(&mut self, tables:bool) -> Vec<(String, Vec<&mut i32>)>{
+        let mut blocks = Vec::new();
+        tapered_blocks(&mut blocks, "pawn_value", [&mut self.pawn_value]);
+        tapered_blocks(&mut blocks, "knight_value", [&mut self.knight_value]);
+        tapered_blocks(&mut blocks, "bishop_value", [&mut self.bishop_value]);
+        tapered_blocks(&mut blocks, "rook_values", &mut self.rook_values);
+        tapered_blocks(&mut blocks, "queen_value", [&mut self.queen_value]);
+        tapered_blocks(&mut blocks, "knight_pawn_bonus", [&mut self.knight_pawn_bonus]);
+        tapered_blocks(&mut blocks, "bishop_pawn_bonus", [&mut self.bishop_pawn_bonus]);
+
+        tapered_blocks(&mut blocks, "rook_file", [&mut self.rook_file]);
+        tapered_blocks(&mut blocks, "mobility", &mut self.mobility);
+        tapered_blocks(&mut blocks, "threat_by_pawn", [&mut self.threat_by_pawn]);
+        tapered_blocks(&mut blocks, "threat_by_minor", [&mut self.threat_by_minor]);
+        tapered_blocks(&mut blocks, "threat_by_rook", [&mut self.threat_by_rook]);
+        tapered_blocks(&mut blocks, "hanging", [&mut self.hanging]);
+
+        tapered_blocks(&mut blocks, "doubled_pawn", [&mut self.doubled_pawn]);
+        tapered_blocks(&mut blocks, "isolated_pawn", [&mut self.isolated_pawn]);
+        tapered_blocks(&mut blocks, "backward_pawn", [&mut self.backward_pawn]);
+        tapered_blocks(&mut blocks, "supported_pawn", [&mut self.supported_pawn]);
+        tapered_blocks(&mut blocks, "phalanx_pawn", [&mut self.phalanx_pawn]);
+        tapered_blocks(&mut blocks, "passed_pawn", &mut self.passed_pawn);
+        tapered_blocks(&mut blocks, "enemy_king_distance", [&mut self.enemy_king_distance]);
+        tapered_blocks(&mut blocks, "own_king_distance", [&mut self.own_king_distance]);
+        tapered_blocks(&mut blocks, "unstoppable_pawn", [&mut self.unstoppable_pawn]);
+
+        tapered_blocks(&mut blocks, "king_shield", &mut self.king_shield);
+        tapered_blocks(&mut blocks, "pawn_storm", &mut self.pawn_storm);
+        blocks.push(("king_zone_attack_units".to_string(), self.king_zone_attack_units.iter_mut().collect()));
+        blocks.push(("safe_check_units".to_string(), self.safe_check_units.iter_mut().collect()));
+        blocks.push(("semi_open_file_units".to_string(), vec![&mut self.semi_open_file_units]));
+        blocks.push(("open_file_units".to_string(), vec![&mut self.open_file_units]));
+        blocks.push(("max_attack_units".to_string(), vec![&mut self.max_attack_units]));
+
+        if tables {
+            blocks.extend(self.pst.blocks_mut());
+        }
+        blocks
+    }
+
+    //every number of the parameters, the order of the file
+    pub fn values_mut(&mut self) -> Vec<&mut i32>{
+        self.blocks_mut().into_iter().flat_map(|(_, values)| values).collect()
+    }
+
+    //every number but the piece square tables by a name of its own, the uci options of the engine
+    //a number of a list is named after the block with its index, like "passed_pawn eg 6"
+    pub fn options_mut(&mut self) -> Vec<(String, &mut i32)>{
+        let mut options = Vec::new();
+        for (name, values) in self.blocks_with_tables_mut(false){
+            if values.len() == 1 {
+                options.extend(values.into_iter().map(|value| (name.clone(), value)));
+            }else{
+                options.extend(values.into_iter().enumerate().map(|(i, value)| (format!("{name} {i}"), value)));
+            }
+        }
+        options
+    }
+
+    //changes the number of the option, see options_mut
+    pub fn set_option(&mut self, name:&str, value:i32) -> Result<(), ParamsError>{
+        let mut options = self.options_mut();
+        let (_, option) = options.iter_mut().find(|(option_name, _)| option_name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| ParamsError::Invalid(format!("'{name}' is not a parameter")))?;
+        **option = value;
+        Ok(())
+    }
+
+    //the built in parameters with the blocks of the text replacing them, see the top of the file
+    pub fn parse(text:&str) -> Result<Self, ParamsError>{
+        let mut params = Self::default();
+        let mut blocks = params.blocks_mut();
+        let mut words = text.lines()
+            .map(|line| line.split('#').next().unwrap())
+            .flat_map(str::split_whitespace);
+
+        while let Some(name) = words.next(){
+            let index = match blocks.iter().position(|(block_name, _)| block_name == name) {
+                Some(index) => index,
+                None => {
+                    let phase = words.next().unwrap_or("");
+                    let block_name = format!("{name} {phase}");
+                    blocks.iter().position(|(other_name, _)| *other_name == block_name)
+                        .ok_or_else(|| ParamsError::Invalid(format!("'{}' is not a parameter", block_name.trim_end())))?
+                }
+            };
+            let (block_name, values) = &mut blocks[index];
+            let size = values.len();
+            for (i, value) in values.iter_mut().enumerate(){
+                let word = words.next().ok_or_else(|| ParamsError::Invalid(format!("{block_name} has {i} values, expected {size}")))?;
+                **value = word.parse::<i32>().map_err(|_| ParamsError::Invalid(format!("'{word}' in {block_name} is not a number")))?;
+            }
+        }
+        drop(blocks);
+        Ok(params)
+    }
+
+    pub fn from_file(path:&str) -> Result<Self, ParamsError>{
+        let text = std::fs::read_to_string(path).map_err(|e| ParamsError::Io(path.to_string(), e))?;
+        Self::parse(&text)
+    }
+
+    //loads the file of EVAL_PARAMS, see the top of the file
+    pub fn from_env() -> Result<Self, ParamsError>{
+        let path = env::var("EVAL_PARAMS").map_err(|_| ParamsError::NotConfigured("EVAL_PARAMS"))?;
+        Self::from_file(&path)
+    }
+}
+
+//every block on its own lines, eight numbers to a line so the piece square tables look like a board
+impl fmt::Display for EvalParams{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut params = self.clone();
+        for (name, values) in params.blocks_mut(){
+            writeln!(f, "{name}")?;
+            for line in values.chunks(8){
+                let numbers:Vec<String> = line.iter().map(|value| format!("{:4}", **value)).collect();
+                writeln!(f, "{}", numbers.join(" "))?;
+            }
+        }
+        Ok(())
+    }
+}
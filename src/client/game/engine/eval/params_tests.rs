@@ -0,0 +1,84 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::eval::params::{EvalParams, ParamsError};
+    use crate::client::game::engine::eval::tapered;
+    use crate::client::game::engine::state_bitboard::{BLACK, KING, KNIGHT, WHITE};
+
+    //squares with a1 as 0
+    const A1:usize = 0;
+    const E4:usize = 28;
+    const E5:usize = 36;
+    const G1:usize = 6;
+
+    #[test]
+    fn parse(){
+        let text = "# a lighter queen\nqueen_value mg 850 # was 900\nking_zone_attack_units 1 1 1 1 1 0\npassed_pawn eg 0 1 2 3 4 5 6 0";
+        let params = EvalParams::parse(text).unwrap();
+        assert_eq!(params.queen_value, tapered(850, 920));
+        assert_eq!(params.king_zone_attack_units, [1, 1, 1, 1, 1, 0]);
+        assert_eq!(params.passed_pawn[6], tapered(60, 6));
+
+        //everything else keeps the built in values
+        let default = EvalParams::default();
+        assert_eq!(params.pawn_value, default.pawn_value);
+        assert_eq!(params.safe_check_units, default.safe_check_units);
+        assert_eq!(params.pst, default.pst);
+    }
+
+    #[test]
+    fn piece_square_tables(){
+        let text = "knight mg\n".to_string() + &(0..64).map(|i| i.to_string()).collect::<Vec<String>>().join(" ");
+        let params = EvalParams::parse(&text).unwrap();
+        //the diagram starts at a8
+        assert_eq!(params.pst.value(KNIGHT, WHITE, 56).mg, 0);
+        assert_eq!(params.pst.value(KNIGHT, WHITE, A1).mg, 56);
+        assert_eq!(params.pst.value(KNIGHT, WHITE, E4).mg, 36);
+        assert_eq!(params.pst.value(KNIGHT, BLACK, E5).mg, 36);
+        //the endgame table and the other pieces keep the built in values
+        let default = EvalParams::default();
+        assert_eq!(params.pst.value(KNIGHT, WHITE, E4).eg, default.pst.value(KNIGHT, WHITE, E4).eg);
+        assert_eq!(params.pst.value(KING, WHITE, G1), default.pst.value(KING, WHITE, G1));
+    }
+
+    //written parameters read back the same, every number is written once
+    #[test]
+    fn round_trip(){
+        let mut params = EvalParams::default();
+        for (i, value) in params.values_mut().into_iter().enumerate(){
+            *value = i as i32 - 500;
+        }
+        assert_eq!(EvalParams::parse(&params.to_string()).unwrap(), params);
+        assert_eq!(EvalParams::parse(&EvalParams::default().to_string()).unwrap(), EvalParams::default());
+        assert_eq!(EvalParams::parse("").unwrap(), EvalParams::default());
+    }
+
+    #[test]
+    fn errors(){
+        assert!(matches!(EvalParams::parse("dragon mg 1"), Err(ParamsError::Invalid(_))));
+        assert!(matches!(EvalParams::parse("knight opening 1"), Err(ParamsError::Invalid(_))));
+        assert!(matches!(EvalParams::parse("knight mg 1 2 3"), Err(ParamsError::Invalid(_))));
+        assert!(matches!(EvalParams::parse("knight eg 1 x"), Err(ParamsError::Invalid(_))));
+        assert!(matches!(EvalParams::parse("knight"), Err(ParamsError::Invalid(_))));
+        assert!(matches!(EvalParams::parse("pawn_value 100"), Err(ParamsError::Invalid(_))));
+        assert!(matches!(EvalParams::parse("open_file_units"), Err(ParamsError::Invalid(_))));
+        assert!(matches!(EvalParams::from_file("/nonexistent/params.txt"), Err(ParamsError::Io(_, _))));
+    }
+
+    //single numbers by name, the piece square tables are left out
+    #[test]
+    fn options(){
+        let mut params = EvalParams::default();
+        let names:Vec<String> = params.options_mut().into_iter().map(|(name, _)| name).collect();
+        assert!(names.contains(&"max_attack_units".to_string()));
+        assert!(names.contains(&"mobility mg 1".to_string()));
+        assert!(!names.iter().any(|name| name.starts_with("knight mg")));
+
+        params.set_option("mobility mg 1", 7).unwrap();
+        params.set_option("OPEN_FILE_UNITS", 9).unwrap();
+        assert_eq!(params.mobility[1], tapered(7, 4));
+        assert_eq!(params.open_file_units, 9);
+        assert!(matches!(params.set_option("mobility mg 6", 1), Err(ParamsError::Invalid(_))));
+        assert!(matches!(params.set_option("knight mg 0", 1), Err(ParamsError::Invalid(_))));
+    }
+}
@@ -0,0 +1,174 @@
+use super::Tapered;
+use super::params::EvalParams;
+use super::super::state_bitboard::bit_boards::{file_of, north_of, pop_lsb, south_of, FILE_A, FILE_H, NEIGHBOUR_FILES};
+use super::super::state_bitboard::{BISHOP, BLACK, KING, KNIGHT, QUEEN, ROOK, WHITE};
+
+/*
+pawn structure evaluation. the structure terms only depend on where the pawns are, so they are computed once for every
+pawn structure and kept in the pawn table, a small hash table owned by the engine. the terms that depend on the kings
+and pieces, how far the kings are from passed pawns and whether a passed pawn can be caught, are computed every time
+from the passed pawns the table keeps.
+*/
+
+const PAWN_TABLE_BITS:u32 = 14; //the table has 2^PAWN_TABLE_BITS entries
+
+//squares attacked by pawns of one color
+pub fn pawn_attacks(pawns:u64, color:usize) -> u64{
+    if color == WHITE {
+        ((pawns & !FILE_A) << 7) | ((pawns & !FILE_H) << 9)
+    }else{
+        ((pawns & !FILE_A) >> 9) | ((pawns & !FILE_H) >> 7)
+    }
+}
+
+//rank of the square as seen from the side of color, 0 for the first rank
+fn relative_rank(square:usize, color:usize) -> i32{
+    if color == WHITE {(square/8) as i32} else {7 - (square/8) as i32}
+}
+
+//all squares on the ranks in front of the square, as seen from the side of color
+fn ranks_ahead(square:usize, color:usize) -> u64{
+    if color == WHITE {
+        if square >= 56 {0} else {north_of(square + 8)}
+    }else{
+        if square < 8 {0} else {south_of(square - 8)}
+    }
+}
+
+//number of king moves between two squares
+fn distance(a:usize, b:usize) -> i32{
+    let file_distance = (a%8) as i32 - (b%8) as i32;
+    let rank_distance = (a/8) as i32 - (b/8) as i32;
+    file_distance.abs().max(rank_distance.abs())
+}
+
+//the result of evaluating a pawn structure
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct PawnEval{
+    pub scores: [Tapered; 2], //structure terms of both colors
+    pub passed: [u64; 2], //passed pawns of both colors
+}
+
+impl PawnEval{
+    //the structure terms from whites side
+    #[cfg(test)]
+    pub fn score(&self) -> Tapered{
+        self.scores[WHITE] - self.scores[BLACK]
+    }
+}
+
+//structure terms of the pawns of one color, and its passed pawns
+fn pawn_structure(pawns:u64, other_pawns:u64, color:usize, params:&EvalParams) -> (Tapered, u64){
+    let mut score = Tapered::default();
+    let mut passed = 0;
+
+    for (file, neighbour_files) in NEIGHBOUR_FILES.iter().enumerate(){
+        let pawns_on_file = u64::count_ones(file_of(file) & pawns) as i32;
+        if pawns_on_file == 0 {
+            continue;
+        }
+        score += params.doubled_pawn*(pawns_on_file - 1);
+        if neighbour_files & pawns == 0 {
+            score += params.isolated_pawn;
+        }
+    }
+
+    let other_attacks = pawn_attacks(other_pawns, 1 - color);
+    let mut remaining = pawns;
+    while remaining != 0 {
+        let square = pop_lsb(&mut remaining);
+        let file = square % 8;
+        let ahead = ranks_ahead(square, color);
+
+        //no enemy pawns in front on the same file or the files next to it, and no own pawn in front
+        if other_pawns & ahead & (file_of(file) | NEIGHBOUR_FILES[file]) == 0 && pawns & ahead & file_of(file) == 0 {
+            passed |= 1 << square;
+            score += params.passed_pawn[relative_rank(square, color) as usize];
+        }
+
+        let stop_square = if color == WHITE {square + 8} else {square - 8};
+        if pawns & NEIGHBOUR_FILES[file] & !ahead == 0 && other_attacks & (1 << stop_square) != 0 {
+            score += params.backward_pawn;
+        }
+    }
+
+    score += params.supported_pawn*u64::count_ones(pawns & pawn_attacks(pawns, color)) as i32;
+    let next_to_pawns = ((pawns & !FILE_H) << 1) | ((pawns & !FILE_A) >> 1);
+    score += params.phalanx_pawn*u64::count_ones(pawns & next_to_pawns) as i32;
+
+    (score, passed)
+}
+
+//evaluates a pawn structure without the pawn table
+pub fn evaluate_pawns(pawns:[u64; 2], params:&EvalParams) -> PawnEval{
+    let (white_score, white_passed) = pawn_structure(pawns[WHITE], pawns[BLACK], WHITE, params);
+    let (black_score, black_passed) = pawn_structure(pawns[BLACK], pawns[WHITE], BLACK, params);
+    let mut scores = [Tapered::default(); 2];
+    let mut passed = [0; 2];
+    scores[WHITE] = white_score;
+    scores[BLACK] = black_score;
+    passed[WHITE] = white_passed;
+    passed[BLACK] = black_passed;
+    PawnEval { scores, passed }
+}
+
+//terms of the passed pawns of one color that depend on the kings and pieces
+pub fn passed_pawn_score(passed:u64, pieces:&[u64; 6], other_pieces:&[u64; 6], color:usize, to_move:bool, params:&EvalParams) -> Tapered{
+    let mut score = Tapered::default();
+    let king = u64::trailing_zeros(pieces[KING]) as usize;
+    let other_king = u64::trailing_zeros(other_pieces[KING]) as usize;
+    let other_has_pieces = other_pieces[KNIGHT] | other_pieces[BISHOP] | other_pieces[ROOK] | other_pieces[QUEEN] != 0;
+
+    let mut remaining = passed;
+    while remaining != 0 {
+        let square = pop_lsb(&mut remaining);
+        let rank = relative_rank(square, color);
+        let stop_square = if color == WHITE {square + 8} else {square - 8};
+
+        if rank > 2 {
+            score += params.enemy_king_distance*(distance(other_king, stop_square)*(rank - 2));
+            score += params.own_king_distance*(distance(king, stop_square)*(rank - 2));
+        }
+
+        //the rule of the square, a pawn on its first move can move two squares
+        if !other_has_pieces {
+            let promotion_square = if color == WHITE {56 + square % 8} else {square % 8};
+            let pawn_distance = (7 - rank).min(5);
+            let king_distance = distance(other_king, promotion_square) - if to_move {0} else {1};
+            if king_distance > pawn_distance {
+                score += params.unstoppable_pawn;
+            }
+        }
+    }
+    score
+}
+
+#[derive(Clone, Copy, Default)]
+struct PawnEntry{
+    pawns: [u64; 2],
+    eval: PawnEval,
+}
+
+//pawn structures that were evaluated before, the newest structure replaces the old one with the same index
+#[derive(Clone)]
+pub struct PawnTable{
+    entries: Vec<PawnEntry>,
+}
+
+impl Default for PawnTable{
+    fn default() -> Self{
+        Self { entries: vec![PawnEntry::default(); 1 << PAWN_TABLE_BITS] }
+    }
+}
+
+impl PawnTable{
+    pub fn probe(&mut self, pawns:[u64; 2], params:&EvalParams) -> PawnEval{
+        let key = (pawns[WHITE] ^ pawns[BLACK].rotate_left(32)).wrapping_mul(0x9E3779B97F4A7C15);
+        let entry = &mut self.entries[(key >> (64 - PAWN_TABLE_BITS)) as usize];
+        //without pawns the empty entry is right as well
+        if entry.pawns != pawns {
+            *entry = PawnEntry { pawns, eval: evaluate_pawns(pawns, params) };
+        }
+        entry.eval
+    }
+}
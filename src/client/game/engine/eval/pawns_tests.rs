@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::eval::params::EvalParams;
+    use crate::client::game::engine::eval::pawns::{evaluate_pawns, passed_pawn_score, PawnEval, PawnTable};
+    use crate::client::game::engine::eval::Tapered;
+    use crate::client::game::engine::state_bitboard::{BitBoardState, BLACK, PAWN, WHITE};
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    fn pawns(fen:&str) -> [u64; 2]{
+        let pieces = BitBoardState::from_fen(fen).unwrap().piece_bb();
+        [pieces[BLACK][PAWN], pieces[WHITE][PAWN]]
+    }
+
+    fn pawn_eval(fen:&str) -> PawnEval{
+        evaluate_pawns(pawns(fen), &EvalParams::default())
+    }
+
+    fn squares(names:&[&str]) -> u64{
+        names.iter().map(|name| {
+            let bytes = name.as_bytes();
+            1u64 << ((bytes[1] - b'1')*8 + bytes[0] - b'a')
+        }).fold(0, |all, square| all | square)
+    }
+
+    #[test]
+    fn passed_pawns(){
+        setup_sliding_magics();
+        //the d pawn is stopped by the pawn on e6 next to it, the a and h pawns are passed
+        let eval = pawn_eval("4k3/8/4p3/8/P2P3P/8/8/4K3 w - - 0 1");
+        assert_eq!(eval.passed[WHITE], squares(&["a4", "h4"]));
+        assert_eq!(eval.passed[BLACK], 0);
+
+        //only the front pawn of doubled pawns is passed
+        let eval = pawn_eval("4k3/8/8/2P5/2P5/8/6p1/4K3 w - - 0 1");
+        assert_eq!(eval.passed[WHITE], squares(&["c5"]));
+        assert_eq!(eval.passed[BLACK], squares(&["g2"]));
+
+        //further pawns are worth more
+        let far = pawn_eval("4k3/P7/8/8/8/8/8/4K3 w - - 0 1");
+        let near = pawn_eval("4k3/8/8/8/8/P7/8/4K3 w - - 0 1");
+        assert!(far.score().eg > near.score().eg);
+    }
+
+    //the pawn structures are mirrored, the scores are the same with the other sign
+    #[test]
+    fn mirrored(){
+        setup_sliding_magics();
+        let white = pawn_eval("4k3/p6p/1p2p3/8/P2P3P/2P5/5PP1/4K3 w - - 0 1");
+        let black = pawn_eval("4k3/5pp1/2p5/p2p3p/8/1P2P3/P6P/4K3 b - - 0 1");
+        assert_eq!(white.score(), Tapered::default() - black.score());
+        assert_eq!(white.passed[WHITE].swap_bytes(), black.passed[BLACK]);
+    }
+
+    #[test]
+    fn structure(){
+        setup_sliding_magics();
+        let score = |fen:&str| pawn_eval(fen).score();
+
+        //doubled and isolated pawns against healthy ones, black keeps the same pawns
+        let healthy = score("4k3/pp6/8/8/8/8/1PP5/4K3 w - - 0 1");
+        assert!(score("4k3/pp6/8/8/8/2P5/2P5/4K3 w - - 0 1").mg < healthy.mg);
+        assert!(score("4k3/pp6/8/8/8/8/P1P5/4K3 w - - 0 1").mg < healthy.mg);
+
+        //a pawn defended by a pawn, and two pawns next to each other
+        let apart = score("4k3/p6p/8/8/8/8/2P2P2/4K3 w - - 0 1");
+        assert!(score("4k3/p6p/8/8/8/3P4/2P5/4K3 w - - 0 1").mg > apart.mg);
+        assert!(score("4k3/p6p/8/8/8/8/2PP4/4K3 w - - 0 1").mg > apart.mg);
+
+        //the d pawn can not move without being taken by the pawn on e5 and has no pawns behind it on the c file
+        let backward = score("4k3/8/5p2/4p3/2P5/3P4/8/4K3 w - - 0 1");
+        let free = score("4k3/5p2/4p3/8/2P5/3P4/8/4K3 w - - 0 1");
+        assert!(backward.mg < free.mg);
+    }
+
+    #[test]
+    fn pawn_table(){
+        setup_sliding_magics();
+        let mut table = PawnTable::default();
+        for fen in ["4k3/p6p/1p2p3/8/P2P3P/2P5/5PP1/4K3 w - - 0 1", "4k3/8/8/8/8/8/8/4K3 w - - 0 1", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"]{
+            let expected = evaluate_pawns(pawns(fen), &EvalParams::default());
+            assert_eq!(table.probe(pawns(fen), &EvalParams::default()), expected);
+            assert_eq!(table.probe(pawns(fen), &EvalParams::default()), expected);
+        }
+    }
+
+    #[test]
+    fn kings_and_passed_pawns(){
+        setup_sliding_magics();
+        let score = |fen:&str| {
+            let state = BitBoardState::from_fen(fen).unwrap();
+            let pieces = state.piece_bb();
+            let passed = evaluate_pawns([pieces[BLACK][PAWN], pieces[WHITE][PAWN]], &EvalParams::default()).passed;
+            passed_pawn_score(passed[WHITE], &pieces[WHITE], &pieces[BLACK], WHITE, state.white_to_move(), &EvalParams::default())
+        };
+
+        //the black king is outside the square of the pawn
+        let unstoppable = score("5k2/8/8/8/P7/8/8/4K3 w - - 0 1");
+        //with black to move the king gets into the square
+        let caught = score("5k2/8/8/8/P7/8/8/4K3 b - - 0 1");
+        //a rook can stop the pawn
+        let rook = score("r4k2/8/8/8/P7/8/8/4K3 w - - 0 1");
+        assert!(unstoppable.eg > caught.eg + 400, "{unstoppable:?} {caught:?}");
+        assert_eq!(caught, rook);
+
+        //the own king in front of the pawn helps, the enemy king in front stops it
+        let escorted = score("8/8/7k/1K6/P7/8/8/8 b - - 0 1");
+        let blocked = score("8/8/k7/8/P7/8/8/7K b - - 0 1");
+        assert!(escorted.eg > blocked.eg, "{escorted:?} {blocked:?}");
+    }
+}
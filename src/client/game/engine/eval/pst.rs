@@ -0,0 +1,196 @@
+use super::{tapered, Tapered};
+use super::super::state_bitboard::{BLACK, KING, PAWN};
+
+/*
+piece square tables, a middlegame and an endgame bonus in centipawns for every piece on every square. the tables are
+written from whites side, black uses the same tables mirrored from rank 1 to rank 8.
+
+the tables are part of the evaluation parameters and can be replaced by the parameter file, where every table is a
+block like "knight mg" or "king eg" with 64 numbers laid out like a board diagram: rank 8 first, every rank from the a
+file to the h file.
+*/
+
+//in the order of the piece indices
+const PIECE_NAMES:[&str; 6] = ["pawn", "knight", "bishop", "rook", "queen", "king"];
+
+//the built in tables as board diagrams, rank 8 first
+const DEFAULT_MG:[[i32; 64]; 6] = [
+    [//pawn
+          0,   0,   0,   0,   0,   0,   0,   0,
+         50,  50,  50,  50,  50,  50,  50,  50,
+         10,  10,  20,  30,  30,  20,  10,  10,
+          5,   5,  10,  25,  25,  10,   5,   5,
+          0,   0,   0,  20,  20,   0,   0,   0,
+          5,  -5, -10,   0,   0, -10,  -5,   5,
+          5,  10,  10, -20, -20,  10,  10,   5,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [//knight
+        -50, -40, -30, -30, -30, -30, -40, -50,
+        -40, -20,   0,   0,   0,   0, -20, -40,
+        -30,   0,  10,  15,  15,  10,   0, -30,
+        -30,   5,  15,  20,  20,  15,   5, -30,
+        -30,   0,  15,  20,  20,  15,   0, -30,
+        -30,   5,  10,  15,  15,  10,   5, -30,
+        -40, -20,   0,   5,   5,   0, -20, -40,
+        -50, -40, -30, -30, -30, -30, -40, -50,
+    ],
+    [//bishop
+        -20, -10, -10, -10, -10, -10, -10, -20,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -10,   0,   5,  10,  10,   5,   0, -10,
+        -10,   5,   5,  10,  10,   5,   5, -10,
+        -10,   0,  10,  10,  10,  10,   0, -10,
+        -10,  10,  10,  10,  10,  10,  10, -10,
+        -10,   5,   0,   0,   0,   0,   5, -10,
+        -20, -10, -10, -10, -10, -10, -10, -20,
+    ],
+    [//rook
+          0,   0,   0,   0,   0,   0,   0,   0,
+          5,  10,  10,  10,  10,  10,  10,   5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+         -5,   0,   0,   0,   0,   0,   0,  -5,
+          0,   0,   0,   5,   5,   0,   0,   0,
+    ],
+    [//queen
+        -20, -10, -10,  -5,  -5, -10, -10, -20,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -10,   0,   5,   5,   5,   5,   0, -10,
+         -5,   0,   5,   5,   5,   5,   0,  -5,
+          0,   0,   5,   5,   5,   5,   0,  -5,
+        -10,   5,   5,   5,   5,   5,   0, -10,
+        -10,   0,   5,   0,   0,   0,   0, -10,
+        -20, -10, -10,  -5,  -5, -10, -10, -20,
+    ],
+    [//king, behind the pawns
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -30, -40, -40, -50, -50, -40, -40, -30,
+        -20, -30, -30, -40, -40, -30, -30, -20,
+        -10, -20, -20, -20, -20, -20, -20, -10,
+         20,  20,   0,   0,   0,   0,  20,  20,
+         20,  30,  10,   0,   0,  10,  30,  20,
+    ],
+];
+
+const DEFAULT_EG:[[i32; 64]; 6] = [
+    [//pawn, the closer to promoting the better
+          0,   0,   0,   0,   0,   0,   0,   0,
+         80,  80,  80,  80,  80,  80,  80,  80,
+         50,  50,  50,  50,  50,  50,  50,  50,
+         30,  30,  30,  30,  30,  30,  30,  30,
+         15,  15,  15,  15,  15,  15,  15,  15,
+          5,   5,   5,   5,   5,   5,   5,   5,
+          0,   0,   0,   0,   0,   0,   0,   0,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [//knight
+        -50, -40, -30, -30, -30, -30, -40, -50,
+        -40, -20,   0,   0,   0,   0, -20, -40,
+        -30,   0,  10,  15,  15,  10,   0, -30,
+        -30,   5,  15,  20,  20,  15,   5, -30,
+        -30,   0,  15,  20,  20,  15,   0, -30,
+        -30,   5,  10,  15,  15,  10,   5, -30,
+        -40, -20,   0,   5,   5,   0, -20, -40,
+        -50, -40, -30, -30, -30, -30, -40, -50,
+    ],
+    [//bishop
+        -20, -10, -10, -10, -10, -10, -10, -20,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -10,   0,   5,  10,  10,   5,   0, -10,
+        -10,   5,   5,  10,  10,   5,   5, -10,
+        -10,   0,  10,  10,  10,  10,   0, -10,
+        -10,  10,  10,  10,  10,  10,  10, -10,
+        -10,   5,   0,   0,   0,   0,   5, -10,
+        -20, -10, -10, -10, -10, -10, -10, -20,
+    ],
+    [//rook
+          0,   0,   0,   0,   0,   0,   0,   0,
+         20,  20,  20,  20,  20,  20,  20,  20,
+          0,   0,   0,   0,   0,   0,   0,   0,
+          0,   0,   0,   0,   0,   0,   0,   0,
+          0,   0,   0,   0,   0,   0,   0,   0,
+          0,   0,   0,   0,   0,   0,   0,   0,
+          0,   0,   0,   0,   0,   0,   0,   0,
+          0,   0,   0,   0,   0,   0,   0,   0,
+    ],
+    [//queen
+        -20, -10, -10,  -5,  -5, -10, -10, -20,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -10,   0,   5,   5,   5,   5,   0, -10,
+         -5,   0,   5,   5,   5,   5,   0,  -5,
+         -5,   0,   5,   5,   5,   5,   0,  -5,
+        -10,   0,   5,   5,   5,   5,   0, -10,
+        -10,   0,   0,   0,   0,   0,   0, -10,
+        -20, -10, -10,  -5,  -5, -10, -10, -20,
+    ],
+    [//king, in the center
+        -50, -40, -30, -20, -20, -30, -40, -50,
+        -30, -20, -10,   0,   0, -10, -20, -30,
+        -30, -10,  20,  30,  30,  20, -10, -30,
+        -30, -10,  30,  40,  40,  30, -10, -30,
+        -30, -10,  30,  40,  40,  30, -10, -30,
+        -30, -10,  20,  30,  30,  20, -10, -30,
+        -30, -30,   0,   0,   0,   0, -30, -30,
+        -50, -30, -30, -30, -30, -30, -30, -50,
+    ],
+];
+
+//a board diagram, rank 8 first, is mirrored into squares with a1 as square 0
+fn diagram_square(index:usize) -> usize{
+    index ^ 56
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct PieceSquareTables{
+    tables: [[Tapered; 64]; 6], //[piece][square] for white
+}
+
+impl Default for PieceSquareTables{
+    fn default() -> Self{
+        let mut tables = [[Tapered::default(); 64]; 6];
+        for piece in PAWN..=KING{
+            for index in 0..64{
+                tables[piece][diagram_square(index)] = tapered(DEFAULT_MG[piece][index], DEFAULT_EG[piece][index]);
+            }
+        }
+        Self { tables }
+    }
+}
+
+impl PieceSquareTables{
+    //the tables as blocks of the parameter file, named like "knight mg", with the values in the order of a board diagram
+    pub fn blocks_mut(&mut self) -> Vec<(String, Vec<&mut i32>)>{
+        let mut blocks = Vec::new();
+        for (table, name) in self.tables.iter_mut().zip(PIECE_NAMES){
+            //rank 8 first
+            let (mg, eg):(Vec<&mut i32>, Vec<&mut i32>) = table.chunks_mut(8).rev().flatten().map(|Tapered { mg, eg }| (mg, eg)).unzip();
+            blocks.push((format!("{name} mg"), mg));
+            blocks.push((format!("{name} eg"), eg));
+        }
+        blocks
+    }
+
+    pub fn value(&self, piece:usize, color:usize, square:usize) -> Tapered{
+        let square = if color == BLACK {square ^ 56} else {square};
+        self.tables[piece][square]
+    }
+
+    //sum of the bonuses of all pieces of one color
+    pub fn score(&self, pieces:&[u64; 6], color:usize) -> Tapered{
+        let mut score = Tapered::default();
+        for (piece, &bb) in pieces.iter().enumerate(){
+            let mut remaining = bb;
+            while remaining != 0 {
+                let square = remaining.trailing_zeros() as usize;
+                remaining &= remaining - 1;
+                score += self.value(piece, color, square);
+            }
+        }
+        score
+    }
+}
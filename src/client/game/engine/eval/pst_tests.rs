@@ -0,0 +1,62 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::eval::pawns::PawnTable;
+    use crate::client::game::engine::eval::params::EvalParams;
+    use crate::client::game::engine::eval::pst::PieceSquareTables;
+    use crate::client::game::engine::eval::{evaluate, tapered};
+    use crate::client::game::engine::state_bitboard::{BitBoardState, BLACK, KING, KNIGHT, PAWN, WHITE};
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    //squares with a1 as 0
+    const A1:usize = 0;
+    const C3:usize = 18;
+    const E2:usize = 12;
+    const E4:usize = 28;
+    const E5:usize = 36;
+    const E7:usize = 52;
+    const G1:usize = 6;
+    const G8:usize = 62;
+
+    #[test]
+    fn mirrored(){
+        let pst = PieceSquareTables::default();
+        for piece in PAWN..=KING{
+            for square in 0..64{
+                assert_eq!(pst.value(piece, WHITE, square), pst.value(piece, BLACK, square ^ 56));
+            }
+        }
+        assert_eq!(pst.value(PAWN, WHITE, E4), pst.value(PAWN, BLACK, E5));
+        assert_eq!(pst.value(PAWN, WHITE, E2), pst.value(PAWN, BLACK, E7));
+        assert_eq!(pst.value(KING, WHITE, G1), pst.value(KING, BLACK, G8));
+    }
+
+    #[test]
+    fn default_tables(){
+        let pst = PieceSquareTables::default();
+        //a knight in the center is better than on c3, and on c3 better than in the corner
+        assert!(pst.value(KNIGHT, WHITE, E4).mg > pst.value(KNIGHT, WHITE, C3).mg);
+        assert!(pst.value(KNIGHT, WHITE, C3).mg > pst.value(KNIGHT, WHITE, A1).mg);
+        //the king hides in the middlegame and comes out in the endgame
+        assert!(pst.value(KING, WHITE, G1).mg > pst.value(KING, WHITE, E4).mg);
+        assert!(pst.value(KING, WHITE, G1).eg < pst.value(KING, WHITE, E4).eg);
+        //pawns are worth more the further they are
+        assert!(pst.value(PAWN, WHITE, E7).eg > pst.value(PAWN, WHITE, E4).eg);
+
+        let start = BitBoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let pieces = start.piece_bb();
+        assert_eq!(pst.score(&pieces[WHITE], WHITE), pst.score(&pieces[BLACK], BLACK));
+    }
+
+    //the tables change the evaluation, a knight on the rim is dim
+    #[test]
+    fn evaluation(){
+        setup_sliding_magics();
+        let state = BitBoardState::from_fen("4k3/8/8/8/8/8/4P3/N3K3 w - - 0 1").unwrap();
+        let default = EvalParams::default();
+        let text = "knight mg\n".to_string() + &["-100"; 64].join(" ") + "\nknight eg\n" + &["-100"; 64].join(" ");
+        let rim = EvalParams::parse(&text).unwrap();
+        assert_eq!(rim.pst.value(KNIGHT, WHITE, A1), tapered(-100, -100));
+        assert!(evaluate(&state, &rim, &mut PawnTable::default()) < evaluate(&state, &default, &mut PawnTable::default()));
+    }
+}
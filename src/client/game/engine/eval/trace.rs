@@ -0,0 +1,66 @@
+use std::fmt;
+
+use super::{Tapered, MAX_PHASE};
+use super::super::state_bitboard::{BLACK, WHITE};
+
+/*
+every term of the classical evaluation for both colors, to see why the evaluation likes a position. the terms are
+indexed by color like piece_bb and every term is what it adds to the score of that color, so the evaluation is the
+sum of the white terms minus the sum of the black terms, tapered by the phase and scaled down when the side ahead can
+not win. evaluate in eval.rs is the eval of the trace, so the trace can never disagree with the search.
+*/
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct EvalTrace{
+    pub material: [Tapered; 2],
+    pub placement: [Tapered; 2], //piece square tables
+    pub pawn_structure: [Tapered; 2],
+    pub passed_pawns: [Tapered; 2], //king distances and unstoppable pawns, the rank bonus is part of the structure
+    pub rooks: [Tapered; 2],
+    pub mobility: [Tapered; 2],
+    pub threats: [Tapered; 2],
+    pub hanging: [Tapered; 2], //enemy pieces that are attacked and not defended
+    pub king_safety: [Tapered; 2],
+    pub phase: i32, //0 with all pieces on the board, MAX_PHASE with only kings and pawns
+    pub scale: i32, //how much of the advantage the side ahead can convert, out of 16
+    pub eval: i32, //centipawns from whites side
+}
+
+impl EvalTrace{
+    pub fn terms(&self) -> [(&'static str, [Tapered; 2]); 9]{
+        [
+            ("material", self.material),
+            ("placement", self.placement),
+            ("pawn structure", self.pawn_structure),
+            ("passed pawns", self.passed_pawns),
+            ("rooks", self.rooks),
+            ("mobility", self.mobility),
+            ("threats", self.threats),
+            ("hanging", self.hanging),
+            ("king safety", self.king_safety),
+        ]
+    }
+
+    //the sum of the terms from whites side, before the phase and the scale
+    pub fn total(&self) -> Tapered{
+        self.terms().iter().fold(Tapered::default(), |total, (_, term)| total + term[WHITE] - term[BLACK])
+    }
+}
+
+impl fmt::Display for EvalTrace{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{:<16}{:>14}{:>14}{:>14}", "term", "white mg eg", "black mg eg", "total mg eg")?;
+        let row = |f: &mut fmt::Formatter<'_>, name:&str, white:Tapered, black:Tapered| -> fmt::Result {
+            let total = white - black;
+            writeln!(f, "{:<16}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}", name, white.mg, white.eg, black.mg, black.eg, total.mg, total.eg)
+        };
+        for (name, term) in self.terms(){
+            row(f, name, term[WHITE], term[BLACK])?;
+        }
+        let sum = |color:usize| self.terms().iter().fold(Tapered::default(), |total, (_, term)| total + term[color]);
+        row(f, "sum", sum(WHITE), sum(BLACK))?;
+        writeln!(f, "phase {} of {}, tapered {}", self.phase, MAX_PHASE, self.total().taper(self.phase))?;
+        writeln!(f, "scale {} of 16", self.scale)?;
+        write!(f, "eval {} from whites side", self.eval)
+    }
+}
@@ -0,0 +1,74 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::eval::params::EvalParams;
+    use crate::client::game::engine::eval::pawns::PawnTable;
+    use crate::client::game::engine::eval::trace::EvalTrace;
+    use crate::client::game::engine::eval::{evaluate, trace, Tapered};
+    use crate::client::game::engine::state_bitboard::{BitBoardState, BLACK, WHITE};
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    fn trace_of(fen:&str) -> EvalTrace{
+        trace(&BitBoardState::from_fen(fen).unwrap(), &EvalParams::default(), &mut PawnTable::default())
+    }
+
+    //the trace adds up to the evaluation the search uses
+    #[test]
+    fn sums(){
+        setup_sliding_magics();
+        let params = EvalParams::default();
+        let mut pawn_table = PawnTable::default();
+        for fen in [
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+            "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+        ]{
+            let state = BitBoardState::from_fen(fen).unwrap();
+            let trace = trace(&state, &params, &mut pawn_table);
+            assert_eq!(trace.eval, evaluate(&state, &params, &mut pawn_table));
+            assert_eq!(trace.eval, trace.total().taper(trace.phase)*trace.scale/16);
+            assert!(trace.to_string().ends_with(&format!("eval {} from whites side", trace.eval)));
+        }
+    }
+
+    #[test]
+    fn terms(){
+        setup_sliding_magics();
+        let params = EvalParams::default();
+
+        //the starting position is the same for both sides
+        let start = trace_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(start.total(), Tapered::default());
+        for (name, term) in start.terms(){
+            assert_eq!(term[WHITE], term[BLACK], "{name}");
+        }
+
+        //an extra knight
+        let knight = trace_of("4k3/pppp4/8/8/8/8/PPPP4/1N2K3 w - - 0 1");
+        assert_eq!(knight.material[WHITE] - knight.material[BLACK], params.knight_value + params.knight_pawn_bonus*8);
+        assert_eq!(knight.material[BLACK], params.pawn_value*4);
+
+        //the black knight is attacked by the queen and not defended
+        let hanging = trace_of("4k3/8/8/3n4/8/8/8/3QK3 w - - 0 1");
+        assert_eq!(hanging.hanging[WHITE], params.hanging);
+        assert_eq!(hanging.hanging[BLACK], Tapered::default());
+
+        //only white has a passed pawn, and the black king can not catch it
+        let passed = trace_of("k7/8/8/8/7P/8/8/K7 w - - 0 1");
+        assert_eq!(passed.passed_pawns[BLACK], Tapered::default());
+        assert!(passed.passed_pawns[WHITE].eg > 0);
+        assert_eq!(passed.pawn_structure[WHITE], params.passed_pawn[3] + params.isolated_pawn);
+    }
+
+    //a lone knight can not win
+    #[test]
+    fn scale(){
+        setup_sliding_magics();
+        let trace = trace_of("4k3/8/8/8/8/8/8/4KN2 w - - 0 1");
+        assert!(trace.total().taper(trace.phase) > 0);
+        assert_eq!(trace.scale, 0);
+        assert_eq!(trace.eval, 0);
+    }
+}
@@ -0,0 +1,166 @@
+use super::evaluate;
+use super::params::EvalParams;
+use super::pawns::PawnTable;
+use super::super::state_bitboard::BitBoardState;
+
+/*
+texel tuning of the evaluation parameters. every position of the training set is labelled with the result of the game
+it was played in, and the evaluation of the position is turned into an expected result by a sigmoid. the tuner fits
+the parameters by minimising the mean squared difference between the expected and the real results: it changes every
+parameter one point up and down and keeps the change when the error goes down, until no change helps anymore.
+
+the positions should be quiet, without captures or checks waiting to happen, since the static evaluation can not see
+those. a line of the position file is a fen followed by the result from whites side, as 1-0, 0-1 or 1/2-1/2, or as
+[1.0], [0.0] or [0.5]. epd lines with the result as c9 "1-0"; work as well, and so do the "<fen> | <score> | <result>"
+lines of the self play data, where the score is left out.
+*/
+
+pub struct TuningPosition{
+    pub state: BitBoardState,
+    pub result: f64, //1 for a white win, 0.5 for a draw, 0 for a black win
+}
+
+//the position and the result of a line of the position file
+pub fn parse_position(line:&str) -> Result<TuningPosition, String>{
+    //self play data
+    if let [fen, _score, result] = line.split('|').map(str::trim).collect::<Vec<&str>>()[..] {
+        let result = match result {
+            "1.0" | "1" => 1.0,
+            "0.0" | "0" => 0.0,
+            "0.5" => 0.5,
+            other => return Err(format!("'{other}' is not a result")),
+        };
+        let state = BitBoardState::from_fen(fen).map_err(|e| e.to_string())?;
+        return Ok(TuningPosition { state, result });
+    }
+
+    let mut fields:Vec<&str> = line.split_whitespace().collect();
+    let last = fields.pop().ok_or("empty line")?;
+    let result = match last.trim_end_matches(';').trim_matches('"') {
+        "1-0" | "[1.0]" | "[1]" => 1.0,
+        "0-1" | "[0.0]" | "[0]" => 0.0,
+        "1/2-1/2" | "[0.5]" => 0.5,
+        other => return Err(format!("'{other}' is not a result")),
+    };
+    if fields.last() == Some(&"c9") {
+        fields.pop();
+    }
+    let state = BitBoardState::from_fen(&fields.join(" ")).map_err(|e| e.to_string())?;
+    Ok(TuningPosition { state, result })
+}
+
+//the positions of a position file, and the line numbers and errors of the lines that could not be read
+pub fn read_positions(text:&str) -> (Vec<TuningPosition>, Vec<(usize, String)>){
+    let mut positions = Vec::new();
+    let mut errors = Vec::new();
+    for (i, line) in text.lines().enumerate(){
+        if line.trim().is_empty() {
+            continue;
+        }
+        match parse_position(line) {
+            Ok(position) => positions.push(position),
+            Err(e) => errors.push((i + 1, e)),
+        }
+    }
+    (positions, errors)
+}
+
+//the expected result of a position from its evaluation, k scales centipawns to winning chances
+pub fn sigmoid(eval:i32, k:f64) -> f64{
+    1.0/(1.0 + 10f64.powf(-k*eval as f64/400.0))
+}
+
+//the evaluations of all positions, spread over the cores
+//every thread uses its own pawn table, pawn structures evaluated with other parameters would be wrong
+pub fn evaluations(positions:&[TuningPosition], params:&EvalParams) -> Vec<i32>{
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk_size = positions.len().div_ceil(threads).max(1);
+    std::thread::scope(|scope| {
+        let handles:Vec<_> = positions.chunks(chunk_size).map(|chunk| {
+            scope.spawn(move || {
+                let mut pawn_table = PawnTable::default();
+                chunk.iter().map(|position| evaluate(&position.state, params, &mut pawn_table)).collect::<Vec<i32>>()
+            })
+        }).collect();
+        handles.into_iter().flat_map(|handle| handle.join().unwrap()).collect()
+    })
+}
+
+//mean squared difference between the expected and the real results
+pub fn mean_error(positions:&[TuningPosition], evals:&[i32], k:f64) -> f64{
+    if positions.is_empty() {
+        return 0.0;
+    }
+    let sum:f64 = positions.iter().zip(evals).map(|(position, &eval)| (position.result - sigmoid(eval, k)).powi(2)).sum();
+    sum/positions.len() as f64
+}
+
+//the k that fits the evaluations best, found to three decimals
+pub fn fit_scale(positions:&[TuningPosition], evals:&[i32]) -> f64{
+    let mut k = 1.0;
+    let mut step = 0.1;
+    while step > 0.0005 {
+        let error = mean_error(positions, evals, k);
+        if mean_error(positions, evals, k + step) < error {
+            k += step;
+        }else if k - step > 0.0 && mean_error(positions, evals, k - step) < error {
+            k -= step;
+        }else{
+            step /= 10.0;
+        }
+    }
+    k
+}
+
+pub struct Tuner{
+    positions: Vec<TuningPosition>,
+    params: EvalParams,
+    k: f64,
+    error: f64,
+}
+
+impl Tuner{
+    //fits k to the starting parameters, k stays the same while the parameters are tuned
+    pub fn new(positions:Vec<TuningPosition>, params:EvalParams) -> Self{
+        let evals = evaluations(&positions, &params);
+        let k = fit_scale(&positions, &evals);
+        let error = mean_error(&positions, &evals, k);
+        Self { positions, params, k, error }
+    }
+
+    pub fn params(&self) -> &EvalParams{
+        &self.params
+    }
+
+    pub fn k(&self) -> f64{
+        self.k
+    }
+
+    pub fn error(&self) -> f64{
+        self.error
+    }
+
+    fn error_of(&self, params:&EvalParams) -> f64{
+        mean_error(&self.positions, &evaluations(&self.positions, params), self.k)
+    }
+
+    //one pass over every parameter, returns how many of them changed
+    pub fn iterate(&mut self) -> usize{
+        let mut changed = 0;
+        let parameter_count = self.params.values_mut().len();
+        for index in 0..parameter_count{
+            for step in [1, -1]{
+                let mut candidate = self.params.clone();
+                *candidate.values_mut()[index] += step;
+                let error = self.error_of(&candidate);
+                if error < self.error {
+                    self.params = candidate;
+                    self.error = error;
+                    changed += 1;
+                    break;
+                }
+            }
+        }
+        changed
+    }
+}
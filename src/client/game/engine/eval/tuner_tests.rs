@@ -0,0 +1,66 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::eval::params::EvalParams;
+    use crate::client::game::engine::eval::tuner::{evaluations, fit_scale, mean_error, parse_position, read_positions, sigmoid, Tuner};
+    use crate::client::game::engine::state_bitboard::{PAWN, WHITE};
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    #[test]
+    fn results(){
+        setup_sliding_magics();
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+        assert_eq!(parse_position(&format!("{fen} 1-0")).unwrap().result, 1.0);
+        assert_eq!(parse_position(&format!("{fen} [0.0]")).unwrap().result, 0.0);
+        assert_eq!(parse_position(&format!("{fen} 1/2-1/2")).unwrap().result, 0.5);
+        assert_eq!(parse_position("4k3/8/8/8/8/8/4P3/4K3 w - - c9 \"0-1\";").unwrap().result, 0.0);
+        assert_eq!(parse_position(&format!("{fen} | 35 | 0.5")).unwrap().result, 0.5);
+        assert!(parse_position(&format!("{fen} | 35 | 2.0")).is_err());
+        assert!(parse_position(fen).is_err());
+        assert!(parse_position("4k3/8/8/8/8/8/4P3 w - - 1-0").is_err());
+
+        let (positions, errors) = read_positions(&format!("{fen} 1-0\n\nnot a position\n{fen} [0.5]\n"));
+        assert_eq!(positions.len(), 2);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 3);
+    }
+
+    #[test]
+    fn sigmoid_error(){
+        assert_eq!(sigmoid(0, 1.0), 0.5);
+        assert!(sigmoid(400, 1.0) > 0.9);
+        assert!(sigmoid(-400, 1.0) < 0.1);
+        assert!((sigmoid(100, 1.0) + sigmoid(-100, 1.0) - 1.0).abs() < 1e-12);
+    }
+
+    //the side a pawn up mostly wins, the tuned parameters fit that better than piece square tables that take most of the
+    //value of the pawns away. the drawn position a pawn up keeps the results from being told apart by the sign alone
+    #[test]
+    fn tuning_lowers_error(){
+        setup_sliding_magics();
+        let text = [
+            "4k3/8/8/8/8/8/3PPP2/4K3 w - - 0 1 1-0",
+            "4k3/3ppp2/8/8/8/8/8/4K3 w - - 0 1 0-1",
+            "4k3/3pp3/8/8/8/8/3PPP2/4K3 b - - 0 1 1-0",
+            "4k3/3ppp2/8/8/8/8/3PP3/4K3 w - - 0 1 0-1",
+            "4k3/3ppp2/8/8/8/8/3PPP2/4K3 w - - 0 1 1/2-1/2",
+            "4k3/3pp3/8/8/8/8/3PPP2/4K3 w - - 0 1 1/2-1/2",
+        ].join("\n");
+        let (positions, errors) = read_positions(&text);
+        assert!(errors.is_empty());
+
+        let evals = evaluations(&positions, &EvalParams::default());
+        let k = fit_scale(&positions, &evals);
+        assert!(k > 0.0);
+        assert!(mean_error(&positions, &evals, k) <= mean_error(&positions, &evals, k + 0.01));
+
+        let text = "pawn mg\n".to_string() + &["-100"; 64].join(" ") + "\npawn eg\n" + &["-100"; 64].join(" ");
+        let params = EvalParams::parse(&text).unwrap();
+        let mut tuner = Tuner::new(positions, params);
+        let error = tuner.error();
+        assert!(tuner.iterate() > 0);
+        assert!(tuner.error() < error);
+        //e2, where the pawns of both sides stand
+        assert!(tuner.params().pst.value(PAWN, WHITE, 12).eg > -100);
+    }
+}
@@ -0,0 +1,273 @@
+#[cfg(test)]
+mod tests {
+
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    use crate::client::game::engine::eval::pawns::PawnTable;
+    use crate::client::game::engine::eval::params::EvalParams;
+    use crate::client::game::engine::board::ChessMove;
+    use crate::client::game::engine::eval::{evaluate, is_check, promising_move, tapered, trace, MAX_PHASE};
+    use crate::client::game::engine::fen::FenError;
+    use crate::client::game::engine::move_string::{uci_move, CastleNotation};
+    use crate::client::game::engine::state_bitboard::{BitBoardState, BLACK, WHITE};
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    fn eval(fen:&str) -> i32{
+        evaluate(&BitBoardState::from_fen(fen).unwrap(), &EvalParams::default(), &mut PawnTable::default())
+    }
+
+    //positions from games and test suites, with castling rights, en passant squares, promotions and endgames
+    const CORPUS:[&str; 16] = [
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+        "r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3",
+        "rnbqkb1r/pp1p1ppp/4pn2/2pP4/2P5/8/PP2PPPP/RNBQKBNR w KQkq c6 0 4",
+        "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+        "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+        "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+        "r1b2rk1/2q1bppp/p2p1n2/np2p3/3PP3/5N1P/PPBN1PP1/R1BQR1K1 b - - 0 13",
+        "2r2rk1/pp3pp1/4p2p/3q4/3P4/P1Q1P3/5PPP/2R2RK1 b - - 0 22",
+        "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+        "8/8/4k3/3pP3/8/8/4K3/8 w - d6 0 2",
+        "8/5pk1/6p1/7p/7P/6P1/5PK1/8 w - - 0 40",
+        "6k1/5ppp/8/8/8/8/1q3PPP/3R2K1 b - - 0 30",
+        "8/P7/8/8/8/8/6kp/4K3 w - - 0 50",
+        "4k3/8/8/8/8/8/8/4KN2 w - - 0 1",
+        "8/8/3k4/8/8/8/3RK3/4B3 b - - 0 60",
+        "r3k3/1P6/8/8/8/8/8/4K2R b Kq - 0 1",
+    ];
+
+    //the same position with the colors swapped and the board turned upside down
+    fn mirror(fen:&str) -> String{
+        let fields:Vec<&str> = fen.split_whitespace().collect();
+        let swap_case = |c:char| if c.is_ascii_uppercase() {c.to_ascii_lowercase()} else {c.to_ascii_uppercase()};
+        let board = fields[0].split('/').rev().map(|rank| rank.chars().map(swap_case).collect::<String>()).collect::<Vec<String>>().join("/");
+        let side = if fields[1] == "w" {"b"} else {"w"};
+        let mut castling:Vec<char> = fields[2].chars().map(swap_case).collect();
+        castling.sort_by_key(|&c| "KQkq-".find(c));
+        let en_passant = fields[3].replace('3', "x").replace('6', "3").replace('x', "6");
+        format!("{} {} {} {} {}", board, side, castling.iter().collect::<String>(), en_passant, fields[4..].join(" "))
+    }
+
+    //the corpus and the positions of random games from the start
+    fn positions() -> Vec<String>{
+        let mut positions:Vec<String> = CORPUS.iter().map(|fen| fen.to_string()).collect();
+        let mut rng = StdRng::seed_from_u64(50);
+        for _ in 0..20{
+            let mut state = BitBoardState::from_fen(CORPUS[0]).unwrap();
+            for _ in 0..60{
+                let moves = state.gen_moves_legal().moves_vec();
+                if moves.is_empty() {
+                    break;
+                }
+                state = state.perform_move(moves[rng.random_range(0..moves.len())]);
+                positions.push(state.to_fen());
+            }
+        }
+        positions
+    }
+
+    #[test]
+    fn taper(){
+        let value = tapered(40, -20);
+        assert_eq!(value.taper(0), 40);
+        assert_eq!(value.taper(MAX_PHASE), -20);
+        assert_eq!(value.taper(MAX_PHASE/2), 10);
+
+        assert_eq!(tapered(1, 2) + tapered(3, 4), tapered(4, 6));
+        assert_eq!(tapered(1, 2) - tapered(3, 4), tapered(-2, -2));
+        assert_eq!(tapered(1, 2)*3, tapered(3, 6));
+    }
+
+    #[test]
+    fn symmetric_start(){
+        setup_sliding_magics();
+        assert_eq!(eval("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), 0);
+    }
+
+    //a central king is only worth something once the pieces are gone
+    #[test]
+    fn king_centralisation(){
+        setup_sliding_magics();
+        let central = eval("4k3/pppppppp/8/8/3K4/8/PPPPPPPP/8 w - - 0 1");
+        let corner = eval("4k3/pppppppp/8/8/8/K7/PPPPPPPP/8 w - - 0 1");
+        assert!(central > corner, "{central} {corner}");
+
+        //with every piece on the board it matters much less
+        let central_middlegame = eval("rnbqkbnr/pppppppp/8/8/3K4/8/PPPPPPPP/RNBQ1BNR w kq - 0 1");
+        let corner_middlegame = eval("rnbqkbnr/pppppppp/8/8/8/K7/PPPPPPPP/RNBQ1BNR w kq - 0 1");
+        assert!(central_middlegame - corner_middlegame < central - corner, "{central_middlegame} {corner_middlegame}");
+    }
+
+    //a rook in the open beats a rook boxed in by its own pieces
+    #[test]
+    fn mobility(){
+        setup_sliding_magics();
+        let open = eval("4k3/p7/8/8/3R4/8/P6P/1N2K3 w - - 0 1");
+        let boxed_in = eval("4k3/p7/8/8/8/8/P6P/RN2K3 w - - 0 1");
+        assert!(open > boxed_in, "{open} {boxed_in}");
+    }
+
+    //a pawn attacking a knight, compared to a knight attacking an undefended pawn
+    #[test]
+    fn threats(){
+        setup_sliding_magics();
+        let attacking = eval("4k3/p7/8/3n4/4P3/8/7P/4K3 w - - 0 1");
+        let attacked = eval("4k3/p7/8/3n4/8/4P3/7P/4K3 w - - 0 1");
+        assert!(attacking > attacked, "{attacking} {attacked}");
+    }
+
+    //the bishop is attacked by the rook, and only defended by the pawn on b3
+    #[test]
+    fn hanging_pieces(){
+        setup_sliding_magics();
+        let defended = eval("2r1k3/p7/8/8/2B5/1P6/7P/4K3 w - - 0 1");
+        let hanging = eval("2r1k3/p7/8/8/2B5/P7/7P/4K3 w - - 0 1");
+        assert!(defended > hanging, "{defended} {hanging}");
+    }
+
+    //black pieces around the castled white king against the same pieces on the other wing
+    #[test]
+    fn king_attack(){
+        setup_sliding_magics();
+        let attacked = eval("r5k1/5ppp/8/8/6nq/8/5PPP/R2Q2K1 w - - 0 1");
+        let quiet = eval("r5k1/5ppp/8/8/nq6/8/5PPP/R2Q2K1 w - - 0 1");
+        assert!(attacked < quiet, "{attacked} {quiet}");
+    }
+
+    //pawns in front of the king shelter it, pushing them opens the king up
+    #[test]
+    fn king_shelter(){
+        setup_sliding_magics();
+        let sheltered = eval("r1bq1rk1/5ppp/2n5/8/8/2N5/5PPP/R1BQ1RK1 w - - 0 1");
+        let open = eval("r1bq1rk1/5ppp/2n5/8/5PPP/2N5/8/R1BQ1RK1 w - - 0 1");
+        assert!(sheltered > open, "{sheltered} {open}");
+    }
+
+    //the same open king is in less danger once the attacker has traded its queen
+    #[test]
+    fn king_danger_shrinks_with_material(){
+        setup_sliding_magics();
+        let with_queens = eval("r2q2k1/5ppp/8/8/8/8/8/R2Q2K1 w - - 0 1") - eval("r2q2k1/8/8/8/8/8/5PPP/R2Q2K1 w - - 0 1");
+        let without_queens = eval("r5k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1") - eval("r5k1/8/8/8/8/8/5PPP/R5K1 w - - 0 1");
+        assert!(with_queens < without_queens, "{with_queens} {without_queens}");
+    }
+
+    #[test]
+    fn mirror_fens(){
+        assert_eq!(mirror("r3k2r/p1pp4/8/8/8/8/PPPP4/R3K2R w Kq e3 0 1"), "r3k2r/pppp4/8/8/8/8/P1PP4/R3K2R b Qk e6 0 1");
+        for fen in CORPUS{
+            assert_eq!(mirror(&mirror(fen)), fen);
+        }
+    }
+
+    //the mirrored position is just as good for the other side, every term of the trace swaps colors
+    #[test]
+    fn mirrored_positions(){
+        setup_sliding_magics();
+        let params = EvalParams::default();
+        let mut pawn_table = PawnTable::default();
+        for fen in positions(){
+            let original = trace(&BitBoardState::from_fen(&fen).unwrap(), &params, &mut pawn_table);
+            let mirrored = trace(&BitBoardState::from_fen(&mirror(&fen)).unwrap(), &params, &mut pawn_table);
+            for ((name, term), (_, mirrored_term)) in original.terms().iter().zip(mirrored.terms()){
+                assert_eq!(term[WHITE], mirrored_term[BLACK], "{name} of {fen}");
+                assert_eq!(term[BLACK], mirrored_term[WHITE], "{name} of {fen}");
+            }
+            assert_eq!(original.phase, mirrored.phase, "{fen}");
+            assert_eq!(original.eval, -mirrored.eval, "{fen}");
+        }
+    }
+
+    //move ordering treats both colors the same, the mirrored move is as promising for the other side
+    #[test]
+    fn mirrored_move_ordering(){
+        setup_sliding_magics();
+        let params = EvalParams::default();
+        let mirror_move = |uci:&str| uci.chars().map(|c| match c.to_digit(10) {
+            Some(rank) => char::from_digit(9 - rank, 10).unwrap(),
+            None => c,
+        }).collect::<String>();
+        for fen in positions(){
+            let mut state = BitBoardState::from_fen(&fen).unwrap();
+            let mut mirrored_state = BitBoardState::from_fen(&mirror(&fen)).unwrap();
+            let mut mirrored_moves = mirrored_state.gen_moves_legal().moves_vec();
+            for mut chess_move in state.gen_moves_legal().moves_vec(){
+                let uci = mirror_move(&uci_move(chess_move, CastleNotation::Standard));
+                let mirrored_move = mirrored_moves.iter_mut()
+                    .find(|mirrored_move| uci_move(**mirrored_move, CastleNotation::Standard) == uci).unwrap();
+                promising_move(&mut state, &mut chess_move, None, &params);
+                promising_move(&mut mirrored_state, mirrored_move, None, &params);
+                assert_eq!(*chess_move.promising_level(), -*mirrored_move.promising_level(), "{uci} in {fen}");
+            }
+        }
+    }
+
+    //castles give check with the rook from the square it lands on
+    #[test]
+    fn castle_checks(){
+        setup_sliding_magics();
+        for (fen, uci, check) in [
+            ("5k2/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1", true),
+            ("3k4/8/8/8/8/8/8/R3K3 w Q - 0 1", "e1c1", true),
+            ("4k2r/8/8/8/8/8/8/5K2 b k - 0 1", "e8g8", true),
+            ("r3k3/8/8/8/8/8/8/3K4 b q - 0 1", "e8c8", true),
+            ("4k3/8/8/8/8/8/8/4K2R w K - 0 1", "e1g1", false),
+            ("5k2/5p2/8/8/8/8/8/4K2R w K - 0 1", "e1g1", false),
+            //the king leaves the first rank, the rook checks along it
+            ("8/8/8/8/8/8/8/R3K2k w Q - 0 1", "e1c1", true),
+        ]{
+            let state = BitBoardState::from_fen(fen).unwrap();
+            let chess_move = ChessMove::from_uci(uci, &state).unwrap();
+            assert_eq!(is_check(&state, &chess_move), check, "{uci} in {fen}");
+        }
+    }
+
+    //an extra piece or pawn anywhere is never worse for the side that gets it
+    #[test]
+    fn extra_material(){
+        setup_sliding_magics();
+        let params = EvalParams::default();
+        let mut pawn_table = PawnTable::default();
+        for fen in [
+            "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+            "4k3/pp6/8/8/8/8/PP6/4K3 w - - 0 1",
+            "4k3/3p4/8/8/8/8/3P4/2B1K3 b - - 0 1",
+            "r3k3/5p2/8/8/8/8/2P5/4K2R w - - 0 1",
+            "6k1/5ppp/8/8/8/8/5PPP/6K1 w - - 0 1",
+        ]{
+            let state = BitBoardState::from_fen(fen).unwrap();
+            let eval = evaluate(&state, &params, &mut pawn_table);
+            let board:Vec<char> = fen.split(' ').next().unwrap().split('/').rev()
+                .flat_map(|rank| rank.chars().flat_map(|c| match c.to_digit(10) {
+                    Some(empty) => vec!['.'; empty as usize],
+                    None => vec![c],
+                }))
+                .collect();
+            for (square, _) in board.iter().enumerate().filter(|(_, c)| **c == '.'){
+                for piece in ['P', 'N', 'B', 'R', 'Q', 'p', 'n', 'b', 'r', 'q']{
+                    if piece.eq_ignore_ascii_case(&'p') && !(8..56).contains(&square) {
+                        continue;
+                    }
+                    let mut extra = board.clone();
+                    extra[square] = piece;
+                    let ranks:Vec<String> = extra.chunks(8).rev().map(|rank| rank.iter().collect()).collect();
+                    let extra_fen = format!("{} {}", ranks.join("/").replace("........", "8"), fen.split_once(' ').unwrap().1);
+                    let extra_fen = (1..8).rev().fold(extra_fen, |fen, empty| fen.replace(&".".repeat(empty), &empty.to_string()));
+                    //pieces giving check to the side not to move make illegal positions
+                    let extra_state = match BitBoardState::from_fen(&extra_fen) {
+                        Err(FenError::SideNotToMoveInCheck) => continue,
+                        extra_state => extra_state.unwrap(),
+                    };
+                    let extra_eval = evaluate(&extra_state, &params, &mut pawn_table);
+                    let color = if piece.is_ascii_uppercase() {WHITE} else {BLACK};
+                    if color == WHITE {
+                        assert!(extra_eval >= eval, "{extra_fen} {extra_eval} against {eval}");
+                    }else{
+                        assert!(extra_eval <= eval, "{extra_fen} {extra_eval} against {eval}");
+                    }
+                }
+            }
+        }
+    }
+}
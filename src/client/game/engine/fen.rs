@@ -0,0 +1,253 @@
+use std::fmt;
+
+use super::move_string::string_square;
+use super::state_bitboard::bit_boards::{self, RANK_1, RANK_8};
+use super::state_bitboard::{BISHOP, BLACK, KING, KNIGHT, PAWN, QUEEN, ROOK, WHITE};
+
+/**
+ * strict FEN parsing, shared by the board representations
+ * the parser checks both the syntax of every field and that the position itself is legal
+ */
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum FenError{
+    MissingField(&'static str),
+    TooManyFields(usize),
+    WrongNumberOfRanks(usize),
+    InvalidPiece{rank: u8, file: u8, character: char},
+    RankTooLong{rank: u8},
+    RankTooShort{rank: u8},
+    InvalidSideToMove(String),
+    InvalidCastleRights(String),
+    InvalidEnPassant(String),
+    InvalidHalfMoveClock(String),
+    InvalidFullMoveNumber(String),
+    WrongNumberOfKings{white: u32, black: u32},
+    TooManyPawns{white: u32, black: u32},
+    PawnOnBackRank(String),
+    CastleRightsWithoutPieces(char),
+    EnPassantWithoutPawn(String),
+    SideNotToMoveInCheck,
+}
+
+impl fmt::Display for FenError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::MissingField(field) => write!(f, "missing {field} field"),
+            FenError::TooManyFields(count) => write!(f, "expected at most 6 fields, found {count}"),
+            FenError::WrongNumberOfRanks(count) => write!(f, "expected 8 ranks in piece placement, found {count}"),
+            FenError::InvalidPiece{rank, file, character} => write!(f, "invalid character '{character}' on rank {rank}, file {}", char::from(b'a' + file)),
+            FenError::RankTooLong{rank} => write!(f, "rank {rank} describes more than 8 squares"),
+            FenError::RankTooShort{rank} => write!(f, "rank {rank} describes less than 8 squares"),
+            FenError::InvalidSideToMove(field) => write!(f, "side to move must be 'w' or 'b', found '{field}'"),
+            FenError::InvalidCastleRights(field) => write!(f, "invalid castle rights '{field}'"),
+            FenError::InvalidEnPassant(field) => write!(f, "invalid en passant square '{field}'"),
+            FenError::InvalidHalfMoveClock(field) => write!(f, "invalid half move clock '{field}'"),
+            FenError::InvalidFullMoveNumber(field) => write!(f, "invalid full move number '{field}'"),
+            FenError::WrongNumberOfKings{white, black} => write!(f, "each side needs exactly one king, found {white} white and {black} black"),
+            FenError::TooManyPawns{white, black} => write!(f, "a side can have at most 8 pawns, found {white} white and {black} black"),
+            FenError::PawnOnBackRank(square) => write!(f, "pawn on back rank at {square}"),
+            FenError::CastleRightsWithoutPieces(right) => write!(f, "castle right '{right}' without king and rook on their starting squares"),
+            FenError::EnPassantWithoutPawn(square) => write!(f, "en passant square {square} without a pawn that just moved past it"),
+            FenError::SideNotToMoveInCheck => write!(f, "the side not to move is in check"),
+        }
+    }
+}
+
+impl std::error::Error for FenError{}
+
+//a parsed and validated FEN, in a form that both board representations can be built from
+pub struct Fen{
+    pub piece_bb: [[u64; 6]; 2],
+    pub white_to_move: bool,
+    pub castle_w_k: bool,
+    pub castle_w_q: bool,
+    pub castle_b_k: bool,
+    pub castle_b_q: bool,
+    pub en_passant_square: Option<u8>,
+    pub half_move_clock: u8,
+    pub full_move_number: u16,
+}
+
+//the half move clock and full move number may be left out, they default to 0 and 1
+pub fn parse_fen(fen:&str) -> Result<Fen, FenError>{
+    let fields:Vec<&str> = fen.split_whitespace().collect();
+    if fields.len() > 6 {
+        return Err(FenError::TooManyFields(fields.len()));
+    }
+    let field = |index:usize, name:&'static str| fields.get(index).copied().ok_or(FenError::MissingField(name));
+
+    let piece_bb = parse_piece_placement(field(0, "piece placement")?)?;
+
+    let white_to_move = match field(1, "side to move")? {
+        "w" => true,
+        "b" => false,
+        other => return Err(FenError::InvalidSideToMove(other.to_string())),
+    };
+
+    let castle_field = field(2, "castle rights")?;
+    let mut castle_rights = [false; 4];
+    if castle_field != "-" {
+        for c in castle_field.chars(){
+            let index = match c {
+                'K' => 0,
+                'Q' => 1,
+                'k' => 2,
+                'q' => 3,
+                _ => return Err(FenError::InvalidCastleRights(castle_field.to_string())),
+            };
+            if castle_rights[index] {
+                return Err(FenError::InvalidCastleRights(castle_field.to_string()));
+            }
+            castle_rights[index] = true;
+        }
+    }
+
+    let en_passant_field = field(3, "en passant")?;
+    let en_passant_square = if en_passant_field == "-" {
+        None
+    }else{
+        let bytes = en_passant_field.as_bytes();
+        let expected_rank = if white_to_move {b'6'} else {b'3'};
+        if bytes.len() != 2 || !(b'a'..=b'h').contains(&bytes[0]) || bytes[1] != expected_rank {
+            return Err(FenError::InvalidEnPassant(en_passant_field.to_string()));
+        }
+        Some((bytes[1] - b'1')*8 + (bytes[0] - b'a'))
+    };
+
+    let half_move_clock = match fields.get(4) {
+        Some(field) => field.parse::<u8>().map_err(|_| FenError::InvalidHalfMoveClock(field.to_string()))?,
+        None => 0,
+    };
+    let full_move_number = match fields.get(5) {
+        Some(field) => match field.parse::<u16>() {
+            Ok(number) if number >= 1 => number,
+            _ => return Err(FenError::InvalidFullMoveNumber(field.to_string())),
+        },
+        None => 1,
+    };
+
+    let fen = Fen {
+        piece_bb,
+        white_to_move,
+        castle_w_k: castle_rights[0],
+        castle_w_q: castle_rights[1],
+        castle_b_k: castle_rights[2],
+        castle_b_q: castle_rights[3],
+        en_passant_square,
+        half_move_clock,
+        full_move_number,
+    };
+    validate(&fen)?;
+    Ok(fen)
+}
+
+fn parse_piece_placement(placement:&str) -> Result<[[u64; 6]; 2], FenError>{
+    let ranks:Vec<&str> = placement.split('/').collect();
+    if ranks.len() != 8 {
+        return Err(FenError::WrongNumberOfRanks(ranks.len()));
+    }
+
+    let mut piece_bb = [[0; 6]; 2];
+    for (i, rank_str) in ranks.iter().enumerate(){
+        let rank = 7 - i as u8;
+        let mut file:u8 = 0;
+        for c in rank_str.chars(){
+            if let Some(empty_squares) = c.to_digit(10).filter(|d| (1..=8).contains(d)) {
+                file += empty_squares as u8;
+                if file > 8 {
+                    return Err(FenError::RankTooLong{rank: rank + 1});
+                }
+                continue;
+            }
+
+            let piece = match c.to_ascii_lowercase() {
+                'p' => PAWN,
+                'n' => KNIGHT,
+                'b' => BISHOP,
+                'r' => ROOK,
+                'q' => QUEEN,
+                'k' => KING,
+                _ => return Err(FenError::InvalidPiece{rank: rank + 1, file: file.min(7), character: c}),
+            };
+            if file >= 8 {
+                return Err(FenError::RankTooLong{rank: rank + 1});
+            }
+            let color = if c.is_ascii_uppercase() {WHITE} else {BLACK};
+            piece_bb[color][piece] |= 1 << (rank*8 + file);
+            file += 1;
+        }
+        if file < 8 {
+            return Err(FenError::RankTooShort{rank: rank + 1});
+        }
+    }
+    Ok(piece_bb)
+}
+
+//checks that the parsed fields describe a position that can occur in a game
+fn validate(fen:&Fen) -> Result<(), FenError>{
+    let pieces = &fen.piece_bb;
+
+    let white_kings = u64::count_ones(pieces[WHITE][KING]);
+    let black_kings = u64::count_ones(pieces[BLACK][KING]);
+    if white_kings != 1 || black_kings != 1 {
+        return Err(FenError::WrongNumberOfKings{white: white_kings, black: black_kings});
+    }
+
+    let white_pawns = u64::count_ones(pieces[WHITE][PAWN]);
+    let black_pawns = u64::count_ones(pieces[BLACK][PAWN]);
+    if white_pawns > 8 || black_pawns > 8 {
+        return Err(FenError::TooManyPawns{white: white_pawns, black: black_pawns});
+    }
+
+    let pawns_on_back_rank = (pieces[WHITE][PAWN] | pieces[BLACK][PAWN]) & (RANK_1 | RANK_8);
+    if pawns_on_back_rank != 0 {
+        return Err(FenError::PawnOnBackRank(string_square(pawns_on_back_rank.trailing_zeros() as u8)));
+    }
+
+    //castle rights require the king and the corresponding rook to be on their starting squares
+    let castle_pieces = [
+        (fen.castle_w_k, 'K', WHITE, 4, 7),
+        (fen.castle_w_q, 'Q', WHITE, 4, 0),
+        (fen.castle_b_k, 'k', BLACK, 60, 63),
+        (fen.castle_b_q, 'q', BLACK, 60, 56),
+    ];
+    for (right, right_char, color, king_square, rook_square) in castle_pieces{
+        if right && (pieces[color][KING] & (1 << king_square) == 0 || pieces[color][ROOK] & (1 << rook_square) == 0) {
+            return Err(FenError::CastleRightsWithoutPieces(right_char));
+        }
+    }
+
+    //the pawn that just moved two squares must stand in front of the en passant square, with the squares it passed empty
+    if let Some(square) = fen.en_passant_square {
+        let square = square as usize;
+        let (moved_color, pawn_square, origin_square) = if fen.white_to_move {
+            (BLACK, square - 8, square + 8)
+        }else{
+            (WHITE, square + 8, square - 8)
+        };
+        let occupied = pieces[WHITE].iter().chain(pieces[BLACK].iter()).fold(0, |acc, bb| acc | bb);
+        if pieces[moved_color][PAWN] & (1 << pawn_square) == 0 || occupied & ((1 << square) | (1 << origin_square)) != 0 {
+            return Err(FenError::EnPassantWithoutPawn(string_square(square as u8)));
+        }
+    }
+
+    let (to_move, other) = if fen.white_to_move {(WHITE, BLACK)} else {(BLACK, WHITE)};
+    if attacked_by(pieces, pieces[other][KING].trailing_zeros() as usize, to_move) {
+        return Err(FenError::SideNotToMoveInCheck);
+    }
+
+    Ok(())
+}
+
+//returns true if any piece of the given color attacks square
+fn attacked_by(pieces:&[[u64; 6]; 2], square:usize, color:usize) -> bool{
+    let occupied = pieces[WHITE].iter().chain(pieces[BLACK].iter()).fold(0, |acc, bb| acc | bb);
+    let attackers = &pieces[color];
+
+    bit_boards::PAWN_CAPTURES[1 - color][square] & attackers[PAWN] != 0 ||
+    bit_boards::KNIGHT_MOVES[square] & attackers[KNIGHT] != 0 ||
+    bit_boards::KING_MOVES[square] & attackers[KING] != 0 ||
+    bit_boards::BishopMoves::mov_map(square, occupied) & (attackers[BISHOP] | attackers[QUEEN]) != 0 ||
+    bit_boards::RookMoves::mov_map(square, occupied) & (attackers[ROOK] | attackers[QUEEN]) != 0
+}
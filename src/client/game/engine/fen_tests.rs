@@ -0,0 +1,57 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::{board::BoardState, fen::{parse_fen, FenError}};
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    fn parse_error(fen:&str) -> FenError{
+        match parse_fen(fen) {
+            Ok(_) => panic!("{fen} should not parse"),
+            Err(e) => e,
+        }
+    }
+
+    #[test]
+    fn en_passant_square(){
+        setup_sliding_magics();
+        let fen = "rnbqkbnr/ppp1pppp/8/8/3pP3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 3";
+        assert_eq!(parse_fen(fen).unwrap().en_passant_square, Some(20));
+        assert_eq!(BoardState::new_from_fen(fen).unwrap().to_fen(), fen);
+
+        let fen = "rnbqkbnr/1pp1pppp/p7/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3";
+        assert_eq!(parse_fen(fen).unwrap().en_passant_square, Some(43));
+        assert_eq!(BoardState::new_from_fen(fen).unwrap().to_fen(), fen);
+    }
+
+    #[test]
+    fn syntax_errors(){
+        setup_sliding_magics();
+        assert_eq!(parse_error(""), FenError::MissingField("piece placement"));
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq"), FenError::MissingField("en passant"));
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1 x"), FenError::TooManyFields(7));
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), FenError::WrongNumberOfRanks(7));
+        assert_eq!(parse_error("rnbqkbnr\\pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), FenError::WrongNumberOfRanks(7));
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/3x4/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), FenError::InvalidPiece{rank: 4, file: 3, character: 'x'});
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNRR w KQkq - 0 1"), FenError::RankTooLong{rank: 1});
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/44/8/PPPPPPP/RNBQKBNR w KQkq - 0 1"), FenError::RankTooShort{rank: 2});
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/9/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), FenError::InvalidPiece{rank: 4, file: 0, character: '9'});
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR x KQkq - 0 1"), FenError::InvalidSideToMove("x".to_string()));
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKq - 0 1"), FenError::InvalidCastleRights("KKq".to_string()));
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e4 0 1"), FenError::InvalidEnPassant("e4".to_string()));
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x 1"), FenError::InvalidHalfMoveClock("x".to_string()));
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 0"), FenError::InvalidFullMoveNumber("0".to_string()));
+    }
+
+    #[test]
+    fn illegal_positions(){
+        setup_sliding_magics();
+        assert_eq!(parse_error("rnbq1bnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQ - 0 1"), FenError::WrongNumberOfKings{white: 1, black: 0});
+        assert_eq!(parse_error("4k3/8/8/8/8/8/8/P3K3 w - - 0 1"), FenError::PawnOnBackRank("a1".to_string()));
+        assert_eq!(parse_error("4k3/pppppppp/p7/8/8/8/8/4K3 w - - 0 1"), FenError::TooManyPawns{white: 0, black: 9});
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN1 w KQkq - 0 1"), FenError::CastleRightsWithoutPieces('K'));
+        assert_eq!(parse_error("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq e6 0 1"), FenError::EnPassantWithoutPawn("e6".to_string()));
+        assert_eq!(parse_error("4k3/8/8/8/8/8/8/4R1K1 w - - 0 1"), FenError::SideNotToMoveInCheck);
+        assert_eq!(parse_error("8/8/8/3kK3/8/8/8/8 b - - 0 1"), FenError::SideNotToMoveInCheck);
+        assert!(parse_fen("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1").is_ok());
+    }
+}
@@ -1,33 +1,35 @@
-use super::board::{ChessMove, W_CASTLE_KING, W_CASTLE_QUEEN, B_CASTLE_KING, B_CASTLE_QUEEN, PROMOTE_TO_BISHOP, PROMOTE_TO_KNIGHT, PROMOTE_TO_ROOK};
+use super::board::{ChessMove, CASTLE_SQUARES, PROMOTE_TO_BISHOP, PROMOTE_TO_KNIGHT, PROMOTE_TO_QUEEN, PROMOTE_TO_ROOK};
 
 
+//how castles are written, standard uci moves the king two squares while chess960 uses the king capturing its own rook
+//the engine only plays standard chess, guis that set UCI_Chess960 still expect the chess960 notation for its castles
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CastleNotation{
+    Standard,
+    Chess960,
+}
+
 pub fn lan_move(chess_move:ChessMove) -> String{
-    match chess_move.flag() {
-        W_CASTLE_KING => {
-            return "e1g1".to_string();
-        }
-        W_CASTLE_QUEEN => {
-            return "e1c1".to_string();
-        }
-        B_CASTLE_KING => {
-            return "e8g8".to_string();
-        }
-        B_CASTLE_QUEEN => {
-            return "e8c8".to_string();
-        }
-        PROMOTE_TO_BISHOP => {
-            return format!("{}{}b", string_square(chess_move.origin()), string_square(chess_move.target()));
-        }
-        PROMOTE_TO_KNIGHT => {
-            return format!("{}{}n", string_square(chess_move.origin()), string_square(chess_move.target()));
-        }
-        PROMOTE_TO_ROOK => {
-            return format!("{}{}r", string_square(chess_move.origin()), string_square(chess_move.target()));
-        }
-        _ => {
-            return format!("{}{}", string_square(chess_move.origin()), string_square(chess_move.target()));
-        }
+    uci_move(chess_move, CastleNotation::Standard)
+}
+
+pub fn uci_move(chess_move:ChessMove, castle_notation:CastleNotation) -> String{
+    if let Some(&(_, king_origin, king_target, rook_square)) = CASTLE_SQUARES.iter().find(|castle| castle.0 == chess_move.flag()) {
+        let target = match castle_notation {
+            CastleNotation::Standard => king_target,
+            CastleNotation::Chess960 => rook_square,
+        };
+        return format!("{}{}", string_square(king_origin), string_square(target));
     }
+
+    let promotion = match chess_move.flag() {
+        PROMOTE_TO_KNIGHT => "n",
+        PROMOTE_TO_BISHOP => "b",
+        PROMOTE_TO_ROOK => "r",
+        PROMOTE_TO_QUEEN => "q",
+        _ => "",
+    };
+    format!("{}{}{}", string_square(chess_move.origin()), string_square(chess_move.target()), promotion)
 }
 
 pub fn string_square(square:u8) -> String{
@@ -55,4 +57,12 @@ pub fn string_square(square:u8) -> String{
         _ => {}
     }
     return str;
-}
\ No newline at end of file
+}
+
+//inverse of string_square, returns None if the string is not a square like "e4"
+pub fn parse_square(square:&str) -> Option<u8>{
+    match square.as_bytes() {
+        [file @ b'a'..=b'h', rank @ b'1'..=b'8'] => Some((rank - b'1')*8 + (file - b'a')),
+        _ => None,
+    }
+}
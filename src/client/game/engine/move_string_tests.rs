@@ -0,0 +1,60 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::{board::{self, ChessMove}, move_string::{lan_move, uci_move, CastleNotation}, state_bitboard::BitBoardState};
+    use crate::client::game::engine::test_setup::{setup_sliding_magics, PERFT_FENS};
+
+    fn assert_round_trip(state:&mut BitBoardState){
+        for chess_move in state.gen_moves_legal().moves_vec(){
+            for castle_notation in [CastleNotation::Standard, CastleNotation::Chess960]{
+                let uci = uci_move(chess_move, castle_notation);
+                assert_eq!(ChessMove::from_uci(&uci, state), Ok(chess_move), "failed for {uci} in {}", state.to_fen());
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip_every_legal_move(){
+        setup_sliding_magics();
+        //two plies deep, so black castles and black promotions are covered as well
+        for fen in PERFT_FENS{
+            let mut root = BitBoardState::from_fen(fen).unwrap();
+            assert_round_trip(&mut root);
+            for chess_move in root.gen_moves_legal().moves_vec(){
+                assert_round_trip(&mut root.perform_move(chess_move));
+            }
+        }
+    }
+
+    #[test]
+    fn promotion_suffixes(){
+        setup_sliding_magics();
+        let mut state = BitBoardState::from_fen(PERFT_FENS[4]).unwrap();
+        let mut promotions:Vec<String> = state.gen_moves_legal().moves_vec().into_iter()
+            .filter(|m| m.flag() <= board::PROMOTE_TO_QUEEN)
+            .map(lan_move)
+            .collect();
+        promotions.sort();
+        assert_eq!(promotions, ["d7c8b", "d7c8n", "d7c8q", "d7c8r"]);
+    }
+
+    #[test]
+    fn castle_notation(){
+        setup_sliding_magics();
+        let mut state = BitBoardState::from_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        let castles = |state:&mut BitBoardState, castle_notation| {
+            let mut castles:Vec<String> = state.gen_moves_legal().moves_vec().into_iter()
+                .filter(|m| board::CASTLE_SQUARES.iter().any(|castle| castle.0 == m.flag()))
+                .map(|m| uci_move(m, castle_notation))
+                .collect();
+            castles.sort();
+            castles
+        };
+        assert_eq!(castles(&mut state, CastleNotation::Standard), ["e1c1", "e1g1"]);
+        assert_eq!(castles(&mut state, CastleNotation::Chess960), ["e1a1", "e1h1"]);
+
+        let mut state = state.perform_move(ChessMove::from_uci("a1b1", &state).unwrap());
+        assert_eq!(castles(&mut state, CastleNotation::Standard), ["e8c8", "e8g8"]);
+        assert_eq!(castles(&mut state, CastleNotation::Chess960), ["e8a8", "e8h8"]);
+    }
+}
@@ -0,0 +1,187 @@
+use std::fmt;
+
+use super::board::{ChessMove, CASTLE_SQUARES, B_CASTLE_QUEEN, PROMOTE_TO_BISHOP, PROMOTE_TO_KNIGHT, PROMOTE_TO_QUEEN, PROMOTE_TO_ROOK, W_CASTLE_QUEEN};
+use super::move_string::{parse_square, string_square};
+use super::state_bitboard::{BitBoardState, BISHOP, BLACK, KNIGHT, PAWN, QUEEN, ROOK, WHITE};
+
+/**
+ * standard algebraic notation, both formatting a move in the context of a position and parsing it back.
+ * parsing is done by matching against the legal moves, so any move returned can be played
+ */
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SanError{
+    Empty,
+    InvalidSyntax(String),
+    IllegalMove(String),
+    AmbiguousMove(String),
+}
+
+impl fmt::Display for SanError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanError::Empty => write!(f, "empty move"),
+            SanError::InvalidSyntax(san) => write!(f, "'{san}' is not a move in standard algebraic notation"),
+            SanError::IllegalMove(san) => write!(f, "{san} is not a legal move in this position"),
+            SanError::AmbiguousMove(san) => write!(f, "{san} matches more than one legal move"),
+        }
+    }
+}
+
+impl std::error::Error for SanError{}
+
+const PIECE_LETTERS:[char; 6] = ['P', 'N', 'B', 'R', 'Q', 'K'];
+
+//the piece type of the given color on square, if any
+fn piece_on(bit_board_state:&BitBoardState, color:usize, square:u8) -> Option<usize>{
+    bit_board_state.piece_bb()[color].iter().position(|bb| bb & (1 << square) != 0)
+}
+
+fn is_castle(chess_move:&ChessMove) -> bool{
+    CASTLE_SQUARES.iter().any(|castle| castle.0 == chess_move.flag())
+}
+
+fn promotion_piece(flag:u8) -> Option<usize>{
+    match flag {
+        PROMOTE_TO_KNIGHT => Some(KNIGHT),
+        PROMOTE_TO_BISHOP => Some(BISHOP),
+        PROMOTE_TO_ROOK => Some(ROOK),
+        PROMOTE_TO_QUEEN => Some(QUEEN),
+        _ => None,
+    }
+}
+
+//formats a legal move of the given position, None for a move that is not legal there, like the empty move of a search
+//without moves
+pub fn san_move(bit_board_state:&BitBoardState, chess_move:ChessMove) -> Option<String>{
+    let mut state = *bit_board_state;
+    let legal_moves = state.gen_moves_legal().moves_vec();
+    if !legal_moves.contains(&chess_move) {
+        return None;
+    }
+    let to_move = if state.white_to_move() {WHITE} else {BLACK};
+    let other = 1 - to_move;
+
+    let mut san = String::with_capacity(8);
+    if is_castle(&chess_move) {
+        san.push_str(if chess_move.flag() == W_CASTLE_QUEEN || chess_move.flag() == B_CASTLE_QUEEN {"O-O-O"} else {"O-O"});
+    }else{
+        let origin = chess_move.origin();
+        let target = chess_move.target();
+        let piece = piece_on(&state, to_move, origin)?;
+        let is_capture = piece_on(&state, other, target).is_some() || (piece == PAWN && origin % 8 != target % 8);
+
+        if piece == PAWN {
+            if is_capture {
+                san.push(char::from(b'a' + origin % 8));
+            }
+        }else{
+            san.push(PIECE_LETTERS[piece]);
+
+            //other pieces of the same type that can move to the same square
+            let others:Vec<u8> = legal_moves.iter()
+                .filter(|m| !is_castle(m) && m.target() == target && m.origin() != origin)
+                .filter(|m| piece_on(&state, to_move, m.origin()) == Some(piece))
+                .map(|m| m.origin())
+                .collect();
+            if !others.is_empty() {
+                let origin_square = string_square(origin);
+                if others.iter().all(|o| o % 8 != origin % 8) {
+                    san.push_str(&origin_square[0..1]);
+                }else if others.iter().all(|o| o / 8 != origin / 8) {
+                    san.push_str(&origin_square[1..2]);
+                }else{
+                    san.push_str(&origin_square);
+                }
+            }
+        }
+
+        if is_capture {
+            san.push('x');
+        }
+        san.push_str(&string_square(target));
+
+        if let Some(promotion) = promotion_piece(chess_move.flag()) {
+            san.push('=');
+            san.push(PIECE_LETTERS[promotion]);
+        }
+    }
+
+    let mut after = state.perform_move(chess_move);
+    if after.in_check() {
+        san.push(if after.gen_moves_legal().size() == 0 {'#'} else {'+'});
+    }
+    Some(san)
+}
+
+//parses a move in standard algebraic notation
+//check and mate markers, annotations like ! and ?, and castles written with zeros are accepted.
+//the capture marker and the = before a promotion piece are optional
+pub fn parse_san(san:&str, bit_board_state:&BitBoardState) -> Result<ChessMove, SanError>{
+    let trimmed = san.trim().trim_end_matches(['+', '#', '!', '?']);
+    if trimmed.is_empty() {
+        return Err(SanError::Empty);
+    }
+    let invalid = || SanError::InvalidSyntax(san.to_string());
+
+    let mut state = *bit_board_state;
+    let legal_moves = state.gen_moves_legal().moves_vec();
+    let to_move = if state.white_to_move() {WHITE} else {BLACK};
+
+    let candidates:Vec<ChessMove> = match trimmed {
+        "O-O" | "0-0" => legal_moves.into_iter().filter(|m| is_castle(m) && m.flag() != W_CASTLE_QUEEN && m.flag() != B_CASTLE_QUEEN).collect(),
+        "O-O-O" | "0-0-0" => legal_moves.into_iter().filter(|m| m.flag() == W_CASTLE_QUEEN || m.flag() == B_CASTLE_QUEEN).collect(),
+        _ => {
+            if !trimmed.is_ascii() {
+                return Err(invalid());
+            }
+            let mut rest = trimmed;
+
+            let piece = match rest.chars().next() {
+                Some(c @ ('N' | 'B' | 'R' | 'Q' | 'K')) => {
+                    rest = &rest[1..];
+                    PIECE_LETTERS.iter().position(|letter| *letter == c).unwrap()
+                }
+                _ => PAWN,
+            };
+
+            let promotion = match rest.chars().last() {
+                Some(c @ ('N' | 'B' | 'R' | 'Q')) if piece == PAWN => {
+                    rest = rest[..rest.len() - 1].strip_suffix('=').unwrap_or(&rest[..rest.len() - 1]);
+                    Some(PIECE_LETTERS.iter().position(|letter| *letter == c).unwrap())
+                }
+                _ => None,
+            };
+
+            if rest.len() < 2 {
+                return Err(invalid());
+            }
+            let target = parse_square(&rest[rest.len() - 2..]).ok_or_else(invalid)?;
+            let disambiguation = rest[..rest.len() - 2].strip_suffix('x').unwrap_or(&rest[..rest.len() - 2]);
+
+            let mut origin_file = None;
+            let mut origin_rank = None;
+            for c in disambiguation.bytes() {
+                match c {
+                    b'a'..=b'h' if origin_file.is_none() && origin_rank.is_none() => origin_file = Some(c - b'a'),
+                    b'1'..=b'8' if origin_rank.is_none() => origin_rank = Some(c - b'1'),
+                    _ => return Err(invalid()),
+                }
+            }
+
+            legal_moves.into_iter()
+                .filter(|m| !is_castle(m) && m.target() == target)
+                .filter(|m| piece_on(&state, to_move, m.origin()) == Some(piece))
+                .filter(|m| origin_file.is_none_or(|file| m.origin() % 8 == file))
+                .filter(|m| origin_rank.is_none_or(|rank| m.origin() / 8 == rank))
+                .filter(|m| promotion_piece(m.flag()) == promotion)
+                .collect()
+        }
+    };
+
+    match candidates.as_slice() {
+        [chess_move] => Ok(*chess_move),
+        [] => Err(SanError::IllegalMove(san.to_string())),
+        _ => Err(SanError::AmbiguousMove(san.to_string())),
+    }
+}
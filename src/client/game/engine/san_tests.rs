@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::{board::ChessMove, san::{parse_san, san_move, SanError}, state_bitboard::BitBoardState};
+    use crate::client::game::engine::test_setup::{setup_sliding_magics, PERFT_FENS};
+
+    fn san(fen:&str, uci_move:&str) -> String{
+        let state = BitBoardState::from_fen(fen).unwrap();
+        san_move(&state, ChessMove::from_uci(uci_move, &state).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn format(){
+        setup_sliding_magics();
+        assert_eq!(san(PERFT_FENS[0], "e2e4"), "e4");
+        assert_eq!(san(PERFT_FENS[0], "g1f3"), "Nf3");
+        assert_eq!(san(PERFT_FENS[1], "e1g1"), "O-O");
+        assert_eq!(san(PERFT_FENS[1], "e1c1"), "O-O-O");
+        assert_eq!(san(PERFT_FENS[1], "e5f7"), "Nxf7");
+        assert_eq!(san(PERFT_FENS[1], "d5e6"), "dxe6");
+        assert_eq!(san(PERFT_FENS[1], "f3f6"), "Qxf6");
+        assert_eq!(san(PERFT_FENS[4], "d7c8q"), "dxc8=Q");
+        assert_eq!(san("4k3/8/8/8/8/8/8/R3K3 w - - 0 1", "a1a8"), "Ra8+");
+        assert_eq!(san(PERFT_FENS[4], "d7c8n"), "dxc8=N");
+        assert_eq!(san("rnbqkbnr/1pp1pppp/p7/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 3", "e5d6"), "exd6");
+
+        //disambiguation by file, by rank and by both
+        assert_eq!(san(PERFT_FENS[5], "a1d1"), "Rad1");
+        assert_eq!(san("4k3/8/8/8/R7/8/8/R3K3 w - - 0 1", "a4a3"), "R4a3");
+        assert_eq!(san("1k6/8/8/8/Q6Q/8/8/K6Q w - - 0 1", "h4e4"), "Qh4e4");
+
+        //mate
+        assert_eq!(san("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2", "d8h4"), "Qh4#");
+    }
+
+    #[test]
+    fn parse(){
+        setup_sliding_magics();
+        let parse = |fen:&str, san:&str| {
+            let state = BitBoardState::from_fen(fen).unwrap();
+            parse_san(san, &state)
+        };
+        let uci = |fen:&str, uci_move:&str| Ok(ChessMove::from_uci(uci_move, &BitBoardState::from_fen(fen).unwrap()).unwrap());
+
+        assert_eq!(parse(PERFT_FENS[1], "O-O"), uci(PERFT_FENS[1], "e1g1"));
+        assert_eq!(parse(PERFT_FENS[1], "0-0-0"), uci(PERFT_FENS[1], "e1c1"));
+        assert_eq!(parse(PERFT_FENS[1], "Nf7"), uci(PERFT_FENS[1], "e5f7"));
+        assert_eq!(parse(PERFT_FENS[1], "Qxf6!?"), uci(PERFT_FENS[1], "f3f6"));
+        assert_eq!(parse(PERFT_FENS[4], "dxc8Q"), uci(PERFT_FENS[4], "d7c8q"));
+        assert_eq!(parse(PERFT_FENS[4], "dxc8=R"), uci(PERFT_FENS[4], "d7c8r"));
+        assert_eq!(parse(PERFT_FENS[5], "Rad1"), uci(PERFT_FENS[5], "a1d1"));
+        assert_eq!(parse("1k6/8/8/8/Q6Q/8/8/K6Q w - - 0 1", "Qh4e4"), uci("1k6/8/8/8/Q6Q/8/8/K6Q w - - 0 1", "h4e4"));
+
+        assert_eq!(parse(PERFT_FENS[0], ""), Err(SanError::Empty));
+        assert_eq!(parse(PERFT_FENS[0], "Xe4"), Err(SanError::InvalidSyntax("Xe4".to_string())));
+        assert_eq!(parse(PERFT_FENS[0], "e9"), Err(SanError::InvalidSyntax("e9".to_string())));
+        assert_eq!(parse(PERFT_FENS[0], "e5"), Err(SanError::IllegalMove("e5".to_string())));
+        assert_eq!(parse(PERFT_FENS[0], "O-O"), Err(SanError::IllegalMove("O-O".to_string())));
+        assert_eq!(parse(PERFT_FENS[4], "dxc8"), Err(SanError::IllegalMove("dxc8".to_string())));
+        assert_eq!(parse(PERFT_FENS[5], "Rd1"), Err(SanError::AmbiguousMove("Rd1".to_string())));
+    }
+
+    //the empty move of a search without moves, and moves of the other side
+    #[test]
+    fn illegal_moves(){
+        setup_sliding_magics();
+        let state = BitBoardState::from_fen(PERFT_FENS[0]).unwrap();
+        assert_eq!(san_move(&state, ChessMove::new_empty()), None);
+        let black_move = ChessMove::from_uci("e7e5", &state.perform_move(ChessMove::from_uci("e2e4", &state).unwrap())).unwrap();
+        assert_eq!(san_move(&state, black_move), None);
+    }
+
+    #[test]
+    fn round_trip_every_legal_move(){
+        setup_sliding_magics();
+        for fen in PERFT_FENS{
+            let mut root = BitBoardState::from_fen(fen).unwrap();
+            for first in root.gen_moves_legal().moves_vec(){
+                let san = san_move(&root, first).unwrap();
+                assert_eq!(parse_san(&san, &root), Ok(first), "failed for {san} in {fen}");
+
+                let mut child = root.perform_move(first);
+                for second in child.gen_moves_legal().moves_vec(){
+                    let san = san_move(&child, second).unwrap();
+                    assert_eq!(parse_san(&san, &child), Ok(second), "failed for {san} in {}", child.to_fen());
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use std::fmt;
+
+/*
+scores of the search, in centipawns from whites perspective.
+a mate is scored as MATE minus the number of plies from the root to the mate, so shorter mates score higher and every
+mate scores above MATE_BOUND, far away from any evaluation. the transposition table stores mates counted from the
+position itself instead of from the root, as the same position can be reached at different plies.
+*/
+
+pub const MATE:i32 = 30000;
+pub const MAX_MATE_PLY:i32 = 1000;
+pub const MATE_BOUND:i32 = MATE - MAX_MATE_PLY; //scores above this (or below minus this) are mates
+
+//the score of the side that mates, when the mate happens ply plies from the root
+pub fn mate_in(ply:usize) -> i32{
+    MATE - ply as i32
+}
+
+pub fn is_mate(score:i32) -> bool{
+    score.abs() > MATE_BOUND
+}
+
+//score of a position ply plies from the root, as it is stored in the transposition table
+pub fn to_table(score:i32, ply:usize) -> i32{
+    if score > MATE_BOUND {
+        score + ply as i32
+    }else if score < -MATE_BOUND {
+        score - ply as i32
+    }else{
+        score
+    }
+}
+
+//inverse of to_table
+pub fn from_table(score:i32, ply:usize) -> i32{
+    if score > MATE_BOUND {
+        score - ply as i32
+    }else if score < -MATE_BOUND {
+        score + ply as i32
+    }else{
+        score
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Score{
+    Cp(i32), //centipawns
+    Mate(i32), //moves until mate, positive when white mates and negative when black mates
+}
+
+impl Score{
+    //score of a search from the root
+    pub fn from_search(score:i32) -> Self{
+        if !is_mate(score) {
+            return Score::Cp(score);
+        }
+        let moves = (MATE - score.abs() + 1) / 2;
+        Score::Mate(if score > 0 {moves} else {-moves})
+    }
+}
+
+//pawns with two decimals like "-0.35", mates like "#3" or "#-3", the format of the %eval command in pgn comments
+impl fmt::Display for Score{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Score::Cp(cp) => format!("{:.2}", *cp as f64 / 100.0),
+            Score::Mate(moves) => format!("#{moves}"),
+        };
+        f.pad(&text)
+    }
+}
@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::score::{from_table, is_mate, mate_in, to_table, Score, MATE_BOUND};
+
+    #[test]
+    fn mate_scores(){
+        assert!(is_mate(mate_in(1)));
+        assert!(is_mate(-mate_in(40)));
+        assert!(!is_mate(MATE_BOUND));
+        assert!(mate_in(3) > mate_in(5));
+
+        assert_eq!(Score::from_search(mate_in(1)), Score::Mate(1));
+        assert_eq!(Score::from_search(mate_in(3)), Score::Mate(2));
+        assert_eq!(Score::from_search(-mate_in(4)), Score::Mate(-2));
+        assert_eq!(Score::from_search(-120), Score::Cp(-120));
+    }
+
+    #[test]
+    fn table_scores(){
+        //a mate 5 plies from the root, stored at ply 2, is 3 plies from the position
+        let stored = to_table(mate_in(5), 2);
+        assert_eq!(stored, mate_in(3));
+        assert_eq!(from_table(stored, 2), mate_in(5));
+        //the same position reached at ply 4
+        assert_eq!(from_table(stored, 4), mate_in(7));
+        assert_eq!(from_table(to_table(-mate_in(6), 3), 1), -mate_in(4));
+        assert_eq!(to_table(250, 7), 250);
+        assert_eq!(from_table(-250, 7), -250);
+    }
+
+    #[test]
+    fn display(){
+        assert_eq!(Score::Cp(-35).to_string(), "-0.35");
+        assert_eq!(Score::Cp(120).to_string(), "1.20");
+        assert_eq!(Score::Mate(3).to_string(), "#3");
+        assert_eq!(Score::Mate(-2).to_string(), "#-2");
+        assert_eq!(format!("{:>6}", Score::Mate(1)), "    #1");
+    }
+}
@@ -1 +1,53 @@
-//TODO move the main search loop here
\ No newline at end of file
+//zobrist hashes of every position leading up to the node currently being searched
+//positions from the game itself come first, followed by the positions on the current search path
+pub struct PositionHistory{
+    hashes: Vec<u64>,
+    root: usize, //index of the first position pushed by the search
+}
+
+impl PositionHistory{
+    //game_history should contain every position played before the position searched from
+    pub fn new(game_history:&[u64]) -> Self{
+        let mut hashes = Vec::<u64>::with_capacity(game_history.len() + 128);
+        hashes.extend_from_slice(game_history);
+        Self {
+            hashes,
+            root: game_history.len(),
+        }
+    }
+
+    pub fn push(&mut self, hash:u64){
+        self.hashes.push(hash);
+    }
+
+    pub fn pop(&mut self){
+        assert!(self.hashes.len() > self.root, "popped a position that was not pushed by the search");
+        self.hashes.pop();
+    }
+
+    //checks if the position should be scored as a draw by repetition
+    //a single repetition of a position on the search path is enough, as the side that could deviate would
+    //allready have done so, while positions from before the search must have occured twice before.
+    //only positions since the last capture or pawn move are scanned, and only those with the same side to move
+    pub fn is_repetition(&self, hash:u64, half_move_clock:u8) -> bool{
+        let len = self.hashes.len();
+        let max_distance = usize::min(half_move_clock as usize, len);
+
+        let mut game_repetitions = 0;
+        let mut distance = 2;
+        while distance <= max_distance {
+            let index = len - distance;
+            if self.hashes[index] == hash {
+                if index >= self.root {
+                    return true;
+                }
+                game_repetitions += 1;
+                if game_repetitions >= 2 {
+                    return true;
+                }
+            }
+            distance += 2;
+        }
+        false
+    }
+}
@@ -0,0 +1,121 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use crate::client::game::engine::eval::params::EvalParams;
+    use crate::client::game::engine::{board, move_string::lan_move, score::Score, search::PositionHistory, state_bitboard::BitBoardState, Engine};
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    fn from_fen(fen:&str) -> BitBoardState{
+        BitBoardState::from_fen(fen).unwrap()
+    }
+
+    //plays the moves from the given position, returns every position along the way including the first
+    fn play_line(fen:&str, uci_moves:&[&str]) -> Vec<BitBoardState>{
+        let mut positions = vec![from_fen(fen)];
+        for uci_move in uci_moves{
+            let mut state = *positions.last().unwrap();
+            let chess_move = state.gen_moves_legal().moves_vec().into_iter()
+                .find(|m| lan_move(*m) == *uci_move)
+                .unwrap_or_else(|| panic!("{uci_move} is not legal"));
+            positions.push(state.perform_move(chess_move));
+        }
+        positions
+    }
+
+    const START_POS:&str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+    const KNIGHT_SHUFFLE:[&str; 4] = ["g1f3", "g8f6", "f3g1", "f6g8"];
+
+    #[test]
+    fn repetition_in_game_history_needs_threefold(){
+        setup_sliding_magics();
+        let positions = play_line(START_POS, &[KNIGHT_SHUFFLE, KNIGHT_SHUFFLE].concat());
+        let hashes:Vec<u64> = positions.iter().map(|p| p.hash()).collect();
+
+        //second occurrence of the starting position, before the search started
+        let second = positions[4];
+        assert_eq!(second.hash(), hashes[0]);
+        assert!(!PositionHistory::new(&hashes[..4]).is_repetition(second.hash(), second.half_move_clock()));
+
+        //third occurrence
+        let third = positions[8];
+        assert!(PositionHistory::new(&hashes[..8]).is_repetition(third.hash(), third.half_move_clock()));
+    }
+
+    #[test]
+    fn single_repetition_inside_search_is_draw(){
+        setup_sliding_magics();
+        let positions = play_line(START_POS, &KNIGHT_SHUFFLE);
+
+        //the root is the starting position, the shuffle happens inside the search tree
+        let mut history = PositionHistory::new(&[]);
+        for position in &positions[..4]{
+            history.push(position.hash());
+        }
+        assert!(history.is_repetition(positions[4].hash(), positions[4].half_move_clock()));
+
+        //after popping back to the root, the root itself is no longer a repetition
+        for _ in 0..4{
+            history.pop();
+        }
+        assert!(!history.is_repetition(positions[0].hash(), positions[0].half_move_clock()));
+    }
+
+    #[test]
+    fn repetition_scan_stops_at_irreversible_move(){
+        setup_sliding_magics();
+        //rook shuffles, interrupted by a pawn move
+        let positions = play_line("4k3/p7/8/8/8/8/P7/R3K3 w - - 0 1", &["a1b1", "e8d8", "b1a1", "d8e8", "a2a3", "e8d8", "a1b1", "d8e8", "b1a1"]);
+        let hashes:Vec<u64> = positions.iter().map(|p| p.hash()).collect();
+
+        //the last position repeats the one right after the pawn move
+        let last = positions[9];
+        assert_eq!(last.half_move_clock(), 4);
+        assert_eq!(last.hash(), hashes[5]);
+        assert!(!PositionHistory::new(&hashes[..9]).is_repetition(last.hash(), last.half_move_clock()));
+
+        //an occurrence from before the pawn move is outside of the scanned window
+        let mut forged = hashes[..9].to_vec();
+        forged[1] = last.hash();
+        assert!(!PositionHistory::new(&forged).is_repetition(last.hash(), last.half_move_clock()));
+
+        //while one inside it makes the position a threefold repetition
+        forged[7] = last.hash();
+        assert!(PositionHistory::new(&forged).is_repetition(last.hash(), last.half_move_clock()));
+    }
+
+    #[test]
+    fn search_keeps_history_balanced(){
+        setup_sliding_magics();
+        let positions = play_line(START_POS, &["e2e4", "e7e5"]);
+        let game_history:Vec<u64> = positions.iter().map(|p| p.hash()).collect();
+
+        let mut engine = Engine::new(4, 8, 1000, None, Arc::new(EvalParams::default()));
+        let result = engine.get_move_bb(*positions.last().unwrap(), &game_history);
+        assert!(*result.chess_move() != board::ChessMove::new_empty());
+    }
+
+    #[test]
+    fn mates_are_reported_in_moves(){
+        setup_sliding_magics();
+        let mut engine = Engine::new(4, 10, 1000, None, Arc::new(EvalParams::default()));
+
+        //back rank mate
+        let position = from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1");
+        let result = engine.get_move_bb(position, &[position.hash()]);
+        assert_eq!(lan_move(*result.chess_move()), "a1a8");
+        assert_eq!(result.eval(), Score::Mate(1));
+
+        //the rook guards the second rank for the queen
+        let position = from_fen("6k1/5ppp/8/8/8/1q6/r7/6K1 b - - 0 1");
+        let result = engine.get_move_bb(position, &[position.hash()]);
+        assert_eq!(lan_move(*result.chess_move()), "b3b1");
+        assert_eq!(result.eval(), Score::Mate(-1));
+
+        //the king has to take away the escape squares first
+        let position = from_fen("k7/8/2K5/8/8/8/8/7R w - - 0 1");
+        let result = engine.get_move_bb(position, &[position.hash()]);
+        assert_eq!(result.eval(), Score::Mate(2));
+    }
+}
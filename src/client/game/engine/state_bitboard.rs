@@ -1,9 +1,14 @@
 
 
 
-use super::board::{BoardState, ChessMoveList, ChessMove, GameState};
+use super::board::{ChessMoveList, ChessMove, GameState};
+#[cfg(test)]
+use super::board::BoardState;
+use super::fen::{parse_fen, FenError};
+use super::move_string::string_square;
 
 pub mod bit_boards;
+mod zobrist;
 
 //Piece indexes
 pub const PAWN:usize = 0;
@@ -74,6 +79,9 @@ pub struct BitBoardState{
     castle_w_q: bool,
     castle_b_k: bool,
     castle_b_q: bool,
+    half_move_clock: u8, //number of half moves since the last capture or pawn move
+    full_move_number: u16,
+    hash: u64, //zobrist hash of the position, updated incrementally in perform_move
 
     //move lists
     legal_moves: ChessMoveList,
@@ -100,6 +108,9 @@ impl BitBoardState{
             castle_w_q: false,
             castle_b_k: false,
             castle_b_q: false,
+            half_move_clock: 0,
+            full_move_number: 1,
+            hash: 0,
 
             legal_moves: ChessMoveList::new(), //move lists should only be instansiated here
             legal_moves_calculated: false,
@@ -114,6 +125,32 @@ impl BitBoardState{
         }
     }
 
+    pub fn from_fen(fen:&str) -> Result<Self, FenError>{
+        let fen = parse_fen(fen)?;
+        let mut state = Self::new();
+
+        state.piece_bb = fen.piece_bb;
+        state.color_mask[BLACK] = fen.piece_bb[BLACK].iter().fold(0, |acc, bb| acc | bb);
+        state.color_mask[WHITE] = fen.piece_bb[WHITE].iter().fold(0, |acc, bb| acc | bb);
+
+        state.to_move = if fen.white_to_move {WHITE} else {BLACK};
+        state.other = if fen.white_to_move {BLACK} else {WHITE};
+
+        state.en_passant_square = fen.en_passant_square.map_or(NO_EN_PASSANT_SQUARE, |square| square as usize);
+        state.en_passant_possible = fen.en_passant_square.is_some();
+
+        state.castle_w_k = fen.castle_w_k;
+        state.castle_w_q = fen.castle_w_q;
+        state.castle_b_k = fen.castle_b_k;
+        state.castle_b_q = fen.castle_b_q;
+
+        state.half_move_clock = fen.half_move_clock;
+        state.full_move_number = fen.full_move_number;
+        state.hash = state.compute_hash();
+        Ok(state)
+    }
+
+    #[cfg(test)]
     fn piece_bb_from_board_state(&mut self, board_state_pieces:&[u8; 64]){
         self.piece_bb = [[0; 6]; 2];
         for i in 0..64{
@@ -149,6 +186,7 @@ impl BitBoardState{
                                 self.piece_bb[WHITE][KING];
     }
 
+    #[cfg(test)]
     pub fn setup_state(&mut self, board_state:&BoardState){
 
         self.piece_bb_from_board_state(board_state.pieces());
@@ -160,11 +198,11 @@ impl BitBoardState{
 
         self.en_passant_possible = if self.en_passant_square >= 64 {false} else {true};
 
-        let castle_rights = board_state.castle_rights();
-        self.castle_w_k = if castle_rights & 0b0001 != 0 {true} else {false};
-        self.castle_w_q = if castle_rights & 0b0010 != 0 {true} else {false};
-        self.castle_b_k = if castle_rights & 0b0100 != 0 {true} else {false};
-        self.castle_b_q = if castle_rights & 0b1000 != 0 {true} else {false};
+        [self.castle_w_k, self.castle_w_q, self.castle_b_k, self.castle_b_q] = board_state.castle_rights();
+
+        self.half_move_clock = board_state.half_move_clock();
+        self.full_move_number = board_state.full_move_number();
+        self.hash = self.compute_hash();
 
         self.checkers = 0;
         self.check_line = 0;
@@ -547,6 +585,12 @@ impl BitBoardState{
         return rook_attackers | bishop_attackers | knight_attackers | pawn_attackers | king_attacker;
     }
 
+    //returns true if the side to move is in check
+    pub fn in_check(&self) -> bool{
+        let king_pos = u64::trailing_zeros(self.piece_bb[self.to_move][KING]) as usize;
+        self.attackers(king_pos) != 0
+    }
+
     //update checkers bitboard, return number of checkers
     fn num_checkers(&mut self) -> usize{
         
@@ -609,6 +653,47 @@ impl BitBoardState{
     
 
 
+    //hashes every part of the position from scratch, perform_move keeps the hash up to date after this
+    fn compute_hash(&self) -> u64{
+        let mut hash:u64 = 0;
+        for color in [BLACK, WHITE]{
+            for piece in 0..6{
+                let mut pieces = self.piece_bb[color][piece];
+                while pieces != 0 {
+                    hash ^= zobrist::piece(color, piece, bit_boards::pop_lsb(&mut pieces));
+                }
+            }
+        }
+        hash ^= Self::castle_hash(self.castle_w_k, self.castle_w_q, self.castle_b_k, self.castle_b_q);
+        hash ^= Self::en_passant_hash(&self.piece_bb, self.en_passant_square, self.en_passant_possible, self.to_move);
+        if self.to_move == WHITE {
+            hash ^= zobrist::WHITE_TO_MOVE;
+        }
+        hash
+    }
+
+    fn castle_hash(castle_w_k:bool, castle_w_q:bool, castle_b_k:bool, castle_b_q:bool) -> u64{
+        let mut hash:u64 = 0;
+        for (i, right) in [castle_w_k, castle_w_q, castle_b_k, castle_b_q].into_iter().enumerate(){
+            if right {
+                hash ^= zobrist::castle(i);
+            }
+        }
+        hash
+    }
+
+    //the en passant square is only part of the hash if a pawn is actually able to capture on it,
+    //otherwise the position would not count as a repetition of the same position without the square
+    fn en_passant_hash(piece_bb:&[[u64; 6]; 2], en_passant_square:usize, en_passant_possible:bool, to_move:usize) -> u64{
+        if !en_passant_possible {
+            return 0;
+        }
+        if bit_boards::PAWN_CAPTURES[1 - to_move][en_passant_square] & piece_bb[to_move][PAWN] == 0 {
+            return 0;
+        }
+        zobrist::en_passant(en_passant_square%8)
+    }
+
     pub fn perform_move(&self, chess_move:ChessMove) -> BitBoardState{
         let origin: u8 = chess_move.origin();
         let origin_bb: u64 = 1<<origin;
@@ -774,6 +859,29 @@ impl BitBoardState{
             new_en_passant_square = NO_EN_PASSANT_SQUARE;
         }
 
+        //pawn moves and captures can not be reversed, positions before them can never repeat
+        let is_castle = flag == W_CASTLE_KING || flag == B_CASTLE_KING || flag == W_CASTLE_QUEEN || flag == B_CASTLE_QUEEN;
+        let new_half_move_clock = if !is_castle && (self.piece_bb[self.to_move][PAWN] & origin_bb != 0 || self.color_mask[self.other] & target_bb != 0) {
+            0
+        }else{
+            self.half_move_clock.saturating_add(1)
+        };
+
+        //update the hash with only the squares that changed
+        let mut new_hash = self.hash ^ zobrist::WHITE_TO_MOVE;
+        for color in [BLACK, WHITE]{
+            for (piece, (old_pieces, new_pieces)) in self.piece_bb[color].iter().zip(new_piece_bb[color].iter()).enumerate(){
+                let mut changed = old_pieces ^ new_pieces;
+                while changed != 0 {
+                    new_hash ^= zobrist::piece(color, piece, bit_boards::pop_lsb(&mut changed));
+                }
+            }
+        }
+        new_hash ^= Self::castle_hash(self.castle_w_k, self.castle_w_q, self.castle_b_k, self.castle_b_q);
+        new_hash ^= Self::castle_hash(new_castle_w_k, new_castle_w_q, new_castle_b_k, new_castle_b_q);
+        new_hash ^= Self::en_passant_hash(&self.piece_bb, self.en_passant_square, self.en_passant_possible, self.to_move);
+        new_hash ^= Self::en_passant_hash(&new_piece_bb, new_en_passant_square, new_en_passant_possible, self.other);
+
         let mut new_color_mask:[u64; 2] = [0; 2];
         new_color_mask[WHITE] = new_piece_bb[WHITE][PAWN] |
                                 new_piece_bb[WHITE][KNIGHT] |
@@ -798,6 +906,9 @@ impl BitBoardState{
             castle_w_q: new_castle_w_q, 
             castle_b_k: new_castle_b_k, 
             castle_b_q: new_castle_b_q,
+            half_move_clock: new_half_move_clock,
+            full_move_number: if self.to_move == BLACK {self.full_move_number + 1} else {self.full_move_number},
+            hash: new_hash,
 
             legal_moves: ChessMoveList::new(), //move lists should only be instansiated here
             legal_moves_calculated: false,
@@ -817,6 +928,19 @@ impl BitBoardState{
         return self.to_move == WHITE;
     }
 
+    //castle rights in the order white king side, white queen side, black king side, black queen side
+    pub fn castle_rights(&self) -> [bool; 4]{
+        [self.castle_w_k, self.castle_w_q, self.castle_b_k, self.castle_b_q]
+    }
+
+    //the en passant square, only if a pawn of the side to move stands next to the pawn that can be captured
+    pub fn en_passant_capture_square(&self) -> Option<usize>{
+        if !self.en_passant_possible || bit_boards::PAWN_CAPTURES[self.other][self.en_passant_square] & self.piece_bb[self.to_move][PAWN] == 0 {
+            return None;
+        }
+        Some(self.en_passant_square)
+    }
+
     #[inline(always)]
     pub fn piece_value(&self, square:usize) -> i32 {
         assert!(square < 64);
@@ -834,7 +958,32 @@ impl BitBoardState{
         -(u64::count_ones(self.piece_bb[BLACK][QUEEN] & mask) as i32)*90;
     }
 
+    //returns true if neither side can possibly deliver mate
+    //covers K vs K, K + minor vs K and positions where all bishops stand on the same colour
+    pub fn insufficient_material(&self) -> bool{
+        let major_pieces_and_pawns = 
+            self.piece_bb[WHITE][PAWN] | self.piece_bb[WHITE][ROOK] | self.piece_bb[WHITE][QUEEN] |
+            self.piece_bb[BLACK][PAWN] | self.piece_bb[BLACK][ROOK] | self.piece_bb[BLACK][QUEEN];
+        if major_pieces_and_pawns != 0 {
+            return false;
+        }
+
+        let knights = self.piece_bb[WHITE][KNIGHT] | self.piece_bb[BLACK][KNIGHT];
+        let bishops = self.piece_bb[WHITE][BISHOP] | self.piece_bb[BLACK][BISHOP];
+        if u64::count_ones(knights | bishops) <= 1 {
+            return true;
+        }
+
+        //any number of bishops, as long as they all move on the same colour
+        knights == 0 && (bishops & bit_boards::LIGHT_SQUARES == 0 || bishops & bit_boards::DARK_SQUARES == 0)
+    }
+
     pub fn game_state(&mut self) -> GameState{
+        //dead positions are drawn regardless of whose turn it is
+        if self.insufficient_material() {
+            return GameState::Draw;
+        }
+
         //use allready calculated moves if possible
         if self.legal_moves_calculated {
             if self.legal_moves.size() == 0{
@@ -908,6 +1057,74 @@ impl BitBoardState{
     pub fn piece_mask(&self) -> u64{
         return self.color_mask[WHITE] | self.color_mask[BLACK];
     }
+
+    pub fn hash(&self) -> u64{
+        self.hash
+    }
+
+    pub fn half_move_clock(&self) -> u8{
+        self.half_move_clock
+    }
+
+    pub fn full_move_number(&self) -> u16{
+        self.full_move_number
+    }
+
+    //by reference like BoardState::to_fen, even though the state is Copy
+    #[allow(clippy::wrong_self_convention)]
+    pub fn to_fen(&self) -> String{
+        const PIECE_CHARS:[[char; 6]; 2] = [
+            ['p', 'n', 'b', 'r', 'q', 'k'],
+            ['P', 'N', 'B', 'R', 'Q', 'K'],
+        ];
+        let mut fen = String::with_capacity(90);
+
+        for rank in (0..8).rev(){
+            let mut empty_squares = 0;
+            for file in 0..8{
+                let square_bb:u64 = 1 << (rank*8 + file);
+                let piece = [BLACK, WHITE].iter()
+                    .flat_map(|&color| (0..6).map(move |piece| (color, piece)))
+                    .find(|&(color, piece)| self.piece_bb[color][piece] & square_bb != 0);
+                match piece {
+                    Some((color, piece)) => {
+                        if empty_squares != 0 {
+                            fen.push(char::from(b'0' + empty_squares));
+                            empty_squares = 0;
+                        }
+                        fen.push(PIECE_CHARS[color][piece]);
+                    }
+                    None => {empty_squares += 1}
+                }
+            }
+            if empty_squares != 0 {
+                fen.push(char::from(b'0' + empty_squares));
+            }
+            if rank != 0 {
+                fen.push('/');
+            }
+        }
+
+        fen.push_str(if self.to_move == WHITE {" w "} else {" b "});
+
+        if !(self.castle_w_k || self.castle_w_q || self.castle_b_k || self.castle_b_q) {
+            fen.push('-');
+        }
+        for (right, right_char) in [(self.castle_w_k, 'K'), (self.castle_w_q, 'Q'), (self.castle_b_k, 'k'), (self.castle_b_q, 'q')]{
+            if right {
+                fen.push(right_char);
+            }
+        }
+
+        if self.en_passant_possible {
+            fen.push_str(&format!(" {} ", string_square(self.en_passant_square as u8)));
+        }else{
+            fen.push_str(" - ");
+        }
+
+        fen.push_str(&format!("{} {}", self.half_move_clock, self.full_move_number));
+        fen
+    }
 }
 
 
@@ -923,6 +1140,9 @@ impl Clone for BitBoardState{
             castle_w_q: false,
             castle_b_k: false,
             castle_b_q: false,
+            half_move_clock: 0,
+            full_move_number: 1,
+            hash: 0,
 
             legal_moves: ChessMoveList::new(), //move lists should only be instansiated here
             legal_moves_calculated: false,
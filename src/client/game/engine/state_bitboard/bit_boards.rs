@@ -39,8 +39,8 @@ pub const FILES:[u64; 8] = [
     FILE_D,
     FILE_E,
     FILE_F,
-    FILE_E,
-    FILE_G
+    FILE_G,
+    FILE_H
 ];
 
 pub const EAST_OF:[u64; 8] = [ //Indexed by file
@@ -81,6 +81,9 @@ pub const CASTLE_W_Q_LINE:u64 = 0x000000000000000E;
 pub const CASTLE_B_K_LINE:u64 = 0x6000000000000000;
 pub const CASTLE_B_Q_LINE:u64 = 0x0E00000000000000;
 
+pub const LIGHT_SQUARES:u64 = 0x55AA55AA55AA55AA;
+pub const DARK_SQUARES:u64 = !LIGHT_SQUARES;
+
 
 
 pub struct RookMoves{
@@ -391,48 +394,3 @@ fn magic_index(blockers: u64, entry:&MagicEntry) -> usize{
     hash = hash.wrapping_mul(entry.magic) >> entry.shift;
     return hash as usize + entry.offset
 }
-
-
-pub const BOARD_CENTER:u64 = 0x00003c3c3c3c0000;
-
-
-pub const TOP_TIER_PAWN:[u64; 2] = [
-    0xe7001818000000,
-    0x181800e700
-];
-
-pub const SEC_TIER_PAWN:[u64; 2] = [
-    0xdb8100180000,
-    0x180081db0000
-];
-
-
-pub const TOP_TIER_BISHOP:[u64; 2] = [
-    0x8142241818244281,
-    0x8142241818244281
-];
-
-pub const SEC_TIER_BISHOP:[u64; 2] = [
-    0x24422424422400,
-    0x24422424422400
-];
-
-
-pub const KING_PAWNS_OPTIMAL:[[u64; 8]; 2] = [
- [0x300,
- 0x700,
- 0xe00,
- 0x1c00,
- 0x3800,
- 0x7000,
- 0xe000,
- 0xc000],
- [0x3000000000000,
- 0x7000000000000,
- 0xe000000000000,
- 0x1c000000000000,
- 0x38000000000000,
- 0x70000000000000,
- 0xe0000000000000,
- 0xc0000000000000]
-];
\ No newline at end of file
@@ -0,0 +1,51 @@
+//zobrist keys used to hash positions
+//the keys are generated at compile time from a fixed seed, so hashes are the same between runs
+
+const PIECE_KEYS_OFFSET:usize = 0;
+const CASTLE_KEYS_OFFSET:usize = 2*6*64;
+const EN_PASSANT_KEYS_OFFSET:usize = CASTLE_KEYS_OFFSET + 4;
+const SIDE_KEY_OFFSET:usize = EN_PASSANT_KEYS_OFFSET + 8;
+const NUM_KEYS:usize = SIDE_KEY_OFFSET + 1;
+
+//splitmix64, returns the next state and the generated number
+const fn split_mix(state:u64) -> (u64, u64){
+    let state = state.wrapping_add(0x9E3779B97F4A7C15);
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    (state, z ^ (z >> 31))
+}
+
+const KEYS:[u64; NUM_KEYS] = {
+    let mut keys:[u64; NUM_KEYS] = [0; NUM_KEYS];
+    let mut state:u64 = 0x2545F4914F6CDD1D;
+    let mut i = 0;
+    while i < NUM_KEYS{
+        let (next_state, key) = split_mix(state);
+        state = next_state;
+        keys[i] = key;
+        i+=1;
+    }
+    keys
+};
+
+//key for a piece of the given color and type standing on square
+#[inline(always)]
+pub fn piece(color:usize, piece:usize, square:usize) -> u64{
+    KEYS[PIECE_KEYS_OFFSET + (color*6 + piece)*64 + square]
+}
+
+//key for a castle right, in the order white king side, white queen side, black king side, black queen side
+#[inline(always)]
+pub fn castle(right:usize) -> u64{
+    KEYS[CASTLE_KEYS_OFFSET + right]
+}
+
+//key for an en passant capture being possible on the given file
+#[inline(always)]
+pub fn en_passant(file:usize) -> u64{
+    KEYS[EN_PASSANT_KEYS_OFFSET + file]
+}
+
+//key xored in when white is to move
+pub const WHITE_TO_MOVE:u64 = KEYS[SIDE_KEY_OFFSET];
@@ -3,13 +3,9 @@ mod tests {
 
     use std::time::SystemTime;
 
-    use board::{BoardState, ChessMove};
-    use crate::client::game::engine::{board, state_bitboard::{BitBoardState, bit_boards}};
-
-    fn setup_sliding_magics(){
-        bit_boards::populate_rook_moves();
-        bit_boards::populate_bishop_moves();
-    }
+    use board::{BoardState, ChessMove, GameState, MoveParseError};
+    use crate::client::game::engine::{board, move_string::lan_move, state_bitboard::{BitBoardState, bit_boards}};
+    use crate::client::game::engine::test_setup::{setup_sliding_magics, PERFT_FENS};
 
     fn string_square(square:u8) -> String{
         let mut str = "".to_owned();
@@ -42,7 +38,7 @@ mod tests {
     #[test]
     fn get_moves_test(){
         setup_sliding_magics();
-        let board_state = BoardState::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let board_state = BoardState::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
         let mut bit_board_state = BitBoardState::new();
         bit_board_state.setup_state(&board_state);
         bit_board_state.gen_moves_legal();
@@ -61,7 +57,7 @@ mod tests {
 
         for i in 0..5{
             let fen = fens[i];
-            let pos = BoardState::new_from_fen(fen);
+            let pos = BoardState::new_from_fen(fen).unwrap();
             let mut bit_board_state = BitBoardState::new();
             bit_board_state.setup_state(&pos);
             let chess_move_list = bit_board_state.gen_moves_legal();
@@ -78,6 +74,188 @@ mod tests {
     }
 
 
+    #[test]
+    fn insufficient_material(){
+        setup_sliding_magics();
+        let drawn = [
+            "8/8/4k3/8/8/3K4/8/8 w - - 0 1",
+            "8/8/4k3/8/8/3K4/8/5B2 w - - 0 1",
+            "8/8/4k3/8/2n5/3K4/8/8 b - - 0 1",
+            "8/1b6/4k3/8/8/3K4/8/5B2 w - - 0 1",
+        ];
+        let playable = [
+            "8/8/4k3/8/8/3K4/4P3/8 w - - 0 1",
+            "8/8/4k3/8/8/3K4/8/R7 w - - 0 1",
+            "8/2b5/4k3/8/8/3K4/8/5B2 w - - 0 1",
+            "8/8/4k3/8/8/3K4/8/4NB2 w - - 0 1",
+        ];
+
+        for fen in drawn{
+            let mut bit_board_state = BitBoardState::new();
+            bit_board_state.setup_state(&BoardState::new_from_fen(fen).unwrap());
+            assert!(bit_board_state.insufficient_material(), "failed for {fen}");
+            assert!(bit_board_state.game_state() == GameState::Draw, "failed for {fen}");
+        }
+        for fen in playable{
+            let mut bit_board_state = BitBoardState::new();
+            bit_board_state.setup_state(&BoardState::new_from_fen(fen).unwrap());
+            assert!(!bit_board_state.insufficient_material(), "failed for {fen}");
+            assert!(bit_board_state.game_state() == GameState::Playing, "failed for {fen}");
+        }
+    }
+
+
+    fn play(bit_board_state:&BitBoardState, uci_move:&str) -> BitBoardState{
+        let mut state = *bit_board_state;
+        let chess_move = state.gen_moves_legal().moves_vec().into_iter()
+            .find(|m| lan_move(*m) == uci_move)
+            .unwrap_or_else(|| panic!("{uci_move} is not legal"));
+        state.perform_move(chess_move)
+    }
+
+    fn from_fen(fen:&str) -> BitBoardState{
+        let mut bit_board_state = BitBoardState::new();
+        bit_board_state.setup_state(&BoardState::new_from_fen(fen).unwrap());
+        bit_board_state
+    }
+
+    #[test]
+    fn hash_transpositions(){
+        setup_sliding_magics();
+        let start = from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+
+        let a = ["g1f3", "b8c6", "b1c3"].iter().fold(start, |s, m| play(&s, m));
+        let b = ["b1c3", "b8c6", "g1f3"].iter().fold(start, |s, m| play(&s, m));
+        assert_eq!(a.hash(), b.hash());
+        assert_eq!(a.hash(), from_fen("r1bqkbnr/pppppppp/2n5/8/8/2N2N2/PPPPPPPP/R1BQKB1R b KQkq - 3 2").hash());
+
+        //en passant square without a pawn able to capture does not change the hash
+        let e4 = play(&start, "e2e4");
+        assert_eq!(e4.hash(), from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").hash());
+
+        //but it does when the capture is possible
+        let d5 = ["e2e4", "a7a6", "e4e5", "d7d5"].iter().fold(start, |s, m| play(&s, m));
+        let no_en_passant = from_fen("rnbqkbnr/1pp1pppp/p7/3pP3/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3");
+        assert_ne!(d5.hash(), no_en_passant.hash());
+        assert_eq!(play(&d5, "g1f3").hash(), play(&no_en_passant, "g1f3").hash());
+
+        //losing castle rights by moving the king back and forth
+        let king_walk = ["e2e4", "e7e5", "e1e2", "e8e7", "e2e1", "e7e8"].iter().fold(start, |s, m| play(&s, m));
+        assert_eq!(king_walk.hash(), from_fen("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w - - 4 4").hash());
+        assert_eq!(king_walk.half_move_clock(), 4);
+    }
+
+
+    #[test]
+    fn fen_round_trip(){
+        setup_sliding_magics();
+        for fen in PERFT_FENS{
+            let board_state = BoardState::new_from_fen(fen).unwrap();
+            assert_eq!(board_state.to_fen(), fen);
+            assert_eq!(from_fen(fen).to_fen(), fen);
+        }
+
+        //missing counters are filled in with their defaults
+        assert_eq!(from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - ").to_fen(), "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1");
+    }
+
+    #[test]
+    fn files(){
+        for square in 0..64{
+            assert_eq!(bit_boards::file_of(square), bit_boards::FILE_A << (square % 8));
+            assert_eq!(bit_boards::file_of(square) & (1 << square), 1 << square);
+        }
+        assert_eq!(bit_boards::FILES[7], bit_boards::FILE_H);
+    }
+
+    #[test]
+    fn fen_after_moves(){
+        setup_sliding_magics();
+        //both representations must agree on the fen after every move two plies deep,
+        //this covers castling rights, en passant squares and both counters
+        for fen in PERFT_FENS{
+            let mut root = from_fen(fen);
+            for first in root.gen_moves_legal().moves_vec(){
+                let mut board_state = BoardState::new_from_fen(fen).unwrap();
+                board_state.perform_move(first);
+                let mut child = root.perform_move(first);
+                assert_eq!(child.to_fen(), board_state.to_fen(), "failed for {fen} after {}", lan_move(first));
+
+                for second in child.gen_moves_legal().moves_vec(){
+                    let mut board_state = board_state.clone();
+                    board_state.perform_move(second);
+                    assert_eq!(child.perform_move(second).to_fen(), board_state.to_fen(),
+                        "failed for {fen} after {} {}", lan_move(first), lan_move(second));
+                }
+            }
+        }
+
+        let line = ["e2e4", "c7c5", "g1f3", "d7d6", "f1b5", "b8c6", "e1g1"].iter()
+            .fold(from_fen(PERFT_FENS[0]), |s, m| play(&s, m));
+        assert_eq!(line.to_fen(), "r1bqkbnr/pp2pppp/2np4/1Bp5/4P3/5N2/PPPP1PPP/RNBQ1RK1 b kq - 3 4");
+    }
+
+    #[test]
+    fn from_fen_matches_board_state(){
+        setup_sliding_magics();
+        for fen in PERFT_FENS{
+            let mut parsed = BitBoardState::from_fen(fen).unwrap();
+            let mut converted = from_fen(fen);
+            assert_eq!(parsed.to_fen(), fen);
+            assert_eq!(parsed.hash(), converted.hash(), "failed for {fen}");
+            assert_eq!(parsed.gen_moves_legal().moves_vec(), converted.gen_moves_legal().moves_vec(), "failed for {fen}");
+        }
+        assert!(BitBoardState::from_fen("8/8/8/8/8/8/8/8 w - - 0 1").is_err());
+    }
+
+    #[test]
+    fn from_uci_flags(){
+        setup_sliding_magics();
+        let start = BitBoardState::from_fen(PERFT_FENS[0]).unwrap();
+        assert_eq!(ChessMove::from_uci("e2e4", &start).unwrap().flag(), board::DOUBLE_PAWN_MOVE);
+        assert_eq!(ChessMove::from_uci("g1f3", &start).unwrap().flag(), board::NO_FLAG);
+
+        let en_passant = ["e2e4", "a7a6", "e4e5", "d7d5"].iter().fold(start, |s, m| play(&s, m));
+        assert_eq!(ChessMove::from_uci("e5d6", &en_passant).unwrap().flag(), board::WHITE_EN_PASSANT);
+
+        let promotions = BitBoardState::from_fen(PERFT_FENS[4]).unwrap();
+        assert_eq!(ChessMove::from_uci("e1g1", &promotions).unwrap().flag(), board::W_CASTLE_KING);
+        assert_eq!(ChessMove::from_uci("e1h1", &promotions).unwrap().flag(), board::W_CASTLE_KING);
+        assert_eq!(ChessMove::from_uci("d7c8q", &promotions).unwrap().flag(), board::PROMOTE_TO_QUEEN);
+        assert_eq!(ChessMove::from_uci("d7c8n", &promotions).unwrap().flag(), board::PROMOTE_TO_KNIGHT);
+
+        let mut state = promotions;
+        for uci_move in ["e1g1", "f2d1", "d7c8q", "d8c8", "f1d1"]{
+            state = state.perform_move(ChessMove::from_uci(uci_move, &state).unwrap());
+        }
+        assert_eq!(state.to_fen(), "rnq2k1r/pp2bppp/2p5/8/2B5/8/PPP1N1PP/RNBR2K1 b - - 0 10");
+    }
+
+    #[test]
+    fn from_uci_errors(){
+        setup_sliding_magics();
+        let start = BitBoardState::from_fen(PERFT_FENS[0]).unwrap();
+        assert_eq!(ChessMove::from_uci("", &start), Err(MoveParseError::InvalidFormat("".to_string())));
+        assert_eq!(ChessMove::from_uci("e2e4q5", &start), Err(MoveParseError::InvalidFormat("e2e4q5".to_string())));
+        assert_eq!(ChessMove::from_uci("e2é4", &start), Err(MoveParseError::InvalidFormat("e2é4".to_string())));
+        assert_eq!(ChessMove::from_uci("i2e4", &start), Err(MoveParseError::InvalidSquare("i2".to_string())));
+        assert_eq!(ChessMove::from_uci("e2e9", &start), Err(MoveParseError::InvalidSquare("e9".to_string())));
+        assert_eq!(ChessMove::from_uci("e2e4k", &start), Err(MoveParseError::InvalidPromotion('k')));
+
+        //syntactically fine, but not legal
+        assert_eq!(ChessMove::from_uci("e2e5", &start), Err(MoveParseError::IllegalMove("e2e5".to_string())));
+        assert_eq!(ChessMove::from_uci("e7e5", &start), Err(MoveParseError::IllegalMove("e7e5".to_string())));
+        assert_eq!(ChessMove::from_uci("e1g1", &start), Err(MoveParseError::IllegalMove("e1g1".to_string())));
+        assert_eq!(ChessMove::from_uci("e2e4q", &start), Err(MoveParseError::IllegalMove("e2e4q".to_string())));
+
+        //castle rights alone are not enough, the king must be able to castle
+        let rook_on_e1 = BitBoardState::from_fen("3k4/8/8/8/8/8/8/R3R1K1 w - - 0 1").unwrap();
+        assert_eq!(ChessMove::from_uci("e1g1", &rook_on_e1), Err(MoveParseError::IllegalMove("e1g1".to_string())));
+        let promotions = BitBoardState::from_fen(PERFT_FENS[4]).unwrap();
+        assert_eq!(ChessMove::from_uci("d7c8", &promotions), Err(MoveParseError::IllegalMove("d7c8".to_string())));
+    }
+
+
     fn perft(bit_board_state:&mut BitBoardState, depth:usize) -> usize{
         if depth == 0{
             return 0;
@@ -98,7 +276,7 @@ mod tests {
     #[test]
     fn perft_start_pos(){
         setup_sliding_magics();
-        let board_state = BoardState::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        let board_state = BoardState::new_from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
         let mut bit_board_state = BitBoardState::new();
         bit_board_state.setup_state(&board_state);
         let start = SystemTime::now();
@@ -118,7 +296,7 @@ mod tests {
     #[test]
     fn perft_extras(){
         setup_sliding_magics();
-        let board_state = BoardState::new_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - ");
+        let board_state = BoardState::new_from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - ").unwrap();
         let mut bit_board_state = BitBoardState::new();
         bit_board_state.setup_state(&board_state);
         assert_eq!(perft(&mut bit_board_state, 1), 48);
@@ -128,7 +306,7 @@ mod tests {
         assert_eq!(perft(&mut bit_board_state, 5), 193690690);
         assert_eq!(perft(&mut bit_board_state, 6), 8031647685);
 
-        let board_state = BoardState::new_from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - ");
+        let board_state = BoardState::new_from_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - ").unwrap();
         let mut bit_board_state = BitBoardState::new();
         bit_board_state.setup_state(&board_state);
         assert_eq!(perft(&mut bit_board_state, 1), 14);
@@ -139,7 +317,7 @@ mod tests {
 
         
 
-        let board_state = BoardState::new_from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1");
+        let board_state = BoardState::new_from_fen("r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1").unwrap();
         let mut bit_board_state = BitBoardState::new();
         bit_board_state.setup_state(&board_state);
         assert_eq!(perft(&mut bit_board_state, 1), 6);
@@ -149,7 +327,7 @@ mod tests {
         assert_eq!(perft(&mut bit_board_state, 5), 15833292);
         assert_eq!(perft(&mut bit_board_state, 6), 706045033);
 
-        let board_state = BoardState::new_from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8 ");
+        let board_state = BoardState::new_from_fen("rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8 ").unwrap();
         let mut bit_board_state = BitBoardState::new();
         bit_board_state.setup_state(&board_state);
         assert_eq!(perft(&mut bit_board_state, 1), 44);
@@ -185,7 +363,7 @@ mod tests {
     #[test]
     fn perft_verbose_test(){
         setup_sliding_magics();
-        let board_state = BoardState::new_from_fen("8/4k3/3P4/1N6/4R3/8/3K4/8 b - - 0 1");
+        let board_state = BoardState::new_from_fen("8/4k3/3P4/1N6/4R3/8/3K4/8 b - - 0 1").unwrap();
         let mut bit_board_state = BitBoardState::new();
         bit_board_state.setup_state(&board_state);
 
@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::{env, fmt};
+
+use super::board::ChessMove;
+use super::eval::is_capture;
+use super::state_bitboard::{BitBoardState, BLACK, PAWN, WHITE};
+
+pub mod table;
+#[cfg(test)]
+mod table_tests;
+
+use table::{Material, ProbeState, Table, TableKind, WDL_BLESSED_LOSS, WDL_CURSED_WIN, WDL_DRAW, WDL_LOSS, WDL_WIN};
+
+/*
+syzygy endgame tablebases, read from the .rtbw (win/draw/loss) and .rtbz (distance to zeroing) files of one or more
+directories. tables are only read from disk the first time they are probed.
+
+the win/draw/loss tables are probed inside the search, for positions right after a capture or pawn move, as the tables
+assume that the fifty move counter is 0. the distance to zeroing tables are probed at the root to play the move that
+keeps the best result: the fastest zeroing move when winning and the slowest when losing, taking the fifty move counter
+of the root into account so wins that come too late are scored as draws.
+
+positions with castling rights are never probed, the tables do not have them.
+
+configured with the environment or the .env file:
+SYZYGY_PATH  directories with the table files, separated like PATH, no tablebase is used if it is not set
+
+the tests against real tables read the 3 piece tables from the directories of SYZYGY_TEST_PATH, they are ignored unless
+they are run with cargo test -- --ignored
+*/
+
+//score of a tablebase win in the search, below mate scores and above every evaluation
+pub const TB_WIN:i32 = 20000;
+
+//ranks of root moves, wins ranked above MAX_DTZ / 2 are wins within the fifty move rule
+const MAX_DTZ:i32 = 1 << 18;
+const RANK_BOUND:i32 = MAX_DTZ / 2 - 100;
+
+#[derive(Debug)]
+pub enum TablebaseError{
+    Io(String, std::io::Error),
+    NoTables(String),
+    NotConfigured(&'static str),
+}
+
+impl fmt::Display for TablebaseError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TablebaseError::Io(path, e) => write!(f, "could not read {path}: {e}"),
+            TablebaseError::NoTables(paths) => write!(f, "no syzygy tables found in {paths}"),
+            TablebaseError::NotConfigured(variable) => write!(f, "{variable} is not set"),
+        }
+    }
+}
+
+impl std::error::Error for TablebaseError{}
+
+//the files of one material combination, like KRPvKR
+#[derive(Default)]
+struct TableFiles{
+    wdl_path: Option<PathBuf>,
+    dtz_path: Option<PathBuf>,
+    wdl: OnceLock<Option<Table>>,
+    dtz: OnceLock<Option<Table>>,
+}
+
+impl TableFiles{
+    fn table(&self, name:&str, kind:TableKind) -> Option<&Table>{
+        let (path, table) = match kind {
+            TableKind::Wdl => (&self.wdl_path, &self.wdl),
+            TableKind::Dtz => (&self.dtz_path, &self.dtz),
+        };
+        table.get_or_init(|| {
+            let path = path.as_ref()?;
+            let material = Material::from_name(name)?;
+            let loaded = std::fs::read(path).map_err(|e| e.to_string()).and_then(|bytes| Table::new(kind, bytes, &material));
+            match loaded {
+                Ok(table) => Some(table),
+                Err(e) => {
+                    eprintln!("Failed to load tablebase file {}: {}", path.display(), e);
+                    None
+                }
+            }
+        }).as_ref()
+    }
+}
+
+//the move to play at the root, with the result it keeps
+#[derive(Clone, Copy, Debug)]
+pub struct RootProbe{
+    pub chess_move: ChessMove,
+    pub wdl: i32, //result for the side to move, fifty move rule included
+}
+
+pub struct Tablebase{
+    tables: HashMap<String, TableFiles>,
+    max_pieces: usize, //pieces of the largest table with a win/draw/loss file
+}
+
+//value of the position before a zeroing move that leads to wdl, in the unit of probe_dtz
+fn dtz_before_zeroing(wdl:i32) -> i32{
+    match wdl {
+        WDL_WIN => 1,
+        WDL_CURSED_WIN => 101,
+        WDL_BLESSED_LOSS => -101,
+        WDL_LOSS => -1,
+        _ => 0,
+    }
+}
+
+//captures and pawn moves reset the fifty move counter
+fn is_zeroing(state:&BitBoardState, chess_move:&ChessMove) -> bool{
+    let to_move = if state.white_to_move() {WHITE} else {BLACK};
+    is_capture(state, chess_move) || state.piece_bb()[to_move][PAWN] & (1 << chess_move.origin()) != 0
+}
+
+fn is_mate(state:&mut BitBoardState) -> bool{
+    state.in_check() && state.gen_moves_legal().size() == 0
+}
+
+impl Tablebase{
+    //reads the names of the table files in the directories, the files themselves are read when they are needed
+    pub fn open(directories:&[PathBuf]) -> Result<Self, TablebaseError>{
+        let mut tables = HashMap::<String, TableFiles>::new();
+        for directory in directories{
+            let entries = std::fs::read_dir(directory).map_err(|e| TablebaseError::Io(directory.display().to_string(), e))?;
+            for entry in entries.flatten(){
+                let path = entry.path();
+                let (Some(name), Some(extension)) = (path.file_stem().and_then(|s| s.to_str()), path.extension().and_then(|s| s.to_str())) else {
+                    continue;
+                };
+                if Material::from_name(name).is_none() {
+                    continue;
+                }
+                let files = tables.entry(name.to_string()).or_default();
+                match extension {
+                    "rtbw" => files.wdl_path = Some(path),
+                    "rtbz" => files.dtz_path = Some(path),
+                    _ => {}
+                }
+            }
+        }
+        tables.retain(|_, files| files.wdl_path.is_some() || files.dtz_path.is_some());
+
+        let max_pieces = tables.iter()
+            .filter(|(_, files)| files.wdl_path.is_some())
+            .filter_map(|(name, _)| Material::from_name(name))
+            .map(|material| material.num_pieces())
+            .max()
+            .unwrap_or(0);
+        if tables.is_empty() {
+            let paths:Vec<String> = directories.iter().map(|directory| directory.display().to_string()).collect();
+            return Err(TablebaseError::NoTables(paths.join(", ")));
+        }
+        Ok(Self { tables, max_pieces })
+    }
+
+    //opens the directories of SYZYGY_PATH, see the top of the file
+    pub fn from_env() -> Result<Self, TablebaseError>{
+        let paths = env::var_os("SYZYGY_PATH").ok_or(TablebaseError::NotConfigured("SYZYGY_PATH"))?;
+        let directories:Vec<PathBuf> = env::split_paths(&paths).filter(|path| path != Path::new("")).collect();
+        Self::open(&directories)
+    }
+
+    pub fn num_tables(&self) -> usize{
+        self.tables.len()
+    }
+
+    pub fn max_pieces(&self) -> usize{
+        self.max_pieces
+    }
+
+    //the tables can have the position, if all the tables below it are there too
+    pub fn can_probe(&self, state:&BitBoardState) -> bool{
+        !state.castle_rights().contains(&true) && state.piece_mask().count_ones() as usize <= self.max_pieces
+    }
+
+    //win/draw/loss for the side to move, as if the fifty move counter was 0
+    pub fn probe_wdl(&self, state:&BitBoardState) -> Option<i32>{
+        if !self.can_probe(state) {
+            return None;
+        }
+        let (wdl, result) = self.search(state, false);
+        (result != ProbeState::Fail).then_some(wdl)
+    }
+
+    //plies to the next zeroing move, see dtz
+    #[cfg(test)]
+    pub fn probe_dtz(&self, state:&BitBoardState) -> Option<i32>{
+        if !self.can_probe(state) {
+            return None;
+        }
+        let (dtz, result) = self.dtz(state);
+        (result != ProbeState::Fail).then_some(dtz)
+    }
+
+    //the move keeping the best result, winning moves that reach the next zeroing move fastest are preferred, so the win
+    //is not lost to the fifty move rule. losing moves hold out as long as possible
+    pub fn probe_root(&self, state:&BitBoardState) -> Option<RootProbe>{
+        if !self.can_probe(state) {
+            return None;
+        }
+        let cnt50 = state.half_move_clock() as i32;
+        let mut best:Option<(i32, RootProbe)> = None;
+        let mut position = *state;
+        for chess_move in position.gen_moves_legal().moves_vec(){
+            let mut next = state.perform_move(chess_move);
+
+            //dtz counted from the root position
+            let mut dtz = if next.half_move_clock() == 0 {
+                let (wdl, result) = self.search(&next, false);
+                if result == ProbeState::Fail {
+                    return None;
+                }
+                dtz_before_zeroing(-wdl)
+            }else if next.half_move_clock() >= 100 && !is_mate(&mut next) {
+                0
+            }else{
+                let (dtz, result) = self.dtz(&next);
+                if result == ProbeState::Fail {
+                    return None;
+                }
+                -dtz + (-dtz).signum()
+            };
+            if dtz == 2 && is_mate(&mut next) {
+                dtz = 1;
+            }
+
+            //wins and losses that are decided within the fifty move rule are ranked above the others
+            let rank = if dtz > 0 {
+                if dtz + cnt50 <= 99 {MAX_DTZ - dtz} else {MAX_DTZ / 2 - (dtz + cnt50)}
+            }else if dtz < 0 {
+                if -dtz*2 + cnt50 < 100 {-MAX_DTZ - dtz} else {-MAX_DTZ / 2 + (-dtz + cnt50)}
+            }else{
+                0
+            };
+            let wdl = if rank >= RANK_BOUND {
+                WDL_WIN
+            }else if rank > 0 {
+                WDL_CURSED_WIN
+            }else if rank == 0 {
+                WDL_DRAW
+            }else if rank > -RANK_BOUND {
+                WDL_BLESSED_LOSS
+            }else{
+                WDL_LOSS
+            };
+            if best.is_none_or(|(best_rank, _)| rank > best_rank) {
+                best = Some((rank, RootProbe { chess_move, wdl }));
+            }
+        }
+        best.map(|(_, root_probe)| root_probe)
+    }
+
+    //looks up the position in its table, the table may store any value for positions where the best move is a capture
+    fn probe_table(&self, state:&BitBoardState, kind:TableKind, wdl:i32) -> (i32, ProbeState){
+        let material = Material::from_state(state);
+        if material.num_pieces() == 2 {
+            return (WDL_DRAW, ProbeState::Ok);
+        }
+        //tables are named with the stronger side first, positions where black is the stronger side use the same table
+        let white = material.side_name(WHITE);
+        let black = material.side_name(BLACK);
+        let (name, black_stronger) = match self.tables.get_key_value(&format!("{white}v{black}")) {
+            Some((name, _)) => (name, false),
+            None => match self.tables.get_key_value(&format!("{black}v{white}")) {
+                Some((name, _)) => (name, true),
+                None => return (0, ProbeState::Fail),
+            },
+        };
+        match self.tables[name].table(name, kind) {
+            Some(table) => table.probe(state, black_stronger, wdl),
+            None => (0, ProbeState::Fail),
+        }
+    }
+
+    //win/draw/loss of the position, the captures (and pawn moves if check_zeroing is set) are searched, as the table
+    //does not have to store the right value when one of them is the best move
+    fn search(&self, state:&BitBoardState, check_zeroing:bool) -> (i32, ProbeState){
+        let mut position = *state;
+        let moves = position.gen_moves_legal().moves_vec();
+        let mut best = WDL_LOSS;
+        let mut searched = 0;
+        for chess_move in moves.iter(){
+            let zeroing = if check_zeroing {is_zeroing(state, chess_move)} else {is_capture(state, chess_move)};
+            if !zeroing {
+                continue;
+            }
+            searched += 1;
+            let (value, result) = self.search(&state.perform_move(*chess_move), false);
+            if result == ProbeState::Fail {
+                return (WDL_DRAW, ProbeState::Fail);
+            }
+            if -value > best {
+                best = -value;
+                if best >= WDL_WIN {
+                    return (best, ProbeState::ZeroingBestMove);
+                }
+            }
+        }
+
+        //if every move was searched the table is not needed, it may even be wrong, like for positions with en passant
+        let no_more_moves = searched > 0 && searched == moves.len();
+        let value = if no_more_moves {
+            best
+        }else{
+            let (value, result) = self.probe_table(state, TableKind::Wdl, WDL_DRAW);
+            if result == ProbeState::Fail {
+                return (WDL_DRAW, ProbeState::Fail);
+            }
+            value
+        };
+
+        if best >= value {
+            let result = if best > WDL_DRAW || no_more_moves {ProbeState::ZeroingBestMove} else {ProbeState::Ok};
+            return (best, result);
+        }
+        (value, ProbeState::Ok)
+    }
+
+    //plies to the next capture or pawn move on the best path, positive when winning, negative when losing and 0 for
+    //draws. cursed wins and blessed losses are 100 plies further away than their zeroing move
+    fn dtz(&self, state:&BitBoardState) -> (i32, ProbeState){
+        let (wdl, result) = self.search(state, true);
+        if result == ProbeState::Fail || wdl == WDL_DRAW {
+            return (0, result);
+        }
+        if result == ProbeState::ZeroingBestMove {
+            return (dtz_before_zeroing(wdl), result);
+        }
+
+        let (dtz, result) = self.probe_table(state, TableKind::Dtz, wdl);
+        match result {
+            ProbeState::Fail => return (0, result),
+            ProbeState::ChangeStm => {}
+            _ => {
+                let cursed = wdl == WDL_CURSED_WIN || wdl == WDL_BLESSED_LOSS;
+                return ((dtz + if cursed {100} else {0}) * wdl.signum(), result);
+            }
+        }
+
+        //the table only has the other side to move, the best move is found with a search of 1 ply
+        let mut min_dtz = i32::MAX;
+        let mut position = *state;
+        for chess_move in position.gen_moves_legal().moves_vec(){
+            let zeroing = is_zeroing(state, &chess_move);
+            let mut next = state.perform_move(chess_move);
+
+            //zeroing moves get the dtz from before the move, only the result after it is needed
+            let (mut dtz, result) = if zeroing {
+                let (value, result) = self.search(&next, false);
+                (-dtz_before_zeroing(value), result)
+            }else{
+                let (value, result) = self.dtz(&next);
+                (-value, result)
+            };
+            if result == ProbeState::Fail {
+                return (0, result);
+            }
+
+            if dtz == 1 && is_mate(&mut next) {
+                min_dtz = 1;
+            }
+            if !zeroing {
+                dtz += dtz.signum();
+            }
+            if dtz < min_dtz && dtz.signum() == wdl.signum() {
+                min_dtz = dtz;
+            }
+        }
+        //no legal moves, the side to move is mated
+        (if min_dtz == i32::MAX {-1} else {min_dtz}, ProbeState::Ok)
+    }
+}
+
+//score of a win/draw/loss result ply plies from the root, cursed wins and blessed losses are draws with the fifty move
+//rule. like mates, wins that are closer to the root score higher
+pub fn wdl_score(wdl:i32, ply:usize) -> i32{
+    match wdl {
+        WDL_WIN => TB_WIN - ply as i32,
+        WDL_LOSS => -TB_WIN + ply as i32,
+        _ => 0,
+    }
+}
@@ -0,0 +1,840 @@
+use std::sync::OnceLock;
+
+use crate::client::game::engine::state_bitboard::{BitBoardState, BLACK, KING, PAWN, WHITE};
+
+/*
+reading of a single syzygy table file, .rtbw for win/draw/loss or .rtbz for distance to zeroing.
+a position is turned into an index by placing the pieces in a fixed order, using the symmetries of the board to
+keep the index small. the values of all indices are compressed with huffman coded symbols that expand to pairs of
+symbols (recursive pairing), stored in blocks that are found through a sparse index.
+
+pieces are coded like in the table files: white pawn to king are 1 to 6, black pawn to king 9 to 14.
+squares are 0 for a1 to 63 for h8, like the rest of the engine.
+*/
+
+pub const MAX_PIECES:usize = 7;
+
+const WDL_MAGIC:[u8; 4] = [0x71, 0xE8, 0x23, 0x5D];
+const DTZ_MAGIC:[u8; 4] = [0xD7, 0x66, 0x0C, 0xA5];
+
+//flags of the table header
+const SPLIT:u8 = 1;
+const HAS_PAWNS:u8 = 2;
+
+//flags of each pairs data
+const STM:u8 = 1;
+const MAPPED:u8 = 2;
+const WIN_PLIES:u8 = 4;
+const LOSS_PLIES:u8 = 8;
+const WIDE:u8 = 16;
+const SINGLE_VALUE:u8 = 128;
+
+//win/draw/loss values, from the side to moves perspective
+pub const WDL_LOSS:i32 = -2;
+pub const WDL_BLESSED_LOSS:i32 = -1;
+pub const WDL_DRAW:i32 = 0;
+pub const WDL_CURSED_WIN:i32 = 1;
+pub const WDL_WIN:i32 = 2;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TableKind{
+    Wdl,
+    Dtz,
+}
+
+//the result of looking up a position, besides the value
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ProbeState{
+    Fail,
+    Ok,
+    ChangeStm, //dtz tables only store one side to move, the other side has to be probed with a 1 ply search
+    ZeroingBestMove, //the best move is a capture or pawn move, the table may store a "don't care" value
+}
+
+//lookup tables for turning positions into indices
+pub struct Encoding{
+    map_b1h1h7: [u64; 64], //squares below the a1-h8 diagonal to 0..27
+    map_a1d1d4: [u64; 64], //squares of the a1-d1-d4 triangle to 0..9, the diagonal last
+    map_kk: [[u64; 64]; 10], //the 462 placements of two kings, the first in the a1-d1-d4 triangle
+    binomial: [[u64; 64]; 6],
+    map_pawns: [u64; 64], //squares a2-h7 to the number of squares left for the other pawns
+    lead_pawn_idx: [[u64; 64]; 6],
+    lead_pawns_size: [[u64; 4]; 6],
+}
+
+fn off_a1h8(square:usize) -> i32{
+    (square / 8) as i32 - (square % 8) as i32
+}
+
+fn kings_touch(a:usize, b:usize) -> bool{
+    (a / 8).abs_diff(b / 8) <= 1 && (a % 8).abs_diff(b % 8) <= 1
+}
+
+impl Encoding{
+    fn new() -> Self{
+        let mut encoding = Self {
+            map_b1h1h7: [0; 64],
+            map_a1d1d4: [0; 64],
+            map_kk: [[0; 64]; 10],
+            binomial: [[0; 64]; 6],
+            map_pawns: [0; 64],
+            lead_pawn_idx: [[0; 64]; 6],
+            lead_pawns_size: [[0; 4]; 6],
+        };
+
+        let mut code = 0;
+        for square in 0..64{
+            if off_a1h8(square) < 0 {
+                encoding.map_b1h1h7[square] = code;
+                code += 1;
+            }
+        }
+
+        code = 0;
+        let mut diagonal = Vec::<usize>::new();
+        for rank in 0..4{
+            for file in 0..4{
+                let square = rank*8 + file;
+                if off_a1h8(square) < 0 {
+                    encoding.map_a1d1d4[square] = code;
+                    code += 1;
+                }else if off_a1h8(square) == 0 {
+                    diagonal.push(square);
+                }
+            }
+        }
+        for square in diagonal{
+            encoding.map_a1d1d4[square] = code;
+            code += 1;
+        }
+
+        //if the first king is on the a1-d4 diagonal, the other one is not above the a1-h8 diagonal
+        code = 0;
+        let mut both_on_diagonal = Vec::<(usize, usize)>::new();
+        for idx in 0..10{
+            for s1 in 0..28{
+                //b1 is mapped to 0
+                if encoding.map_a1d1d4[s1] != idx as u64 || !(idx != 0 || s1 == 1) || s1 % 8 > 3 || s1 / 8 > 3 {
+                    continue;
+                }
+                for s2 in 0..64{
+                    if kings_touch(s1, s2) || (off_a1h8(s1) == 0 && off_a1h8(s2) > 0) {
+                        continue;
+                    }
+                    if off_a1h8(s1) == 0 && off_a1h8(s2) == 0 {
+                        both_on_diagonal.push((idx, s2));
+                    }else{
+                        encoding.map_kk[idx][s2] = code;
+                        code += 1;
+                    }
+                }
+            }
+        }
+        for (idx, s2) in both_on_diagonal{
+            encoding.map_kk[idx][s2] = code;
+            code += 1;
+        }
+
+        encoding.binomial[0][0] = 1;
+        for n in 1..64{
+            for k in 0..6.min(n + 1){
+                encoding.binomial[k][n] = if k > 0 {encoding.binomial[k - 1][n - 1]} else {0}
+                    + if k < n {encoding.binomial[k][n - 1]} else {0};
+            }
+        }
+
+        //the leading pawn is the one with the highest map_pawns value, nearest to the edge and lowest on its file
+        let mut available_squares = 47;
+        for lead_pawns in 1..6{
+            for file in 0..4{
+                let mut idx = 0;
+                for rank in 1..7{
+                    let square = rank*8 + file;
+                    if lead_pawns == 1 {
+                        encoding.map_pawns[square] = available_squares;
+                        encoding.map_pawns[square ^ 7] = available_squares - 1;
+                        available_squares = available_squares.saturating_sub(2); //the last pair gets 1 and 0
+                    }
+                    encoding.lead_pawn_idx[lead_pawns][square] = idx;
+                    idx += encoding.binomial[lead_pawns - 1][encoding.map_pawns[square] as usize];
+                }
+                encoding.lead_pawns_size[lead_pawns][file] = idx;
+            }
+        }
+        encoding
+    }
+
+    pub fn get() -> &'static Encoding{
+        static ENCODING:OnceLock<Encoding> = OnceLock::new();
+        ENCODING.get_or_init(Encoding::new)
+    }
+
+    #[cfg(test)]
+    pub fn num_king_placements(&self) -> u64{
+        self.map_kk.iter().flat_map(|row| row.iter()).copied().max().unwrap() + 1
+    }
+
+    #[cfg(test)]
+    pub fn lead_pawns_size(&self, lead_pawns:usize, file:usize) -> u64{
+        self.lead_pawns_size[lead_pawns][file]
+    }
+}
+
+//piece counts of a table, parsed from its name like KRPvKR
+#[derive(Clone, PartialEq, Debug)]
+pub struct Material{
+    pub counts: [[u8; 6]; 2], //[color][piece], colors and pieces indexed like BitBoardState
+}
+
+impl Material{
+    pub fn from_name(name:&str) -> Option<Self>{
+        let (white, black) = name.split_once('v')?;
+        let mut counts = [[0; 6]; 2];
+        for (color, pieces) in [(WHITE, white), (BLACK, black)]{
+            for c in pieces.chars(){
+                let piece = "PNBRQK".find(c)?;
+                counts[color][piece] += 1;
+            }
+            if counts[color][KING] != 1 {
+                return None;
+            }
+        }
+        Some(Self { counts })
+    }
+
+    pub fn from_state(state:&BitBoardState) -> Self{
+        let piece_bb = state.piece_bb();
+        let mut counts = [[0; 6]; 2];
+        for color in [BLACK, WHITE]{
+            for piece in 0..6{
+                counts[color][piece] = piece_bb[color][piece].count_ones() as u8;
+            }
+        }
+        Self { counts }
+    }
+
+    //the name of the pieces of one side, from the strongest piece down
+    pub fn side_name(&self, color:usize) -> String{
+        let mut name = String::new();
+        for (piece, &c) in b"PNBRQK".iter().enumerate().rev(){
+            for _ in 0..self.counts[color][piece]{
+                name.push(c as char);
+            }
+        }
+        name
+    }
+
+    pub fn num_pieces(&self) -> usize{
+        self.counts.iter().flat_map(|counts| counts.iter()).map(|&count| count as usize).sum()
+    }
+
+    fn is_symmetric(&self) -> bool{
+        self.counts[WHITE] == self.counts[BLACK]
+    }
+}
+
+#[derive(Default, Clone)]
+struct PairsData{
+    flags: u8,
+    max_sym_len: u8,
+    min_sym_len: u8, //the value of the table, if it only stores a single value
+    num_blocks: u32,
+    block_size: u64,
+    span: u64, //about every span values there is a sparse index entry
+    lowest_sym: usize, //offsets into the file
+    btree: usize,
+    block_length: usize,
+    block_length_size: u32,
+    sparse_index: usize,
+    sparse_index_size: u64,
+    data: usize,
+    base64: Vec<u64>, //base64[l - min_sym_len] is the lowest symbol of length l, padded to 64 bits
+    symlen: Vec<u8>, //number of values minus one a symbol expands to
+    pieces: [u8; MAX_PIECES],
+    group_idx: [u64; MAX_PIECES + 1],
+    group_len: [usize; MAX_PIECES + 1], //zero terminated
+    map_idx: [u16; 4], //dtz only, offsets of the value maps for win, loss, cursed win and blessed loss
+}
+
+pub struct Table{
+    kind: TableKind,
+    bytes: Vec<u8>,
+    symmetric: bool,
+    num_pieces: usize,
+    has_pawns: bool,
+    has_unique_pieces: bool,
+    pawn_count: [u8; 2], //[leading color, other color]
+    items: [[PairsData; 4]; 2], //[side to move][file of the leading pawn, or 0]
+    map: usize, //dtz value maps
+}
+
+//reads of the file are None past its end, offsets read from a broken file can point anywhere
+fn le_u16(bytes:&[u8], offset:usize) -> Option<u16>{
+    Some(u16::from_le_bytes(bytes.get(offset..offset.checked_add(2)?)?.try_into().unwrap()))
+}
+
+fn le_u32(bytes:&[u8], offset:usize) -> Option<u32>{
+    Some(u32::from_le_bytes(bytes.get(offset..offset.checked_add(4)?)?.try_into().unwrap()))
+}
+
+//big endian read that treats bytes past the end of the file as 0, the last block may be cut short
+fn be_u32_padded(bytes:&[u8], offset:usize) -> u32{
+    let mut value = 0;
+    for i in 0..4{
+        value = value << 8 | *bytes.get(offset + i).unwrap_or(&0) as u32;
+    }
+    value
+}
+
+impl Table{
+    //bytes is the whole file, material is taken from the file name
+    pub fn new(kind:TableKind, bytes:Vec<u8>, material:&Material) -> Result<Self, String>{
+        let magic = if kind == TableKind::Wdl {WDL_MAGIC} else {DTZ_MAGIC};
+        if bytes.len() < 5 || bytes[0..4] != magic {
+            return Err("wrong magic number".to_string());
+        }
+
+        let has_pawns = material.counts[WHITE][PAWN] + material.counts[BLACK][PAWN] > 0;
+        let has_unique_pieces = material.counts.iter().any(|counts| counts[..KING].contains(&1));
+        //the side with less pawns leads, as that compresses better
+        let white_leads = material.counts[BLACK][PAWN] == 0
+            || (material.counts[WHITE][PAWN] > 0 && material.counts[BLACK][PAWN] >= material.counts[WHITE][PAWN]);
+        let (lead, other) = if white_leads {(WHITE, BLACK)} else {(BLACK, WHITE)};
+
+        let mut table = Self {
+            kind,
+            bytes,
+            symmetric: material.is_symmetric(),
+            num_pieces: material.num_pieces(),
+            has_pawns,
+            has_unique_pieces,
+            pawn_count: [material.counts[lead][PAWN], material.counts[other][PAWN]],
+            items: Default::default(),
+            map: 0,
+        };
+        table.set()?;
+        Ok(table)
+    }
+
+    fn sides(&self) -> usize{
+        if self.kind == TableKind::Wdl && !self.symmetric {2} else {1}
+    }
+
+    fn pairs(&self, stm:usize, file:usize) -> &PairsData{
+        let side = if self.kind == TableKind::Wdl {stm % 2} else {0};
+        &self.items[side][if self.has_pawns {file} else {0}]
+    }
+
+    //reads the header, the layout of every part of the file is only known after reading the parts before it
+    fn set(&mut self) -> Result<(), String>{
+        let check = |table:&Self, offset:usize| -> Result<(), String> {
+            if offset > table.bytes.len() {Err("file is too short".to_string())} else {Ok(())}
+        };
+        let flags = self.bytes[4];
+        if self.has_pawns != (flags & HAS_PAWNS != 0) || (self.kind == TableKind::Wdl && self.symmetric == (flags & SPLIT != 0)) {
+            return Err("header does not match the name of the table".to_string());
+        }
+        let mut data = 5;
+
+        let sides = self.sides();
+        let max_file = if self.has_pawns {3} else {0};
+        let pawns_on_both_sides = self.has_pawns && self.pawn_count[1] > 0;
+        let encoding = Encoding::get();
+
+        for file in 0..=max_file{
+            check(self, data + 2 + self.num_pieces)?;
+            let order = [
+                [self.bytes[data] & 0xF, if pawns_on_both_sides {self.bytes[data + 1] & 0xF} else {0xF}],
+                [self.bytes[data] >> 4, if pawns_on_both_sides {self.bytes[data + 1] >> 4} else {0xF}],
+            ];
+            data += 1 + pawns_on_both_sides as usize;
+
+            for k in 0..self.num_pieces{
+                for side in 0..sides{
+                    self.items[side][file].pieces[k] = if side == 1 {self.bytes[data] >> 4} else {self.bytes[data] & 0xF};
+                }
+                data += 1;
+            }
+
+            for (side, &order) in order.iter().enumerate().take(sides){
+                self.set_groups(side, file, order, encoding)?;
+            }
+        }
+        data += data & 1;
+
+        for file in 0..=max_file{
+            for side in 0..sides{
+                data = self.set_sizes(side, file, data)?;
+            }
+        }
+
+        if self.kind == TableKind::Dtz {
+            data = self.set_dtz_map(data, max_file)?;
+        }
+
+        for file in 0..=max_file{
+            for side in 0..sides{
+                let d = &mut self.items[side][file];
+                d.sparse_index = data;
+                data += d.sparse_index_size as usize * 6;
+            }
+        }
+        for file in 0..=max_file{
+            for side in 0..sides{
+                let d = &mut self.items[side][file];
+                d.block_length = data;
+                data += d.block_length_size as usize * 2;
+            }
+        }
+        for file in 0..=max_file{
+            for side in 0..sides{
+                //single value tables have no blocks, the file may end before the alignment
+                let d = &mut self.items[side][file];
+                if d.num_blocks > 0 {
+                    data = (data + 0x3F) & !0x3F;
+                    d.data = data;
+                    data += d.num_blocks as usize * d.block_size as usize;
+                }
+            }
+        }
+        check(self, data)
+    }
+
+    //the pieces of the table are split in groups, the leading group is placed first and every other group on the
+    //squares left, the groups are combined in the order of order[]
+    fn set_groups(&mut self, side:usize, file:usize, order:[u8; 2], encoding:&Encoding) -> Result<(), String>{
+        let has_pawns = self.has_pawns;
+        let has_unique_pieces = self.has_unique_pieces;
+        let num_pieces = self.num_pieces;
+        let pawns_on_both_sides = has_pawns && self.pawn_count[1] > 0;
+        let d = &mut self.items[side][file];
+
+        let mut n = 0;
+        let mut first_len:i32 = if has_pawns {0} else if has_unique_pieces {3} else {2};
+        d.group_len[n] = 1;
+        for i in 1..num_pieces{
+            first_len -= 1;
+            if first_len > 0 || d.pieces[i] == d.pieces[i - 1] {
+                d.group_len[n] += 1;
+            }else{
+                n += 1;
+                d.group_len[n] = 1;
+            }
+        }
+        n += 1;
+        d.group_len[n] = 0;
+        //the encoding has no larger groups, only a broken header has them
+        if d.group_len.iter().any(|&len| len > 5) || (has_pawns && d.group_len[0] == 0) {
+            return Err("invalid piece groups".to_string());
+        }
+
+        let mut next = if pawns_on_both_sides {2} else {1};
+        let mut free_squares = 64 - d.group_len[0] - if pawns_on_both_sides {d.group_len[1]} else {0};
+        let mut idx:u64 = 1;
+        let mut k = 0;
+        while next < n || k == order[0] as usize || k == order[1] as usize {
+            if k == order[0] as usize {
+                d.group_idx[0] = idx;
+                idx *= if has_pawns {
+                    encoding.lead_pawns_size[d.group_len[0]][file]
+                }else if has_unique_pieces {
+                    31332
+                }else{
+                    462
+                };
+            }else if k == order[1] as usize {
+                d.group_idx[1] = idx;
+                idx *= encoding.binomial[d.group_len[1]][48 - d.group_len[0]];
+            }else{
+                d.group_idx[next] = idx;
+                idx *= encoding.binomial[d.group_len[next]][free_squares];
+                free_squares -= d.group_len[next];
+                next += 1;
+            }
+            k += 1;
+        }
+        d.group_idx[n] = idx;
+        Ok(())
+    }
+
+    fn set_sizes(&mut self, side:usize, file:usize, mut data:usize) -> Result<usize, String>{
+        let too_short = || "file is too short".to_string();
+        let bytes = &self.bytes;
+        let d = &mut self.items[side][file];
+        if data + 2 > bytes.len() {
+            return Err(too_short());
+        }
+        d.flags = bytes[data];
+        data += 1;
+
+        if d.flags & SINGLE_VALUE != 0 {
+            d.num_blocks = 0;
+            d.span = 0;
+            d.block_length_size = 0;
+            d.sparse_index_size = 0;
+            d.min_sym_len = bytes[data];
+            return Ok(data + 1);
+        }
+        if data + 9 > bytes.len() {
+            return Err(too_short());
+        }
+
+        let tb_size = d.group_idx[d.group_len.iter().position(|&len| len == 0).unwrap()];
+        if bytes[data] >= 32 || bytes[data + 1] >= 64 {
+            return Err("invalid block size".to_string());
+        }
+        d.block_size = 1 << bytes[data];
+        d.span = 1 << bytes[data + 1];
+        d.sparse_index_size = tb_size.div_ceil(d.span);
+        let padding = bytes[data + 2] as u32;
+        d.num_blocks = le_u32(bytes, data + 3).ok_or_else(too_short)?;
+        d.block_length_size = d.num_blocks.checked_add(padding).ok_or("invalid number of blocks")?;
+        d.max_sym_len = bytes[data + 7];
+        d.min_sym_len = bytes[data + 8];
+        data += 9;
+        if d.min_sym_len == 0 || d.max_sym_len < d.min_sym_len || d.max_sym_len > 64 {
+            return Err("invalid symbol lengths".to_string());
+        }
+        d.lowest_sym = data;
+
+        //in the canonical huffman code longer symbols have lower values
+        let num_lengths = (d.max_sym_len - d.min_sym_len + 1) as usize;
+        if data + num_lengths*2 + 2 > bytes.len() {
+            return Err(too_short());
+        }
+        d.base64 = vec![0; num_lengths];
+        for i in (0..num_lengths - 1).rev(){
+            let lowest = |i:usize| le_u16(bytes, d.lowest_sym + 2*i).unwrap() as u64; //inside the file, checked above
+            d.base64[i] = (d.base64[i + 1] + lowest(i)).checked_sub(lowest(i + 1)).ok_or("invalid symbol lengths")? / 2;
+        }
+        for i in 0..num_lengths{
+            let shift = 64 - i as u32 - d.min_sym_len as u32;
+            d.base64[i] = if shift >= 64 {0} else {d.base64[i] << shift};
+        }
+
+        data += num_lengths*2;
+        let num_symbols = le_u16(bytes, data).ok_or_else(too_short)? as usize;
+        data += 2;
+        d.btree = data;
+        if data + num_symbols*3 > bytes.len() {
+            return Err(too_short());
+        }
+        //every pair has to be made of symbols of the table
+        for sym in 0..num_symbols{
+            let (left, right) = Self::btree(bytes, d, sym).ok_or_else(too_short)?;
+            if right != 0xFFF && (left >= num_symbols || right >= num_symbols) {
+                return Err("invalid symbol pairs".to_string());
+            }
+        }
+
+        d.symlen = vec![0; num_symbols];
+        let mut visited = vec![false; num_symbols];
+        for sym in 0..num_symbols{
+            if !visited[sym] {
+                let len = Self::set_symlen(bytes, d, sym, &mut visited);
+                d.symlen[sym] = len;
+            }
+        }
+        Ok(data + num_symbols*3 + (num_symbols & 1))
+    }
+
+    fn btree(bytes:&[u8], d:&PairsData, sym:usize) -> Option<(usize, usize)>{
+        let lr = bytes.get(d.btree + 3*sym..d.btree + 3*sym + 3)?;
+        let left = ((lr[1] as usize & 0xF) << 8) | lr[0] as usize;
+        let right = ((lr[2] as usize) << 4) | (lr[1] as usize >> 4);
+        Some((left, right))
+    }
+
+    fn set_symlen(bytes:&[u8], d:&mut PairsData, sym:usize, visited:&mut [bool]) -> u8{
+        visited[sym] = true;
+        let (left, right) = Self::btree(bytes, d, sym).unwrap(); //checked by set_sizes
+        if right == 0xFFF {
+            return 0;
+        }
+        if !visited[left] {
+            let len = Self::set_symlen(bytes, d, left, visited);
+            d.symlen[left] = len;
+        }
+        if !visited[right] {
+            let len = Self::set_symlen(bytes, d, right, visited);
+            d.symlen[right] = len;
+        }
+        d.symlen[left].wrapping_add(d.symlen[right]).wrapping_add(1)
+    }
+
+    fn set_dtz_map(&mut self, mut data:usize, max_file:usize) -> Result<usize, String>{
+        self.map = data;
+        for file in 0..=max_file{
+            let flags = self.items[0][file].flags;
+            if flags & MAPPED == 0 {
+                continue;
+            }
+            if flags & WIDE != 0 {
+                data += data & 1;
+                for i in 0..4{
+                    if data + 2 > self.bytes.len() {
+                        return Err("file is too short".to_string());
+                    }
+                    self.items[0][file].map_idx[i] = ((data - self.map) / 2 + 1) as u16;
+                    data += 2*le_u16(&self.bytes, data).unwrap() as usize + 2;
+                }
+            }else{
+                for i in 0..4{
+                    if data >= self.bytes.len() {
+                        return Err("file is too short".to_string());
+                    }
+                    self.items[0][file].map_idx[i] = (data - self.map + 1) as u16;
+                    data += self.bytes[data] as usize + 1;
+                }
+            }
+        }
+        Ok(data + (data & 1))
+    }
+
+    //value stored at idx, None when the offsets of the file point outside of it
+    fn decompress_pairs(&self, d:&PairsData, idx:u64) -> Option<i32>{
+        if d.flags & SINGLE_VALUE != 0 {
+            return Some(d.min_sym_len as i32);
+        }
+        let bytes = &self.bytes;
+
+        //the sparse index gives a block and an offset in it for a value near idx
+        let k = (idx / d.span) as usize;
+        if k as u64 >= d.sparse_index_size {
+            return None;
+        }
+        let mut block = le_u32(bytes, d.sparse_index + 6*k)? as usize;
+        let mut offset = le_u16(bytes, d.sparse_index + 6*k + 4)? as i64;
+        offset += (idx % d.span) as i64 - (d.span / 2) as i64;
+
+        let block_length = |block:usize| -> Option<i64> {
+            if block >= d.block_length_size as usize {
+                return None;
+            }
+            Some(le_u16(bytes, d.block_length + 2*block)? as i64)
+        };
+        while offset < 0 {
+            block = block.checked_sub(1)?;
+            offset += block_length(block)? + 1;
+        }
+        while offset > block_length(block)? {
+            offset -= block_length(block)? + 1;
+            block += 1;
+        }
+        if block >= d.num_blocks as usize {
+            return None;
+        }
+
+        //find the symbol that covers the offset, symbols are read from a 64 bit buffer
+        let mut ptr = d.data + block * d.block_size as usize;
+        let mut buf64 = (be_u32_padded(bytes, ptr) as u64) << 32 | be_u32_padded(bytes, ptr + 4) as u64;
+        ptr += 8;
+        let mut buf64_size = 64;
+        let mut sym;
+        loop {
+            let mut len = 0;
+            while buf64 < *d.base64.get(len)? {
+                len += 1;
+            }
+            sym = ((buf64 - d.base64[len]) >> (64 - len - d.min_sym_len as usize)) as usize;
+            sym += le_u16(bytes, d.lowest_sym + 2*len)? as usize;
+
+            let sym_len = *d.symlen.get(sym)? as i64;
+            if offset < sym_len + 1 {
+                break;
+            }
+            offset -= sym_len + 1;
+            len += d.min_sym_len as usize;
+            buf64 = if len >= 64 {0} else {buf64 << len};
+            buf64_size -= len as i32;
+            if buf64_size <= 32 {
+                buf64_size += 32;
+                buf64 |= (be_u32_padded(bytes, ptr) as u64) << (64 - buf64_size);
+                ptr += 4;
+            }
+        }
+
+        //expand the symbol into its pair until the symbol holding the value at offset is found
+        while d.symlen[sym] != 0 {
+            let (left, right) = Self::btree(bytes, d, sym)?;
+            let next = if offset < d.symlen[left] as i64 + 1 {
+                left
+            }else{
+                offset -= d.symlen[left] as i64 + 1;
+                right
+            };
+            //the pairs of a broken file can go in circles, real ones always expand to fewer values
+            if d.symlen[next] >= d.symlen[sym] {
+                return None;
+            }
+            sym = next;
+        }
+        Some(Self::btree(bytes, d, sym)?.0 as i32)
+    }
+
+    //turns the stored value into a wdl value, or a dtz in plies
+    fn map_score(&self, file:usize, value:i32, wdl:i32) -> Option<i32>{
+        if self.kind == TableKind::Wdl {
+            //only a broken file has other values, the search indexes with them
+            return Some(value - 2).filter(|wdl| (WDL_LOSS..=WDL_WIN).contains(wdl));
+        }
+        const WDL_MAP:[usize; 5] = [1, 3, 0, 2, 0];
+        let d = self.pairs(0, file);
+        let mut value = value;
+        if d.flags & MAPPED != 0 {
+            let idx = d.map_idx[WDL_MAP[(wdl + 2) as usize]] as usize + value as usize;
+            value = if d.flags & WIDE != 0 {
+                le_u16(&self.bytes, self.map + 2*idx)? as i32
+            }else{
+                *self.bytes.get(self.map + idx)? as i32
+            };
+        }
+        //tables store either moves or plies, plies are returned
+        if (wdl == WDL_WIN && d.flags & WIN_PLIES == 0) || (wdl == WDL_LOSS && d.flags & LOSS_PLIES == 0)
+            || wdl == WDL_CURSED_WIN || wdl == WDL_BLESSED_LOSS {
+            value *= 2;
+        }
+        Some(value + 1)
+    }
+
+    //looks up the position, black_stronger is set if the white pieces of the position are the black pieces of the table
+    pub fn probe(&self, state:&BitBoardState, black_stronger:bool, wdl:i32) -> (i32, ProbeState){
+        let encoding = Encoding::get();
+        let piece_bb = state.piece_bb();
+        let black_to_move = !state.white_to_move();
+
+        //tables store white as the stronger side and symmetric tables only white to move, otherwise colors are swapped
+        let flip = (self.symmetric && black_to_move) || black_stronger;
+        let flip_color = if flip {8} else {0};
+        let flip_squares = if flip {56} else {0};
+        let stm = (flip ^ black_to_move) as usize;
+
+        let mut squares = [0usize; MAX_PIECES];
+        let mut pieces = [0u8; MAX_PIECES];
+        let mut size = 0;
+        let mut lead_pawns_count = 0;
+        let mut lead_pawns = 0;
+        let mut file = 0;
+
+        let pawns_compare = |a:&usize, b:&usize| encoding.map_pawns[*a].cmp(&encoding.map_pawns[*b]);
+
+        //the leading pawns are the ones with the color of the first piece of the table
+        if self.has_pawns {
+            let lead_color = if (self.pairs(0, 0).pieces[0] ^ flip_color) & 8 != 0 {BLACK} else {WHITE};
+            lead_pawns = piece_bb[lead_color][PAWN];
+            let mut b = lead_pawns;
+            while b != 0 {
+                squares[size] = b.trailing_zeros() as usize ^ flip_squares;
+                b &= b - 1;
+                size += 1;
+            }
+            lead_pawns_count = size;
+            //the first piece of a broken file can be of the color without pawns
+            let Some(lead) = (0..lead_pawns_count).max_by(|&a, &b| pawns_compare(&squares[a], &squares[b]).then(b.cmp(&a))) else {
+                return (0, ProbeState::Fail);
+            };
+            squares.swap(0, lead);
+            file = (squares[0] % 8).min(7 - squares[0] % 8);
+        }
+
+        if self.kind == TableKind::Dtz && !((self.pairs(stm, file).flags & STM) as usize == stm || (self.symmetric && !self.has_pawns)) {
+            return (0, ProbeState::ChangeStm);
+        }
+
+        let mut b = (piece_bb[WHITE].iter().chain(piece_bb[BLACK].iter()).fold(0, |all, bb| all | bb)) ^ lead_pawns;
+        while b != 0 {
+            let square = b.trailing_zeros() as usize;
+            b &= b - 1;
+            let color = if piece_bb[WHITE].iter().any(|bb| bb & (1 << square) != 0) {WHITE} else {BLACK};
+            let piece = (0..6).find(|&piece| piece_bb[color][piece] & (1 << square) != 0).unwrap();
+            squares[size] = square ^ flip_squares;
+            pieces[size] = (piece as u8 + 1) | if color == BLACK {8} else {0};
+            pieces[size] ^= flip_color;
+            size += 1;
+        }
+
+        let d = self.pairs(stm, file);
+
+        //order the pieces like the table does
+        for i in lead_pawns_count..size.saturating_sub(1){
+            for j in i + 1..size{
+                if d.pieces[i] == pieces[j] {
+                    pieces.swap(i, j);
+                    squares.swap(i, j);
+                    break;
+                }
+            }
+        }
+
+        //the leading piece goes to the a1-d1-d4 triangle, or the a-d files for pawns
+        if squares[0] % 8 > 3 {
+            for square in squares.iter_mut().take(size){
+                *square ^= 7;
+            }
+        }
+
+        let mut idx:u64;
+        if self.has_pawns {
+            idx = encoding.lead_pawn_idx[lead_pawns_count][squares[0]];
+            squares[1..lead_pawns_count].sort_by(pawns_compare);
+            for (i, &square) in squares.iter().enumerate().take(lead_pawns_count).skip(1){
+                idx += encoding.binomial[i][encoding.map_pawns[square] as usize];
+            }
+        }else{
+            if squares[0] / 8 > 3 {
+                for square in squares.iter_mut().take(size){
+                    *square ^= 56;
+                }
+            }
+            //the first piece of the leading group not on the a1-h8 diagonal has to be below it
+            if let Some(i) = (0..d.group_len[0]).find(|&i| off_a1h8(squares[i]) != 0)
+                && off_a1h8(squares[i]) > 0 {
+                for square in squares.iter_mut().take(size).skip(i){
+                    *square = ((*square >> 3) | (*square << 3)) & 63;
+                }
+            }
+
+            idx = if self.has_unique_pieces {
+                let adjust1 = (squares[1] > squares[0]) as u64;
+                let adjust2 = (squares[2] > squares[0]) as u64 + (squares[2] > squares[1]) as u64;
+                let rank = |square:usize| (square / 8) as u64;
+                if off_a1h8(squares[0]) != 0 {
+                    (encoding.map_a1d1d4[squares[0]] * 63 + (squares[1] as u64 - adjust1)) * 62 + squares[2] as u64 - adjust2
+                }else if off_a1h8(squares[1]) != 0 {
+                    (6*63 + rank(squares[0]) * 28 + encoding.map_b1h1h7[squares[1]]) * 62 + squares[2] as u64 - adjust2
+                }else if off_a1h8(squares[2]) != 0 {
+                    6*63*62 + 4*28*62 + rank(squares[0]) * 7*28 + (rank(squares[1]) - adjust1) * 28 + encoding.map_b1h1h7[squares[2]]
+                }else{
+                    6*63*62 + 4*28*62 + 4*7*28 + rank(squares[0]) * 7*6 + (rank(squares[1]) - adjust1) * 6 + (rank(squares[2]) - adjust2)
+                }
+            }else{
+                encoding.map_kk[encoding.map_a1d1d4[squares[0]] as usize][squares[1]]
+            };
+        }
+
+        //the other groups are placed on the squares left, in ascending order
+        idx *= d.group_idx[0];
+        let mut group_start = d.group_len[0];
+        let mut remaining_pawns = self.has_pawns && self.pawn_count[1] > 0;
+        let mut next = 1;
+        while d.group_len[next] != 0 {
+            let group_end = group_start + d.group_len[next];
+            squares[group_start..group_end].sort();
+            let mut n = 0;
+            for i in 0..d.group_len[next]{
+                let square = squares[group_start + i];
+                let adjust = squares[..group_start].iter().filter(|&&other| square > other).count();
+                n += encoding.binomial[i + 1][square - adjust - if remaining_pawns {8} else {0}];
+            }
+            remaining_pawns = false;
+            idx += n * d.group_idx[next];
+            group_start = group_end;
+            next += 1;
+        }
+
+        match self.decompress_pairs(d, idx).and_then(|value| self.map_score(file, value, wdl)) {
+            Some(value) => (value, ProbeState::Ok),
+            None => (0, ProbeState::Fail),
+        }
+    }
+}
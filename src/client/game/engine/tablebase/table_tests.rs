@@ -0,0 +1,250 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::state_bitboard::{BitBoardState, BLACK, KING, PAWN, ROOK, WHITE};
+    use crate::client::game::engine::tablebase::table::{Encoding, Material, ProbeState, Table, TableKind, WDL_LOSS, WDL_WIN};
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    //win/draw/loss table where every value with white to move is one bit of the data, 0 for a loss and 1 for a win,
+    //with the symbols of the huffman code being 1 bit long. black to move is a single value loss.
+    //header has the flags and the order and pieces of every file, sizes the number of values of every file
+    fn bit_table(header:&[u8], sizes:&[u16]) -> Vec<u8>{
+        let mut bytes = vec![0x71, 0xE8, 0x23, 0x5D];
+        bytes.extend(header);
+        if bytes.len() % 2 == 1 {
+            bytes.push(0);
+        }
+        for _ in sizes{
+            //white to move, one block of 4096 bytes
+            bytes.extend([0, 12, 15, 0]); //flags, block size, span, padding
+            bytes.extend(1u32.to_le_bytes()); //number of blocks
+            bytes.extend([1, 1]); //maximum and minimum symbol length
+            bytes.extend(0u16.to_le_bytes()); //lowest symbol of length 1
+            bytes.extend(2u16.to_le_bytes()); //number of symbols
+            bytes.extend([0, 0xF0, 0xFF]); //symbol 0 is the value 0
+            bytes.extend([4, 0xF0, 0xFF]); //symbol 1 is the value 4
+            //black to move
+            bytes.extend([0x80, 0]);
+        }
+        //sparse index, the value in the middle of the span is value 16384 of block 0
+        for _ in sizes{
+            bytes.extend(0u32.to_le_bytes());
+            bytes.extend(16384u16.to_le_bytes());
+        }
+        for size in sizes{
+            bytes.extend((size - 1).to_le_bytes()); //block length
+        }
+
+        let mut state:u64 = 7;
+        for _ in sizes{
+            bytes.resize(bytes.len().next_multiple_of(64), 0);
+            for _ in 0..4096{
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                bytes.push(state as u8);
+            }
+        }
+        bytes
+    }
+
+    //split table, white king, white queen and black king
+    fn kqvk_bit_table() -> Vec<u8>{
+        bit_table(&[1, 0x00, 0x66, 0x55, 0xEE], &[31332])
+    }
+
+    //split table with pawns, for every file the pawn, white king and black king
+    fn kpvk_bit_table() -> Vec<u8>{
+        let mut header = vec![3];
+        for _ in 0..4{
+            header.extend([0x00, 0x11, 0x66, 0xEE]);
+        }
+        //6 squares for the pawn on each file, 63 for the white king and 62 for the black king
+        bit_table(&header, &[6*63*62; 4])
+    }
+
+    fn fen(pieces:&[(char, usize, usize)], white_to_move:bool) -> String{
+        let mut board = [['1'; 8]; 8];
+        for &(piece, rank, file) in pieces{
+            board[rank][file] = piece;
+        }
+        let ranks:Vec<String> = board.iter().rev().map(|rank| rank.iter().collect()).collect();
+        format!("{} {} - - 0 1", ranks.join("/"), if white_to_move {"w"} else {"b"})
+    }
+
+    #[test]
+    fn encoding(){
+        let encoding = Encoding::get();
+        assert_eq!(encoding.num_king_placements(), 462);
+        //the leading pawn on the a file can be on 6 squares, a second pawn on the squares left by each of them
+        assert_eq!(encoding.lead_pawns_size(1, 0), 6);
+        assert_eq!(encoding.lead_pawns_size(2, 0), 47 + 45 + 43 + 41 + 39 + 37);
+    }
+
+    #[test]
+    fn material(){
+        let material = Material::from_name("KRPvKR").unwrap();
+        assert_eq!(material.counts[WHITE][ROOK], 1);
+        assert_eq!(material.counts[WHITE][PAWN], 1);
+        assert_eq!(material.counts[BLACK][ROOK], 1);
+        assert_eq!(material.counts[BLACK][KING], 1);
+        assert_eq!(material.num_pieces(), 5);
+        assert_eq!(material.side_name(WHITE), "KRP");
+        assert_eq!(material.side_name(BLACK), "KR");
+
+        assert_eq!(Material::from_name("KRvR"), None);
+        assert_eq!(Material::from_name("KXvK"), None);
+        assert_eq!(Material::from_name("KRK"), None);
+
+        setup_sliding_magics();
+        let state = BitBoardState::from_fen("8/8/4k3/8/2r5/8/1PP5/1K2N3 w - - 0 1").unwrap();
+        let material = Material::from_state(&state);
+        assert_eq!(material.side_name(WHITE), "KNPP");
+        assert_eq!(material.side_name(BLACK), "KR");
+    }
+
+    #[test]
+    fn invalid_tables(){
+        let material = Material::from_name("KQvK").unwrap();
+        let table = kqvk_bit_table();
+        assert!(Table::new(TableKind::Wdl, table.clone(), &material).is_ok());
+        //the magic of the other kind of table
+        assert!(Table::new(TableKind::Dtz, table.clone(), &material).is_err());
+        //not split, while KQvK has different tables for both sides to move
+        let mut not_split = table.clone();
+        not_split[4] = 0;
+        assert!(Table::new(TableKind::Wdl, not_split, &material).is_err());
+        //the header says there are pawns
+        assert!(Table::new(TableKind::Wdl, table.clone(), &Material::from_name("KPvK").unwrap()).is_err());
+        assert!(Table::new(TableKind::Wdl, table[..4000].to_vec(), &material).is_err());
+    }
+
+    //offsets read from a truncated or corrupt file can point anywhere, the table is refused or the probe fails
+    #[test]
+    fn corrupt_tables(){
+        setup_sliding_magics();
+        let tables = [
+            ("KQvK", kqvk_bit_table(), vec![
+                fen(&[('K', 2, 2), ('Q', 1, 5), ('k', 7, 0)], true),
+                fen(&[('K', 0, 0), ('Q', 6, 7), ('k', 4, 4)], true),
+                fen(&[('K', 7, 7), ('Q', 3, 3), ('k', 0, 1)], false),
+            ]),
+            ("KPvK", kpvk_bit_table(), vec![
+                fen(&[('P', 1, 0), ('K', 0, 4), ('k', 7, 4)], true),
+                fen(&[('P', 6, 3), ('K', 4, 5), ('k', 1, 1)], true),
+                fen(&[('P', 3, 6), ('K', 0, 0), ('k', 7, 7)], false),
+            ]),
+        ];
+
+        for (name, table, fens) in tables{
+            let material = Material::from_name(name).unwrap();
+            let states:Vec<BitBoardState> = fens.iter().map(|fen| BitBoardState::from_fen(fen).unwrap()).collect();
+            let probe_all = |bytes:Vec<u8>| {
+                if let Ok(table) = Table::new(TableKind::Wdl, bytes, &material) {
+                    for state in &states{
+                        let (value, result) = table.probe(state, false, 0);
+                        assert!(result != ProbeState::Ok || (WDL_LOSS..=WDL_WIN).contains(&value), "{name} {value}");
+                    }
+                }
+            };
+
+            //the header, the sizes and the start of the data
+            let header_end = table.len() - 4096*(table.len() / 4096) + 64;
+            for length in (0..table.len()).step_by(7).chain(0..header_end){
+                probe_all(table[..length].to_vec());
+            }
+            for offset in 4..header_end{
+                for value in [0x00, 0x01, 0x0F, 0x7F, 0x80, 0xFF]{
+                    let mut corrupt = table.clone();
+                    corrupt[offset] = value;
+                    probe_all(corrupt);
+                }
+            }
+        }
+    }
+
+    //pawns can only be mirrored from one side of the board to the other, or swapped with a pawn of the other color
+    #[test]
+    fn pawn_symmetries(){
+        setup_sliding_magics();
+        let table = Table::new(TableKind::Wdl, kpvk_bit_table(), &Material::from_name("KPvK").unwrap()).unwrap();
+
+        //pawn, white king and black king as rank and file, with white to move
+        let positions = [
+            [(1, 0), (0, 4), (7, 4)],
+            [(3, 2), (2, 6), (5, 1)],
+            [(6, 3), (4, 5), (1, 1)],
+            [(4, 1), (7, 7), (2, 3)],
+            [(2, 0), (1, 1), (5, 5)],
+            [(5, 2), (0, 0), (7, 0)],
+        ];
+
+        let mut values = Vec::new();
+        for squares in positions{
+            let (value, result) = table.probe(&BitBoardState::from_fen(&fen(&[('P', squares[0].0, squares[0].1), ('K', squares[1].0, squares[1].1), ('k', squares[2].0, squares[2].1)], true)).unwrap(), false, 0);
+            assert_eq!(result, ProbeState::Ok);
+            values.push(value);
+
+            let [pawn, king, other_king] = squares.map(|(rank, file)| (rank, 7 - file));
+            let mirrored = BitBoardState::from_fen(&fen(&[('P', pawn.0, pawn.1), ('K', king.0, king.1), ('k', other_king.0, other_king.1)], true)).unwrap();
+            assert_eq!(table.probe(&mirrored, false, 0), (value, ProbeState::Ok), "{}", mirrored.to_fen());
+
+            let [pawn, king, other_king] = squares.map(|(rank, file)| (7 - rank, file));
+            let swapped = BitBoardState::from_fen(&fen(&[('p', pawn.0, pawn.1), ('k', king.0, king.1), ('K', other_king.0, other_king.1)], false)).unwrap();
+            assert_eq!(table.probe(&swapped, true, 0), (value, ProbeState::Ok), "{}", swapped.to_fen());
+        }
+        assert!(values.contains(&WDL_WIN) && values.contains(&WDL_LOSS), "{values:?}");
+    }
+
+    //positions that are the same after mirroring, rotating or swapping colors have the same index
+    #[test]
+    fn symmetries(){
+        setup_sliding_magics();
+        let table = Table::new(TableKind::Wdl, kqvk_bit_table(), &Material::from_name("KQvK").unwrap()).unwrap();
+
+        //white king, white queen and black king as rank and file, with white to move
+        let positions = [
+            [(2, 2), (1, 5), (7, 0)],
+            [(5, 6), (1, 1), (7, 4)],
+            [(4, 3), (0, 0), (4, 7)],
+            [(0, 1), (6, 7), (7, 3)],
+            [(0, 0), (1, 1), (6, 7)],
+            [(2, 2), (4, 4), (5, 7)],
+        ];
+        type Transform = fn(usize, usize) -> (usize, usize);
+        let transforms:[Transform; 8] = [
+            |r, f| (r, f),
+            |r, f| (r, 7 - f),
+            |r, f| (7 - r, f),
+            |r, f| (7 - r, 7 - f),
+            |r, f| (f, r),
+            |r, f| (7 - f, r),
+            |r, f| (f, 7 - r),
+            |r, f| (7 - f, 7 - r),
+        ];
+
+        let mut values = Vec::new();
+        for squares in positions{
+            let original = BitBoardState::from_fen(&fen(&[('K', squares[0].0, squares[0].1), ('Q', squares[1].0, squares[1].1), ('k', squares[2].0, squares[2].1)], true)).unwrap();
+            let (value, result) = table.probe(&original, false, 0);
+            assert_eq!(result, ProbeState::Ok);
+            assert!(value == WDL_WIN || value == WDL_LOSS);
+            values.push(value);
+
+            for transform in transforms{
+                let [king, queen, other_king] = squares.map(|(rank, file)| transform(rank, file));
+                let state = BitBoardState::from_fen(&fen(&[('K', king.0, king.1), ('Q', queen.0, queen.1), ('k', other_king.0, other_king.1)], true)).unwrap();
+                assert_eq!(table.probe(&state, false, 0), (value, ProbeState::Ok), "{}", state.to_fen());
+
+                //black has the queen, the table is used with the colors swapped
+                let swapped = BitBoardState::from_fen(&fen(&[('k', king.0, king.1), ('q', queen.0, queen.1), ('K', other_king.0, other_king.1)], false)).unwrap();
+                assert_eq!(table.probe(&swapped, true, 0), (value, ProbeState::Ok), "{}", swapped.to_fen());
+            }
+        }
+        //the values come from different parts of the data
+        assert!(values.contains(&WDL_WIN) && values.contains(&WDL_LOSS), "{values:?}");
+
+        let black_to_move = BitBoardState::from_fen("k7/8/2K5/8/8/8/5Q2/8 b - - 0 1").unwrap();
+        assert_eq!(table.probe(&black_to_move, false, 0), (WDL_LOSS, ProbeState::Ok));
+    }
+}
@@ -0,0 +1,254 @@
+#[cfg(test)]
+mod tests {
+
+    use std::path::PathBuf;
+    use std::sync::Arc;
+
+    use crate::client::game::engine::board::ChessMove;
+    use crate::client::game::engine::eval::params::EvalParams;
+    use crate::client::game::engine::score::Score;
+    use crate::client::game::engine::state_bitboard::BitBoardState;
+    use crate::client::game::engine::tablebase::table::{WDL_CURSED_WIN, WDL_DRAW, WDL_LOSS, WDL_WIN};
+    use crate::client::game::engine::tablebase::{Tablebase, TablebaseError, TB_WIN};
+    use crate::client::game::engine::Engine;
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    //a directory of the temporary directory that is removed with everything in it when the test ends
+    struct TempDir(PathBuf);
+
+    impl TempDir{
+        fn new(name:&str) -> Self{
+            let directory = std::env::temp_dir().join(format!("rustchess2_tablebase_{name}_{}", std::process::id()));
+            std::fs::create_dir_all(&directory).unwrap();
+            TempDir(directory)
+        }
+    }
+
+    impl Drop for TempDir{
+        fn drop(&mut self){
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    //KQvK tables that only store a single value: a win for white to move and a loss for black to move, at a distance
+    //of 9 plies from zeroing the fifty move counter
+    fn kqvk_tables(test:&str) -> TempDir{
+        let mut wdl = vec![0x71, 0xE8, 0x23, 0x5D, 1, 0x00, 0x66, 0x55, 0xEE, 0];
+        wdl.extend([0x80, 4, 0x80, 0]);
+        let mut dtz = vec![0xD7, 0x66, 0x0C, 0xA5, 0, 0x00, 0x06, 0x05, 0x0E, 0];
+        dtz.extend([0x80, 4]);
+
+        let directory = TempDir::new(test);
+        std::fs::write(directory.0.join("KQvK.rtbw"), wdl).unwrap();
+        std::fs::write(directory.0.join("KQvK.rtbz"), dtz).unwrap();
+        std::fs::write(directory.0.join("notes.txt"), "not a table").unwrap();
+        directory
+    }
+
+    fn state(fen:&str) -> BitBoardState{
+        BitBoardState::from_fen(fen).unwrap()
+    }
+
+    #[test]
+    fn open(){
+        let directory = kqvk_tables("open");
+        let tablebase = Tablebase::open(std::slice::from_ref(&directory.0)).unwrap();
+        assert_eq!(tablebase.num_tables(), 1);
+        assert_eq!(tablebase.max_pieces(), 3);
+
+        let empty = TempDir::new("empty");
+        assert!(matches!(Tablebase::open(std::slice::from_ref(&empty.0)), Err(TablebaseError::NoTables(_))));
+        assert!(matches!(Tablebase::open(&[empty.0.join("missing")]), Err(TablebaseError::Io(_, _))));
+    }
+
+    #[test]
+    fn probe_wdl(){
+        setup_sliding_magics();
+        let directory = kqvk_tables("wdl");
+        let tablebase = Tablebase::open(std::slice::from_ref(&directory.0)).unwrap();
+
+        assert_eq!(tablebase.probe_wdl(&state("k7/8/2K5/8/8/8/5Q2/8 w - - 0 1")), Some(WDL_WIN));
+        assert_eq!(tablebase.probe_wdl(&state("k7/8/2K5/8/8/8/5Q2/8 b - - 0 1")), Some(WDL_LOSS));
+        //black has the queen
+        assert_eq!(tablebase.probe_wdl(&state("K7/8/2k5/8/8/8/5q2/8 b - - 0 1")), Some(WDL_WIN));
+        //the queen can be taken, the table does not know that
+        assert_eq!(tablebase.probe_wdl(&state("8/8/8/8/8/8/5Qk1/K7 b - - 0 1")), Some(WDL_DRAW));
+        assert_eq!(tablebase.probe_wdl(&state("8/8/8/8/8/8/6k1/K7 w - - 0 1")), Some(WDL_DRAW));
+
+        //too many pieces, or a table that is not there
+        assert_eq!(tablebase.probe_wdl(&state("k7/8/2K5/8/8/8/5Q2/7R w - - 0 1")), None);
+        assert_eq!(tablebase.probe_wdl(&state("k7/8/2K5/8/8/8/5R2/8 w - - 0 1")), None);
+    }
+
+    #[test]
+    fn probe_root(){
+        setup_sliding_magics();
+        let directory = kqvk_tables("root");
+        let tablebase = Tablebase::open(std::slice::from_ref(&directory.0)).unwrap();
+
+        //mate in one is the fastest win
+        let mate = state("k7/8/1K6/8/8/8/7Q/8 w - - 0 1");
+        let root_probe = tablebase.probe_root(&mate).unwrap();
+        assert_eq!(root_probe.chess_move, ChessMove::from_uci("h2h8", &mate).unwrap());
+        assert_eq!(root_probe.wdl, WDL_WIN);
+
+        //every move takes 11 plies to the next capture, too late for the fifty move rule with 95 plies gone
+        let position = state("4k3/8/8/8/8/8/8/K4Q2 w - - 0 1");
+        assert_eq!(tablebase.probe_root(&position).unwrap().wdl, WDL_WIN);
+        let late = state("4k3/8/8/8/8/8/8/K4Q2 w - - 95 100");
+        let root_probe = tablebase.probe_root(&late).unwrap();
+        assert_eq!(root_probe.wdl, WDL_CURSED_WIN);
+        //giving away the queen is still worse
+        assert_ne!(root_probe.chess_move, ChessMove::from_uci("f1f7", &late).unwrap());
+    }
+
+    #[test]
+    fn engine(){
+        setup_sliding_magics();
+        let directory = kqvk_tables("engine");
+        let tablebase = Arc::new(Tablebase::open(std::slice::from_ref(&directory.0)).unwrap());
+        let mut engine = Engine::new(3, 10, 1000, None, Arc::new(EvalParams::default()));
+        engine.set_tablebase(Some(tablebase));
+
+        //the root is in the tables
+        let mate = state("k7/8/1K6/8/8/8/7Q/8 w - - 0 1");
+        let result = engine.get_move_bb(mate, &[mate.hash()]);
+        assert_eq!(*result.chess_move(), ChessMove::from_uci("h2h8", &mate).unwrap());
+        assert_eq!(result.eval(), Score::Cp(TB_WIN));
+
+        //taking the rook goes into the tables
+        let capture = state("7k/8/8/8/8/8/6r1/K5Q1 w - - 0 1");
+        let result = engine.get_move_bb(capture, &[capture.hash()]);
+        assert_eq!(*result.chess_move(), ChessMove::from_uci("g1g2", &capture).unwrap());
+        assert!(matches!(result.eval(), Score::Cp(eval) if eval > TB_WIN - 10), "{}", result.eval());
+    }
+
+    //the real 3 piece tables of SYZYGY_TEST_PATH, the tests against them are ignored unless they are asked for with
+    //cargo test -- --ignored
+    fn real_tables() -> Tablebase{
+        let paths = std::env::var_os("SYZYGY_TEST_PATH")
+            .expect("SYZYGY_TEST_PATH has to point to the KQvK, KRvK and KPvK tables");
+        let tablebase = Tablebase::open(&std::env::split_paths(&paths).collect::<Vec<_>>()).unwrap();
+        for fen in ["4k3/8/8/8/8/8/8/4KQ2 w - - 0 1", "4k3/8/8/8/8/8/8/4KR2 w - - 0 1", "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1"]{
+            assert!(tablebase.probe_dtz(&state(fen)).is_some(), "the KQvK, KRvK and KPvK tables are needed, {fen}");
+        }
+        tablebase
+    }
+
+    //fen of pieces on squares, a1 is 0
+    fn placement_fen(pieces:&[(char, usize)], white_to_move:bool) -> String{
+        let mut board = [['1'; 8]; 8];
+        for &(piece, square) in pieces{
+            board[square / 8][square % 8] = piece;
+        }
+        let ranks:Vec<String> = board.iter().rev().map(|rank| rank.iter().collect()).collect();
+        format!("{} {} - - 0 1", ranks.join("/"), if white_to_move {"w"} else {"b"})
+    }
+
+    #[test]
+    #[ignore = "needs the 3 piece tables of SYZYGY_TEST_PATH"]
+    fn real_known_positions(){
+        setup_sliding_magics();
+        let tablebase = real_tables();
+        let wdl = |fen:&str| tablebase.probe_wdl(&state(fen));
+        let dtz = |fen:&str| tablebase.probe_dtz(&state(fen));
+
+        //KQvK
+        assert_eq!(wdl("4k3/8/8/8/8/8/8/4KQ2 w - - 0 1"), Some(WDL_WIN));
+        assert_eq!(wdl("4k3/8/8/8/8/8/8/4KQ2 b - - 0 1"), Some(WDL_LOSS));
+        assert_eq!(wdl("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), Some(WDL_DRAW)); //stalemate
+        assert_eq!(wdl("8/8/8/8/8/K7/8/5kQ1 b - - 0 1"), Some(WDL_DRAW)); //the queen is taken
+        assert_eq!(wdl("4K3/8/8/8/8/8/8/4kq2 b - - 0 1"), Some(WDL_WIN)); //black has the queen
+        assert_eq!(dtz("k7/8/1K6/8/8/8/8/2Q5 w - - 0 1"), Some(1));
+        let mate = state("k7/8/1K6/8/8/8/8/2Q5 w - - 0 1");
+        assert_eq!(tablebase.probe_root(&mate).unwrap().chess_move, ChessMove::from_uci("c1c8", &mate).unwrap());
+
+        //KRvK
+        assert_eq!(wdl("4k3/8/8/8/8/8/8/4KR2 w - - 0 1"), Some(WDL_WIN));
+        assert_eq!(dtz("k7/8/1K6/8/8/8/8/7R w - - 0 1"), Some(1));
+        assert_eq!(wdl("8/8/8/8/8/8/6k1/K6R b - - 0 1"), Some(WDL_DRAW));
+
+        //KPvK, pawn moves zero the fifty move counter
+        assert_eq!(wdl("8/8/8/8/8/k7/6P1/6K1 w - - 0 1"), Some(WDL_WIN));
+        assert_eq!(dtz("8/8/8/8/8/k7/6P1/6K1 w - - 0 1"), Some(1));
+        assert_eq!(wdl("6k1/6p1/K7/8/8/8/8/8 b - - 0 1"), Some(WDL_WIN));
+        assert_eq!(wdl("k7/8/8/8/8/8/P7/7K w - - 0 1"), Some(WDL_DRAW)); //the wrong rook pawn
+        assert_eq!(wdl("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"), Some(WDL_DRAW)); //stalemate
+        assert_eq!(dtz("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"), Some(0));
+    }
+
+    //KQvK and KRvK are won for white to move, with black to move they are lost unless black can take the piece or is
+    //stalemated. the distance to zeroing of a win is one more than the fastest loss it can move to
+    #[test]
+    #[ignore = "needs the 3 piece tables of SYZYGY_TEST_PATH"]
+    fn real_pieces(){
+        setup_sliding_magics();
+        let tablebase = real_tables();
+
+        for (piece, max_dtz) in [('Q', 20), ('R', 32)]{
+            for (king, other, other_king) in (0..64).step_by(5).flat_map(|k| (0..64).step_by(3).flat_map(move |o| (0..64).step_by(7).map(move |ok| (k, o, ok)))){
+                if king == other || king == other_king || other == other_king {
+                    continue;
+                }
+                let pieces = [('K', king), (piece, other), ('k', other_king)];
+
+                if let Ok(mut black) = BitBoardState::from_fen(&placement_fen(&pieces, false)) {
+                    let moves = black.gen_moves_legal().moves_vec();
+                    let expected = if moves.is_empty() {
+                        if black.in_check() {WDL_LOSS} else {WDL_DRAW}
+                    }else if moves.iter().any(|&m| black.perform_move(m).piece_mask().count_ones() == 2) {
+                        WDL_DRAW
+                    }else{
+                        WDL_LOSS
+                    };
+                    assert_eq!(tablebase.probe_wdl(&black), Some(expected), "{}", black.to_fen());
+                    let dtz = tablebase.probe_dtz(&black).unwrap();
+                    assert!(if expected == WDL_LOSS {(-max_dtz..0).contains(&dtz)} else {dtz == 0}, "{} {dtz}", black.to_fen());
+                }
+
+                let Ok(mut white) = BitBoardState::from_fen(&placement_fen(&pieces, true)) else { continue };
+                assert_eq!(tablebase.probe_wdl(&white), Some(WDL_WIN), "{}", white.to_fen());
+                let dtz = tablebase.probe_dtz(&white).unwrap();
+                let mut fastest = i32::MAX;
+                for chess_move in white.gen_moves_legal().moves_vec(){
+                    let mut next = white.perform_move(chess_move);
+                    let next_dtz = tablebase.probe_dtz(&next).unwrap();
+                    if next.in_check() && next.gen_moves_legal().size() == 0 {
+                        fastest = 1;
+                    }else if next_dtz < 0 {
+                        fastest = fastest.min(1 - next_dtz);
+                    }
+                }
+                //tables that store moves instead of plies can be one ply further
+                assert!(dtz == fastest || dtz == fastest + 1, "{} {dtz} {fastest}", white.to_fen());
+                assert!(dtz <= max_dtz, "{} {dtz}", white.to_fen());
+            }
+        }
+    }
+
+    //a pawn position and the same position with the colors swapped have the same result
+    #[test]
+    #[ignore = "needs the 3 piece tables of SYZYGY_TEST_PATH"]
+    fn real_pawns(){
+        setup_sliding_magics();
+        let tablebase = real_tables();
+
+        let mut results = Vec::new();
+        for (pawn, king, other_king) in (8..56).step_by(3).flat_map(|p| (0..64).step_by(5).flat_map(move |k| (0..64).step_by(11).map(move |ok| (p, k, ok)))){
+            if pawn == king || pawn == other_king || king == other_king {
+                continue;
+            }
+            for white_to_move in [true, false]{
+                let Ok(position) = BitBoardState::from_fen(&placement_fen(&[('P', pawn), ('K', king), ('k', other_king)], white_to_move)) else { continue };
+                let Ok(swapped) = BitBoardState::from_fen(&placement_fen(&[('p', pawn ^ 56), ('k', king ^ 56), ('K', other_king ^ 56)], !white_to_move)) else { continue };
+                let wdl = tablebase.probe_wdl(&position).unwrap();
+                assert_eq!(tablebase.probe_wdl(&swapped), Some(wdl), "{}", position.to_fen());
+                let dtz = tablebase.probe_dtz(&position).unwrap();
+                assert_eq!(dtz.signum(), wdl.signum(), "{} {dtz}", position.to_fen());
+                assert_eq!(tablebase.probe_dtz(&swapped), Some(dtz), "{}", position.to_fen());
+                results.push(wdl);
+            }
+        }
+        assert!(results.contains(&WDL_WIN) && results.contains(&WDL_DRAW) && results.contains(&WDL_LOSS));
+    }
+}
@@ -0,0 +1,22 @@
+use super::state_bitboard::bit_boards;
+
+/*
+shared by the tests: the setup the move generation needs, and the positions of the perft tests that several tests
+walk through
+*/
+
+//the moves of the sliding pieces are looked up in tables that are filled once
+pub fn setup_sliding_magics(){
+    bit_boards::populate_rook_moves();
+    bit_boards::populate_bishop_moves();
+}
+
+//the positions of the perft tests, between them they have castles, en passant and promotions for both sides
+pub const PERFT_FENS:[&str; 6] = [
+    "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1",
+    "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+    "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+    "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+    "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+    "r4rk1/1pp1qppp/p1np1n2/2b1p1B1/2B1P1b1/P1NP1N2/1PP1QPPP/R4RK1 w - - 0 10",
+];
@@ -0,0 +1,137 @@
+use super::engine::board::ChessMove;
+use super::engine::move_string::lan_move;
+use super::engine::san::san_move;
+use super::engine::state_bitboard::BitBoardState;
+use super::engine::GetMoveResult;
+use super::STARTING_POS;
+
+pub mod reader;
+#[cfg(test)]
+mod reader_tests;
+
+/*
+record of a game in PGN form, built up move by move while the game is played
+*/
+
+const LINE_LENGTH:usize = 80;
+
+pub struct PgnGame{
+    tags: Vec<(String, String)>, //in the order they are written, starting with the seven tag roster
+    start: BitBoardState,
+    position: BitBoardState,
+    moves: Vec<(String, Option<String>)>, //san of every move, with the comment written after it
+    pending_comment: Option<(ChessMove, String)>,
+}
+
+impl PgnGame{
+    pub fn new(start:BitBoardState) -> Self{
+        let mut tags:Vec<(String, String)> = ["Event", "Site", "Date", "Round", "White", "Black", "Result"].iter()
+            .map(|name| (name.to_string(), "?".to_string()))
+            .collect();
+        tags[6].1 = "*".to_string();
+
+        let start_fen = start.to_fen();
+        if start_fen != STARTING_POS {
+            tags.push(("SetUp".to_string(), "1".to_string()));
+            tags.push(("FEN".to_string(), start_fen));
+        }
+
+        Self {
+            tags,
+            start,
+            position: start,
+            moves: Vec::new(),
+            pending_comment: None,
+        }
+    }
+
+    //sets the value of a tag, tags not set before are added after the existing ones
+    pub fn set_tag(&mut self, name:&str, value:&str){
+        let value = value.replace('\\', "\\\\").replace('"', "\\\"");
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some(tag) => tag.1 = value,
+            None => self.tags.push((name.to_string(), value)),
+        }
+    }
+
+    //the comment is written after chess_move, if it is the next move pushed
+    pub fn annotate_next(&mut self, chess_move:ChessMove, comment:String){
+        self.pending_comment = Some((chess_move, comment));
+    }
+
+    //chess_move has to be legal in the current position of the game, anything else is written in uci notation
+    pub fn push_move(&mut self, chess_move:ChessMove){
+        let comment = match self.pending_comment.take() {
+            Some((annotated_move, comment)) if annotated_move == chess_move => Some(comment),
+            _ => None,
+        };
+        let san = san_move(&self.position, chess_move).unwrap_or_else(|| lan_move(chess_move));
+        self.moves.push((san, comment));
+        self.position = self.position.perform_move(chess_move);
+    }
+
+    pub fn to_pgn(&self) -> String{
+        let mut pgn = String::new();
+        for (name, value) in &self.tags{
+            pgn.push_str(&format!("[{name} \"{value}\"]\n"));
+        }
+        pgn.push('\n');
+
+        //movetext tokens, wrapped into lines of at most LINE_LENGTH characters where possible
+        let mut tokens = Vec::<String>::new();
+        let mut white_to_move = self.start.white_to_move();
+        let mut move_number = self.start.full_move_number();
+        for (i, (san, comment)) in self.moves.iter().enumerate(){
+            if white_to_move {
+                tokens.push(format!("{move_number}."));
+            }else if i == 0 || self.moves[i - 1].1.is_some() {
+                tokens.push(format!("{move_number}..."));
+            }
+            tokens.push(san.clone());
+            if let Some(comment) = comment {
+                tokens.push(format!("{{{}}}", comment.replace('}', ")")));
+            }
+            if !white_to_move {
+                move_number += 1;
+            }
+            white_to_move = !white_to_move;
+        }
+        tokens.push(self.result().to_string());
+
+        let mut line_length = 0;
+        for token in tokens{
+            if line_length > 0 && line_length + 1 + token.len() > LINE_LENGTH {
+                pgn.push('\n');
+                line_length = 0;
+            }else if line_length > 0 {
+                pgn.push(' ');
+                line_length += 1;
+            }
+            line_length += token.len();
+            pgn.push_str(&token);
+        }
+        pgn.push_str("\n\n");
+        pgn
+    }
+
+    fn result(&self) -> &str{
+        self.tags.iter().find(|(name, _)| name == "Result").map(|(_, value)| value.as_str()).unwrap_or("*")
+    }
+}
+
+//the comment added to moves played by the bot, the eval is given in pawns from whites perspective, or as #n for mates
+pub fn engine_comment(move_result:&GetMoveResult) -> String{
+    format!("[%eval {}] [%emt {}] depth {}/{}, {} nodes",
+        move_result.eval(),
+        elapsed_move_time(move_result.time()),
+        move_result.depth_reached(),
+        move_result.max_depth_reached(),
+        move_result.num_pos(),
+    )
+}
+
+//time in the H:MM:SS.s format used by the %emt command
+fn elapsed_move_time(millis:u128) -> String{
+    let tenths = millis / 100;
+    format!("{}:{:02}:{:02}.{}", tenths / 36000, tenths / 600 % 60, tenths / 10 % 60, tenths % 10)
+}
@@ -0,0 +1,448 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::client::game::engine::board::ChessMove;
+use crate::client::game::engine::fen::FenError;
+use crate::client::game::engine::san::{parse_san, SanError};
+use crate::client::game::engine::state_bitboard::BitBoardState;
+use crate::client::game::STARTING_POS;
+
+/*
+PGN reader, reads every game of a file into the moves played and the positions they lead to.
+move text is checked against the legal moves, so every move read can be played on a BitBoardState.
+the reader is tolerant of the export format used by lichess: comments with clock and eval commands,
+move numbers repeated after comments and the Variant "From Position" tag.
+*/
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PgnErrorKind{
+    InvalidTag(String),
+    UnterminatedComment,
+    UnterminatedVariation,
+    UnmatchedVariationEnd,
+    VariationBeforeFirstMove,
+    InvalidFen(FenError),
+    InvalidMove{san: String, error: SanError},
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PgnError{
+    pub line: usize, //line the error was found on, starting at 1
+    pub kind: PgnErrorKind,
+}
+
+impl fmt::Display for PgnError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            PgnErrorKind::InvalidTag(tag) => write!(f, "invalid tag pair '{tag}'"),
+            PgnErrorKind::UnterminatedComment => write!(f, "comment is never closed"),
+            PgnErrorKind::UnterminatedVariation => write!(f, "variation is never closed"),
+            PgnErrorKind::UnmatchedVariationEnd => write!(f, "')' without a variation to close"),
+            PgnErrorKind::VariationBeforeFirstMove => write!(f, "variation before the first move"),
+            PgnErrorKind::InvalidFen(e) => write!(f, "invalid FEN tag, {e}"),
+            PgnErrorKind::InvalidMove{san, error} => write!(f, "can not play {san}, {error}"),
+        }
+    }
+}
+
+impl std::error::Error for PgnError{}
+
+pub struct ParsedMove{
+    pub chess_move: ChessMove,
+    pub san: String, //the move as it was written, without annotation glyphs
+    pub comment: Option<String>,
+    pub nags: Vec<u8>, //numeric annotation glyphs, !, ?, !!, ??, !? and ?! are read as 1 to 6
+    pub variations: Vec<Vec<ParsedMove>>, //alternatives to this move, only filled in if variations are kept
+}
+
+pub struct ParsedGame{
+    pub tags: Vec<(String, String)>,
+    pub start: BitBoardState,
+    pub moves: Vec<ParsedMove>, //the main line
+    pub result: String,
+}
+
+impl ParsedGame{
+    pub fn tag(&self, name:&str) -> Option<&str>{
+        self.tags.iter().find(|(tag, _)| tag == name).map(|(_, value)| value.as_str())
+    }
+
+    //every position of the main line, starting with the initial position
+    pub fn positions(&self) -> Vec<BitBoardState>{
+        let mut positions = Vec::with_capacity(self.moves.len() + 1);
+        positions.push(self.start);
+        for parsed_move in &self.moves{
+            positions.push(positions.last().unwrap().perform_move(parsed_move.chess_move));
+        }
+        positions
+    }
+}
+
+enum Token{
+    Tag(String, String),
+    Comment(String),
+    Nag(u8),
+    Move(String),
+    VariationStart,
+    VariationEnd,
+    Result(String),
+    Error(PgnErrorKind), //text that could not be read, the game it is in is not read
+}
+
+//reads every game in text, a game that can not be read does not stop the games after it from being read.
+//variations are either checked and kept, or skipped without looking at the moves in them
+pub fn read_pgn(text:&str, keep_variations:bool) -> Vec<Result<ParsedGame, PgnError>>{
+    let tokens = tokenize(text);
+
+    //split the tokens into games, a game ends with its result or when the tags of the next game start.
+    //tags after an error in the tags of a game are still part of that game
+    let mut games = Vec::<&[(Token, usize)]>::new();
+    let mut game_start = 0;
+    for (i, (token, _)) in tokens.iter().enumerate(){
+        let before = tokens[game_start..i].iter().rev().find(|(token, _)| !matches!(token, Token::Error(_)));
+        let starts_new_game = matches!(token, Token::Tag(..)) && before.is_some_and(|(token, _)| !matches!(token, Token::Tag(..)));
+        if starts_new_game {
+            games.push(&tokens[game_start..i]);
+            game_start = i;
+        }
+        if matches!(token, Token::Result(_)) {
+            games.push(&tokens[game_start..=i]);
+            game_start = i + 1;
+        }
+    }
+    if game_start < tokens.len() {
+        games.push(&tokens[game_start..]);
+    }
+
+    games.into_iter()
+        .map(|game| parse_game(game, keep_variations))
+        .collect()
+}
+
+fn parse_game(tokens:&[(Token, usize)], keep_variations:bool) -> Result<ParsedGame, PgnError>{
+    let mut tags = Vec::<(String, String)>::new();
+    let mut start = None;
+    let mut i = 0;
+    while let Some((Token::Tag(name, value), line)) = tokens.get(i) {
+        if name == "FEN" {
+            let state = BitBoardState::from_fen(value).map_err(|e| PgnError{line: *line, kind: PgnErrorKind::InvalidFen(e)})?;
+            start = Some(state);
+        }
+        tags.push((name.clone(), value.clone()));
+        i += 1;
+    }
+    if let Some((Token::Error(kind), line)) = tokens.get(i) {
+        return Err(PgnError{line: *line, kind: kind.clone()});
+    }
+    let start = start.unwrap_or_else(|| BitBoardState::from_fen(STARTING_POS).unwrap());
+
+    let moves = parse_line(tokens, &mut i, start, keep_variations, false)?;
+
+    let result = match tokens.get(i) {
+        Some((Token::Result(result), _)) => result.clone(),
+        _ => tags.iter().find(|(name, _)| name == "Result").map_or("*".to_string(), |(_, value)| value.clone()),
+    };
+
+    Ok(ParsedGame {
+        tags,
+        start,
+        moves,
+        result,
+    })
+}
+
+//parses moves from start until the end of the variation, or the result if this is the main line
+//comments are kept with the move before them, a comment before the first move is about the whole line and not kept
+fn parse_line(tokens:&[(Token, usize)], i:&mut usize, start:BitBoardState, keep_variations:bool, in_variation:bool) -> Result<Vec<ParsedMove>, PgnError>{
+    let mut moves = Vec::<ParsedMove>::new();
+    let mut state = start;
+    let mut before_last_move = start;
+    let mut last_line = tokens.get(*i).map_or(0, |(_, line)| *line);
+
+    while let Some((token, line)) = tokens.get(*i) {
+        last_line = *line;
+        *i += 1;
+        match token {
+            Token::Move(san) => {
+                let chess_move = parse_san(san, &state)
+                    .map_err(|error| PgnError{line: *line, kind: PgnErrorKind::InvalidMove{san: san.clone(), error}})?;
+                moves.push(ParsedMove {
+                    chess_move,
+                    san: san.clone(),
+                    comment: None,
+                    nags: Vec::new(),
+                    variations: Vec::new(),
+                });
+                before_last_move = state;
+                state = state.perform_move(chess_move);
+            }
+            Token::Comment(text) => {
+                if let Some(last_move) = moves.last_mut() {
+                    match &mut last_move.comment {
+                        Some(comment) => {
+                            comment.push(' ');
+                            comment.push_str(text);
+                        }
+                        None => last_move.comment = Some(text.clone()),
+                    }
+                }
+            }
+            Token::Nag(nag) => {
+                if let Some(last_move) = moves.last_mut() {
+                    last_move.nags.push(*nag);
+                }
+            }
+            Token::VariationStart => {
+                let Some(last_move) = moves.last_mut() else {
+                    return Err(PgnError{line: *line, kind: PgnErrorKind::VariationBeforeFirstMove});
+                };
+                if keep_variations {
+                    let variation = parse_line(tokens, i, before_last_move, keep_variations, true)?;
+                    last_move.variations.push(variation);
+                }else{
+                    skip_variation(tokens, i, *line)?;
+                }
+            }
+            Token::VariationEnd => {
+                if in_variation {
+                    return Ok(moves);
+                }
+                return Err(PgnError{line: *line, kind: PgnErrorKind::UnmatchedVariationEnd});
+            }
+            Token::Result(_) | Token::Tag(..) => {
+                //tags can not show up here, as games are split on them
+                *i -= 1;
+                break;
+            }
+            Token::Error(kind) => return Err(PgnError{line: *line, kind: kind.clone()}),
+        }
+    }
+
+    if in_variation {
+        return Err(PgnError{line: last_line, kind: PgnErrorKind::UnterminatedVariation});
+    }
+    Ok(moves)
+}
+
+//moves past the end of the variation that was just opened
+fn skip_variation(tokens:&[(Token, usize)], i:&mut usize, start_line:usize) -> Result<(), PgnError>{
+    let mut depth = 1;
+    while let Some((token, line)) = tokens.get(*i) {
+        match token {
+            Token::VariationStart => depth += 1,
+            Token::VariationEnd => depth -= 1,
+            Token::Result(_) => break,
+            Token::Error(kind) => return Err(PgnError{line: *line, kind: kind.clone()}),
+            _ => {}
+        }
+        *i += 1;
+        if depth == 0 {
+            return Ok(());
+        }
+    }
+    Err(PgnError{line: start_line, kind: PgnErrorKind::UnterminatedVariation})
+}
+
+fn glyph_nag(glyph:&str) -> Option<u8>{
+    match glyph {
+        "!" => Some(1),
+        "?" => Some(2),
+        "!!" => Some(3),
+        "??" => Some(4),
+        "!?" => Some(5),
+        "?!" => Some(6),
+        _ => None,
+    }
+}
+
+//splits the text into tokens with the line they start on
+//after an error the text is skipped up to the next line starting with a tag or the next result, the next game
+fn tokenize(text:&str) -> Vec<(Token, usize)>{
+    let mut tokens = Vec::<(Token, usize)>::new();
+    let mut line = 1;
+    let mut chars = text.chars().peekable();
+    let mut at_line_start = true;
+
+    loop {
+        let token_start = chars.clone();
+        let Some(c) = chars.next() else { break };
+        let token_line = line;
+        if c == '\n' {
+            line += 1;
+            at_line_start = true;
+            continue;
+        }
+        //lines starting with % are escaped and ignored
+        if c == '%' && at_line_start {
+            while chars.next_if(|c| *c != '\n').is_some() {}
+            continue;
+        }
+        at_line_start = false;
+
+        let mut error = None;
+        match c {
+            c if c.is_whitespace() => {}
+            '[' => {
+                let mut tag = String::new();
+                let mut in_string = false;
+                let mut escaped = false;
+                loop {
+                    match chars.next() {
+                        None | Some('\n') => {
+                            error = Some(PgnErrorKind::InvalidTag(format!("[{tag}")));
+                            break;
+                        }
+                        Some(']') if !in_string => {
+                            match parse_tag(&tag) {
+                                Some((name, value)) => tokens.push((Token::Tag(name, value), token_line)),
+                                None => error = Some(PgnErrorKind::InvalidTag(format!("[{tag}]"))),
+                            }
+                            break;
+                        }
+                        Some(c) => {
+                            if in_string && !escaped && c == '"' {
+                                in_string = false;
+                            }else if !in_string && c == '"' {
+                                in_string = true;
+                            }
+                            escaped = in_string && !escaped && c == '\\';
+                            tag.push(c);
+                        }
+                    }
+                }
+            }
+            '{' => {
+                let mut comment = String::new();
+                loop {
+                    match chars.next() {
+                        None => {
+                            error = Some(PgnErrorKind::UnterminatedComment);
+                            break;
+                        }
+                        Some('}') => {
+                            tokens.push((Token::Comment(comment.split_whitespace().collect::<Vec<&str>>().join(" ")), token_line));
+                            break;
+                        }
+                        Some(c) => {
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            comment.push(c);
+                        }
+                    }
+                }
+            }
+            ';' => {
+                let mut comment = String::new();
+                while let Some(c) = chars.next_if(|c| *c != '\n') {
+                    comment.push(c);
+                }
+                tokens.push((Token::Comment(comment.trim().to_string()), token_line));
+            }
+            '(' => tokens.push((Token::VariationStart, token_line)),
+            ')' => tokens.push((Token::VariationEnd, token_line)),
+            _ => {
+                let mut symbol = String::from(c);
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"{}()[];".contains(*c)) {
+                    symbol.push(c);
+                }
+                push_symbol(&mut tokens, &symbol, token_line);
+            }
+        }
+
+        //the broken tag or comment may have run into the next games, they are read again from right after its start
+        if let Some(kind) = error {
+            tokens.push((Token::Error(kind), token_line));
+            chars = token_start;
+            chars.next();
+            line = token_line;
+            at_line_start = skip_to_next_game(&mut chars, &mut line, &mut tokens);
+        }
+    }
+    tokens
+}
+
+//skips to the next line starting with a tag, or past the next result which is added to the tokens.
+//returns whether the text left starts at the start of a line
+fn skip_to_next_game(chars:&mut Peekable<Chars>, line:&mut usize, tokens:&mut Vec<(Token, usize)>) -> bool{
+    let mut at_line_start = false;
+    while let Some(&c) = chars.peek() {
+        if c == '[' && at_line_start {
+            return true;
+        }
+        at_line_start = c == '\n';
+        if c == '\n' {
+            *line += 1;
+        }
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        let mut word = String::new();
+        while let Some(c) = chars.next_if(|c| !c.is_whitespace()) {
+            word.push(c);
+        }
+        if matches!(word.as_str(), "1-0" | "0-1" | "1/2-1/2" | "*") {
+            tokens.push((Token::Result(word), *line));
+            return false;
+        }
+    }
+    false
+}
+
+//a symbol is a result, a numeric annotation glyph, or a move possibly preceded by its number and followed by glyphs
+fn push_symbol(tokens:&mut Vec<(Token, usize)>, symbol:&str, line:usize){
+    if matches!(symbol, "1-0" | "0-1" | "1/2-1/2" | "*") {
+        tokens.push((Token::Result(symbol.to_string()), line));
+        return;
+    }
+    if let Some(nag) = symbol.strip_prefix('$') && let Ok(nag) = nag.parse::<u8>() {
+        tokens.push((Token::Nag(nag), line));
+        return;
+    }
+    if let Some(nag) = glyph_nag(symbol) {
+        tokens.push((Token::Nag(nag), line));
+        return;
+    }
+
+    //move numbers like 12. or 12... are dropped, castles written with zeros are not move numbers
+    let mut san = symbol;
+    let digits = san.len() - san.trim_start_matches(|c:char| c.is_ascii_digit()).len();
+    if digits > 0 && san[digits..].starts_with('.') {
+        san = san[digits..].trim_start_matches('.');
+    }
+    if san.is_empty() {
+        return;
+    }
+
+    let glyph_start = san.trim_end_matches(['!', '?']).len();
+    let (san, glyph) = san.split_at(glyph_start);
+    tokens.push((Token::Move(san.to_string()), line));
+    if let Some(nag) = glyph_nag(glyph) {
+        tokens.push((Token::Nag(nag), line));
+    }
+}
+
+//parses the inside of a tag pair, like Event "Rated blitz game"
+fn parse_tag(tag:&str) -> Option<(String, String)>{
+    let tag = tag.trim();
+    let name_end = tag.find(|c:char| !(c.is_ascii_alphanumeric() || c == '_'))?;
+    let (name, rest) = tag.split_at(name_end);
+    let quoted = rest.trim();
+    if name.is_empty() || quoted.len() < 2 || !quoted.starts_with('"') || !quoted.ends_with('"') {
+        return None;
+    }
+    let mut value = String::new();
+    let mut escaped = false;
+    for c in quoted[1..quoted.len() - 1].chars(){
+        if c == '\\' && !escaped {
+            escaped = true;
+            continue;
+        }
+        escaped = false;
+        value.push(c);
+    }
+    Some((name.to_string(), value))
+}
@@ -0,0 +1,196 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::{board::ChessMove, san::SanError, state_bitboard::BitBoardState};
+    use crate::client::game::pgn::PgnGame;
+    use crate::client::game::pgn::reader::{read_pgn, ParsedGame, PgnError, PgnErrorKind};
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    fn read_one(text:&str, keep_variations:bool) -> ParsedGame{
+        let mut games = read_pgn(text, keep_variations);
+        assert_eq!(games.len(), 1);
+        games.pop().unwrap().unwrap()
+    }
+
+    fn sans(game:&ParsedGame) -> Vec<&str>{
+        game.moves.iter().map(|parsed_move| parsed_move.san.as_str()).collect()
+    }
+
+    #[test]
+    fn lichess_export(){
+        setup_sliding_magics();
+        let text = concat!(
+            "[Event \"Rated blitz game\"]\n",
+            "[Site \"https://lichess.org/abcdefgh\"]\n",
+            "[White \"sonkolbot\"]\n",
+            "[Black \"Some \\\"quoted\\\" name\"]\n",
+            "[Result \"0-1\"]\n",
+            "[WhiteElo \"1500\"]\n",
+            "\n",
+            "1. e4 { [%eval 0.36] [%clk 0:03:00] } 1... e5 { [%eval 0.25] [%clk 0:03:00] } 2. Qh5?! { [%eval -0.2] }\n",
+            "2... Nc6 3. Bc4 g6?! 4. Qf3 Nf6 5. Qb3 $2 d5 6. Bxd5 Nxd5 7. exd5 Nd4 8. Qa4+?? Bd7 0-1\n",
+            "\n",
+        );
+        let game = read_one(text, false);
+        assert_eq!(game.tag("Black"), Some("Some \"quoted\" name"));
+        assert_eq!(game.tag("WhiteElo"), Some("1500"));
+        assert_eq!(game.tag("Opening"), None);
+        assert_eq!(game.result, "0-1");
+        assert_eq!(game.moves.len(), 16);
+        assert_eq!(sans(&game)[..4], ["e4", "e5", "Qh5", "Nc6"]);
+        assert_eq!(game.moves[0].comment.as_deref(), Some("[%eval 0.36] [%clk 0:03:00]"));
+        assert_eq!(game.moves[2].nags, vec![6]);
+        assert_eq!(game.moves[5].nags, vec![6]);
+        assert_eq!(game.moves[8].nags, vec![2]);
+        assert_eq!(game.moves[14].nags, vec![4]);
+        assert_eq!(game.moves[3].comment, None);
+
+        let positions = game.positions();
+        assert_eq!(positions.len(), 17);
+        assert_eq!(positions[0].to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+        assert_eq!(positions[16].to_fen(), "r2qkb1r/pppb1p1p/6p1/3Pp3/Q2n4/8/PPPP1PPP/RNB1K1NR w KQkq - 3 9");
+    }
+
+    #[test]
+    fn starting_from_fen(){
+        setup_sliding_magics();
+        let text = concat!(
+            "[Variant \"From Position\"]\n",
+            "[SetUp \"1\"]\n",
+            "[FEN \"4k3/8/8/8/8/8/4P3/4K2R b K - 3 20\"]\n",
+            "\n",
+            "20... Kd7 21. O-O Kc6 22. e4 *\n",
+        );
+        let game = read_one(text, false);
+        assert_eq!(sans(&game), ["Kd7", "O-O", "Kc6", "e4"]);
+        assert_eq!(game.result, "*");
+        assert_eq!(game.positions()[4].to_fen(), "8/8/2k5/8/4P3/8/8/5RK1 b - e3 0 22");
+    }
+
+    #[test]
+    fn variations_kept_or_skipped(){
+        setup_sliding_magics();
+        let text = "1. e4 e5 (1... c5 2. Nf3 (2. Nc3 Nc6) d6) (1... e6) 2. Nf3 {main line} Nc6 1-0";
+
+        let skipped = read_one(text, false);
+        assert_eq!(sans(&skipped), ["e4", "e5", "Nf3", "Nc6"]);
+        assert!(skipped.moves.iter().all(|parsed_move| parsed_move.variations.is_empty()));
+        assert_eq!(skipped.moves[2].comment.as_deref(), Some("main line"));
+
+        let kept = read_one(text, true);
+        assert_eq!(sans(&kept), ["e4", "e5", "Nf3", "Nc6"]);
+        let variations = &kept.moves[1].variations;
+        assert_eq!(variations.len(), 2);
+        let sicilian:Vec<&str> = variations[0].iter().map(|parsed_move| parsed_move.san.as_str()).collect();
+        assert_eq!(sicilian, ["c5", "Nf3", "d6"]);
+        assert_eq!(variations[0][1].variations[0][0].san, "Nc3");
+        assert_eq!(variations[1][0].san, "e6");
+        assert_eq!(kept.result, "1-0");
+    }
+
+    #[test]
+    fn multiple_games_with_errors(){
+        setup_sliding_magics();
+        let text = concat!(
+            "[Event \"first\"]\n",
+            "\n",
+            "1. d4 d5 2. c4 1/2-1/2\n",
+            "\n",
+            "[Event \"second\"]\n",
+            "\n",
+            "1. e4 e5\n",
+            "2. Ke3 Nc6 1-0\n",
+            "\n",
+            "[Event \"third\"]\n",
+            "\n",
+            "1. Nf3 (1. e4 e5 (1... c5 ) 0-1\n",
+            "\n",
+            "[Event \"fourth\"]\n",
+            "1. c4 ) c5 *\n",
+            "[Event \"fifth\"]\n",
+            "1. g3 g6\n",
+        );
+        let games = read_pgn(text, true);
+        assert_eq!(games.len(), 5);
+        let first = games[0].as_ref().unwrap();
+        assert_eq!(first.tag("Event"), Some("first"));
+        assert_eq!(first.result, "1/2-1/2");
+        assert_eq!(games[1].as_ref().err(), Some(&PgnError{
+            line: 8,
+            kind: PgnErrorKind::InvalidMove{san: "Ke3".to_string(), error: SanError::IllegalMove("Ke3".to_string())},
+        }));
+        assert_eq!(games[2].as_ref().err().map(|e| &e.kind), Some(&PgnErrorKind::UnterminatedVariation));
+        assert_eq!(games[3].as_ref().err(), Some(&PgnError{line: 15, kind: PgnErrorKind::UnmatchedVariationEnd}));
+        //a game without a result token is still read, up to the end of the file
+        let fifth = games[4].as_ref().unwrap();
+        assert_eq!(fifth.tag("Event"), Some("fifth"));
+        assert_eq!(sans(fifth), ["g3", "g6"]);
+        assert_eq!(fifth.result, "*");
+    }
+
+    //a broken game is reported in its place, the games after it are still read
+    #[test]
+    fn broken_game_between_good_ones(){
+        setup_sliding_magics();
+        let good = |event:&str| format!("[Event \"{event}\"]\n\n1. e4 e5 2. Nf3 1-0\n\n");
+        let broken_games = [
+            ("[Event \"broken\"]\n[Site x]\n\n1. d4 d5 0-1\n\n", 2, PgnErrorKind::InvalidTag("[Site x]".to_string())),
+            ("[Event \"broken\"]\n[Site \"x\n\n1. d4 d5 0-1\n\n", 2, PgnErrorKind::InvalidTag("[Site \"x".to_string())),
+            ("[Event \"broken\"]\n\n1. d4 {never closed 1. c4 0-1\n\n", 3, PgnErrorKind::UnterminatedComment),
+            ("[Event \"broken\"]\n\n1. d4 {never\nclosed\n", 3, PgnErrorKind::UnterminatedComment),
+        ];
+        for (broken, line, kind) in broken_games{
+            let text = format!("{}{}{}", good("first"), broken, good("last"));
+            let games = read_pgn(&text, false);
+            assert_eq!(games.len(), 3, "{text}");
+            assert_eq!(games[0].as_ref().unwrap().tag("Event"), Some("first"));
+            assert_eq!(games[1].as_ref().err(), Some(&PgnError{line: 4 + line, kind}));
+            let last = games[2].as_ref().unwrap();
+            assert_eq!(last.tag("Event"), Some("last"));
+            assert_eq!(sans(last), ["e4", "e5", "Nf3"]);
+        }
+    }
+
+    #[test]
+    fn syntax_errors(){
+        setup_sliding_magics();
+        let unterminated = read_pgn("[Event \"x\"]\n\n1. e4 e5\n2. Nf3 {never\nclosed\n", false);
+        assert_eq!(unterminated.len(), 1);
+        assert_eq!(unterminated[0].as_ref().err(), Some(&PgnError{line: 4, kind: PgnErrorKind::UnterminatedComment}));
+
+        let bad_tag = read_pgn("\n[Event x]\n1. e4 *", false);
+        assert_eq!(bad_tag[0].as_ref().err(), Some(&PgnError{line: 2, kind: PgnErrorKind::InvalidTag("[Event x]".to_string())}));
+
+        let bad_fen = read_pgn("[FEN \"8/8/8/8 w - - 0 1\"]\n1. e4 *", false);
+        assert!(matches!(bad_fen[0], Err(PgnError{line: 1, kind: PgnErrorKind::InvalidFen(_)})));
+
+        let variation_first = read_pgn("(1. d4) 1. e4 *", false);
+        assert_eq!(variation_first[0].as_ref().err(), Some(&PgnError{line: 1, kind: PgnErrorKind::VariationBeforeFirstMove}));
+    }
+
+    #[test]
+    fn reads_written_games(){
+        setup_sliding_magics();
+        let mut state = BitBoardState::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        let start = state;
+        let mut pgn = PgnGame::new(state);
+        let mut played = Vec::<ChessMove>::new();
+        for uci_move in ["e1g1", "e8c8", "d5e6", "h3g2", "e6f7", "g2f1q", "g1f1", "d7d5", "e4d5"]{
+            let chess_move = ChessMove::from_uci(uci_move, &state).unwrap();
+            if uci_move == "e6f7" {
+                pgn.annotate_next(chess_move, "a {comment}".to_string());
+            }
+            pgn.push_move(chess_move);
+            played.push(chess_move);
+            state = state.perform_move(chess_move);
+        }
+        pgn.set_tag("Result", "1/2-1/2");
+
+        let game = read_one(&pgn.to_pgn(), false);
+        assert_eq!(game.tag("FEN"), Some(start.to_fen().as_str()));
+        assert_eq!(game.moves.iter().map(|parsed_move| parsed_move.chess_move).collect::<Vec<ChessMove>>(), played);
+        assert_eq!(game.moves[4].comment.as_deref(), Some("a {comment)"));
+        assert_eq!(game.positions().last().unwrap().to_fen(), state.to_fen());
+        assert_eq!(game.result, "1/2-1/2");
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::{board::ChessMove, score::mate_in, state_bitboard::BitBoardState, GetMoveResult};
+    use crate::client::game::pgn::{engine_comment, PgnGame};
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    fn play(pgn:&mut PgnGame, state:&mut BitBoardState, uci_moves:&[&str]){
+        for uci_move in uci_moves{
+            let chess_move = ChessMove::from_uci(uci_move, state).unwrap();
+            pgn.push_move(chess_move);
+            *state = state.perform_move(chess_move);
+        }
+    }
+
+    #[test]
+    fn tags_and_movetext(){
+        setup_sliding_magics();
+        let mut state = BitBoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let mut pgn = PgnGame::new(state);
+        pgn.set_tag("White", "sonkolbot");
+        pgn.set_tag("Black", "some \"bot\"");
+        pgn.set_tag("WhiteElo", "1500");
+
+        play(&mut pgn, &mut state, &["e2e4", "e7e5", "f1c4"]);
+        play(&mut pgn, &mut state, &["b8c6"]);
+        let qh5 = ChessMove::from_uci("d1h5", &state).unwrap();
+        let result = GetMoveResult::new(qh5, 1234, -35, 8, 14, 2150);
+        pgn.annotate_next(qh5, engine_comment(&result));
+        play(&mut pgn, &mut state, &["d1h5", "g8f6", "h5f7"]);
+        pgn.set_tag("Result", "1-0");
+
+        assert_eq!(pgn.to_pgn(), concat!(
+            "[Event \"?\"]\n",
+            "[Site \"?\"]\n",
+            "[Date \"?\"]\n",
+            "[Round \"?\"]\n",
+            "[White \"sonkolbot\"]\n",
+            "[Black \"some \\\"bot\\\"\"]\n",
+            "[Result \"1-0\"]\n",
+            "[WhiteElo \"1500\"]\n",
+            "\n",
+            "1. e4 e5 2. Bc4 Nc6 3. Qh5\n",
+            "{[%eval -0.35] [%emt 0:00:02.1] depth 8/14, 1234 nodes} 3... Nf6 4. Qxf7# 1-0\n",
+            "\n",
+        ));
+    }
+
+    #[test]
+    fn mate_comment(){
+        setup_sliding_magics();
+        let state = BitBoardState::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+        let ra8 = ChessMove::from_uci("a1a8", &state).unwrap();
+        let result = GetMoveResult::new(ra8, 52, mate_in(1), 4, 6, 30);
+        assert_eq!(engine_comment(&result), "[%eval #1] [%emt 0:00:00.0] depth 4/6, 52 nodes");
+        let result = GetMoveResult::new(ra8, 52, -mate_in(4), 4, 6, 30);
+        assert!(engine_comment(&result).starts_with("[%eval #-2]"));
+    }
+
+    #[test]
+    fn comment_only_for_the_annotated_move(){
+        setup_sliding_magics();
+        let mut state = BitBoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let mut pgn = PgnGame::new(state);
+        let e4 = ChessMove::from_uci("e2e4", &state).unwrap();
+        pgn.annotate_next(e4, "expected e4".to_string());
+        play(&mut pgn, &mut state, &["d2d4", "d7d5"]);
+        assert!(pgn.to_pgn().ends_with("\n1. d4 d5 *\n\n"));
+    }
+
+    #[test]
+    fn starting_from_fen(){
+        setup_sliding_magics();
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 3 17";
+        let mut state = BitBoardState::from_fen(fen).unwrap();
+        let mut pgn = PgnGame::new(state);
+        play(&mut pgn, &mut state, &["e8c8", "e1g1"]);
+        let text = pgn.to_pgn();
+        assert!(text.contains("[SetUp \"1\"]\n[FEN \"r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R b KQkq - 3 17\"]\n"));
+        assert!(text.ends_with("\n17... O-O-O 18. O-O *\n\n"));
+    }
+
+    #[test]
+    fn long_games_are_wrapped(){
+        setup_sliding_magics();
+        let mut state = BitBoardState::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        let mut pgn = PgnGame::new(state);
+        for _ in 0..10{
+            play(&mut pgn, &mut state, &["g1f3", "g8f6", "f3g1", "f6g8"]);
+        }
+        let text = pgn.to_pgn();
+        let movetext = text.split("\n\n").nth(1).unwrap();
+        assert!(movetext.lines().count() > 1);
+        assert!(movetext.lines().all(|line| line.len() <= 80));
+        assert!(movetext.ends_with("Ng1 Ng8 *"));
+    }
+}
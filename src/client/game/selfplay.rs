@@ -0,0 +1,209 @@
+use std::io::{self, Write};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::engine::board::{ChessMove, GameState, PROMOTE_TO_BISHOP, PROMOTE_TO_KNIGHT, PROMOTE_TO_QUEEN, PROMOTE_TO_ROOK};
+use super::engine::eval::is_capture;
+use super::engine::eval::nnue::Network;
+use super::engine::eval::params::EvalParams;
+use super::engine::score::Score;
+use super::engine::state_bitboard::BitBoardState;
+use super::engine::Engine;
+use super::STARTING_POS;
+
+/*
+training data from games of the engine against itself. every game starts with a few random moves, then both sides
+search every move to a fixed depth or number of positions. the quiet positions of the game, the ones that are not in
+check and where the best move is not a capture or a promotion, are kept with the score of the search and the result
+of the game. openings the search already considers lost are thrown away.
+
+every position is a line "<fen> | <score> | <result>", the score in centipawns and the result as 1.0, 0.5 or 0.0,
+both from whites side. the tuner reads these lines, see tuner.rs.
+
+games are played on several threads at once, each game with its own engine and a random generator seeded from the
+seed of the run and the number of the game, so a run with the same settings gives the same games.
+*/
+
+const TABLE_SIZE:usize = 1000000;
+const MAX_DEPTH:usize = 20;
+const MAX_OPENING_SCORE:i32 = 300; //openings where the first search finds more than this are too unbalanced to learn from
+const MAX_PLIES:usize = 400; //longer games are drawn
+const MAX_OPENING_ATTEMPTS:usize = 100;
+
+#[derive(Clone, Debug)]
+pub struct SelfPlayConfig{
+    pub games: usize,
+    pub threads: usize,
+    pub depth: i64,
+    pub nodes: Option<usize>, //searches stop after the iteration that searches this many positions
+    pub random_plies: usize, //random moves at the start of every game
+    pub seed: u64,
+}
+
+impl Default for SelfPlayConfig{
+    fn default() -> Self{
+        Self {
+            games: 100,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            depth: 6,
+            nodes: None,
+            random_plies: 8,
+            seed: 0,
+        }
+    }
+}
+
+//a position of a game, with the score the engine gave it
+#[derive(Clone, PartialEq, Debug)]
+pub struct DataPosition{
+    pub fen: String,
+    pub score: i32, //centipawns from whites side
+}
+
+//the quiet positions of a game and its result from whites side, 1 for a white win, 0.5 for a draw and 0 for a black win
+#[derive(Clone, PartialEq, Debug)]
+pub struct SelfPlayGame{
+    pub positions: Vec<DataPosition>,
+    pub result: f64,
+}
+
+impl SelfPlayGame{
+    //the lines of the data file, see the top of the file
+    pub fn lines(&self) -> Vec<String>{
+        self.positions.iter().map(|position| format!("{} | {} | {:.1}", position.fen, position.score, self.result)).collect()
+    }
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct SelfPlayStats{
+    pub games: usize,
+    pub positions: usize,
+    pub white_wins: usize,
+    pub draws: usize,
+    pub black_wins: usize,
+}
+
+fn is_promotion(chess_move:&ChessMove) -> bool{
+    matches!(chess_move.flag(), PROMOTE_TO_KNIGHT | PROMOTE_TO_BISHOP | PROMOTE_TO_ROOK | PROMOTE_TO_QUEEN)
+}
+
+//a position after random_plies random moves that is still balanced, None when no such position is found
+fn random_opening(engine:&mut Engine, rng:&mut StdRng, random_plies:usize) -> Option<(BitBoardState, Vec<u64>)>{
+    for _ in 0..MAX_OPENING_ATTEMPTS{
+        let mut state = BitBoardState::from_fen(STARTING_POS).unwrap();
+        let mut history = vec![state.hash()];
+        for _ in 0..random_plies{
+            let moves = state.gen_moves_legal().moves_vec();
+            if moves.is_empty() {
+                break;
+            }
+            state = state.perform_move(moves[rng.random_range(0..moves.len())]);
+            history.push(state.hash());
+        }
+        if state.game_state() != GameState::Playing {
+            continue;
+        }
+        if let Score::Cp(score) = engine.get_move_bb(state, &history).eval()
+            && score.abs() <= MAX_OPENING_SCORE {
+            return Some((state, history));
+        }
+    }
+    None
+}
+
+//the result of the game in the position from whites side, None while it goes on
+fn game_result(state:&mut BitBoardState, history:&[u64]) -> Option<f64>{
+    match state.game_state() {
+        GameState::White => return Some(1.0),
+        GameState::Black => return Some(0.0),
+        GameState::Draw => return Some(0.5),
+        GameState::Playing => {}
+    }
+    let repetitions = history.iter().filter(|&&hash| hash == state.hash()).count();
+    if repetitions >= 3 || state.half_move_clock() >= 100 || history.len() > MAX_PLIES {
+        return Some(0.5);
+    }
+    None
+}
+
+//plays one game, game_index picks the random generator so the same index gives the same game
+pub fn play_game(config:&SelfPlayConfig, params:&Arc<EvalParams>, network:&Option<Arc<Network>>, game_index:usize) -> Option<SelfPlayGame>{
+    let mut engine = Engine::new(config.depth, MAX_DEPTH, TABLE_SIZE, None, params.clone());
+    engine.set_node_limit(config.nodes);
+    engine.set_network(network.clone());
+    let mut rng = StdRng::seed_from_u64(config.seed.wrapping_mul(0x9E3779B97F4A7C15).wrapping_add(game_index as u64));
+
+    let (mut state, mut history) = random_opening(&mut engine, &mut rng, config.random_plies)?;
+    let mut positions = Vec::new();
+    loop {
+        if let Some(result) = game_result(&mut state, &history) {
+            return Some(SelfPlayGame { positions, result });
+        }
+
+        let search_result = engine.get_move_bb(state, &history);
+        let chess_move = *search_result.chess_move();
+        match search_result.eval() {
+            //the side that found a mate wins, playing it out teaches nothing
+            Score::Mate(moves) => {
+                return Some(SelfPlayGame { positions, result: if moves > 0 {1.0} else {0.0} });
+            }
+            Score::Cp(score) => {
+                if !state.in_check() && !is_capture(&state, &chess_move) && !is_promotion(&chess_move) {
+                    positions.push(DataPosition { fen: state.to_fen(), score });
+                }
+            }
+        }
+        state = state.perform_move(chess_move);
+        history.push(state.hash());
+    }
+}
+
+//plays config.games games on config.threads threads and writes the positions of every game as soon as it is finished
+pub fn generate(config:&SelfPlayConfig, params:Arc<EvalParams>, network:Option<Arc<Network>>, output:&mut impl Write) -> io::Result<SelfPlayStats>{
+    let next_game = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel::<Option<SelfPlayGame>>();
+    let mut stats = SelfPlayStats::default();
+
+    std::thread::scope(|scope| -> io::Result<()> {
+        for _ in 0..config.threads.max(1){
+            let sender = sender.clone();
+            let (next_game, params, network) = (&next_game, &params, &network);
+            scope.spawn(move || {
+                loop {
+                    let game_index = next_game.fetch_add(1, Ordering::Relaxed);
+                    if game_index >= config.games {
+                        break;
+                    }
+                    //the writer stopped, no one wants the games anymore
+                    if sender.send(play_game(config, params, network, game_index)).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        drop(sender);
+
+        for game in receiver{
+            let Some(game) = game else {
+                continue;
+            };
+            for line in game.lines(){
+                writeln!(output, "{line}")?;
+            }
+            stats.games += 1;
+            stats.positions += game.positions.len();
+            if game.result > 0.5 {
+                stats.white_wins += 1;
+            }else if game.result < 0.5 {
+                stats.black_wins += 1;
+            }else{
+                stats.draws += 1;
+            }
+        }
+        output.flush()
+    })?;
+    Ok(stats)
+}
@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod tests {
+
+    use std::sync::Arc;
+
+    use crate::client::game::engine::eval::params::EvalParams;
+    use crate::client::game::engine::eval::tuner::parse_position;
+    use crate::client::game::engine::state_bitboard::BitBoardState;
+    use crate::client::game::selfplay::{generate, play_game, SelfPlayConfig};
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+
+    fn config(games:usize) -> SelfPlayConfig{
+        SelfPlayConfig { games, threads: 2, depth: 2, nodes: None, random_plies: 6, seed: 7 }
+    }
+
+    #[test]
+    fn games(){
+        setup_sliding_magics();
+        let params = Arc::new(EvalParams::default());
+        let game = play_game(&config(1), &params, &None, 3).unwrap();
+        assert_eq!(play_game(&config(1), &params, &None, 3).unwrap(), game);
+        assert!(!game.positions.is_empty());
+        assert!([0.0, 0.5, 1.0].contains(&game.result));
+
+        //quiet positions the tuner can read
+        for (position, line) in game.positions.iter().zip(game.lines()){
+            assert!(!BitBoardState::from_fen(&position.fen).unwrap().in_check());
+            assert_eq!(parse_position(&line).unwrap().result, game.result);
+        }
+    }
+
+    #[test]
+    fn parallel_games(){
+        setup_sliding_magics();
+        let mut output = Vec::new();
+        let stats = generate(&config(3), Arc::new(EvalParams::default()), None, &mut output).unwrap();
+        assert_eq!(stats.games, 3);
+        assert_eq!(stats.white_wins + stats.draws + stats.black_wins, 3);
+        let text = String::from_utf8(output).unwrap();
+        assert_eq!(text.lines().count(), stats.positions);
+        assert!(text.lines().all(|line| parse_position(line).is_ok()));
+    }
+}
@@ -0,0 +1,251 @@
+use std::io::{self, BufRead, Write};
+use std::sync::Arc;
+
+use super::engine::board::ChessMove;
+use super::engine::eval::nnue::{Accumulator, Network};
+use super::engine::eval::params::EvalParams;
+use super::engine::eval::pawns::PawnTable;
+use super::engine::eval::trace;
+use super::engine::move_string::{uci_move, CastleNotation};
+use super::engine::score::Score;
+use super::engine::state_bitboard::BitBoardState;
+use super::engine::tablebase::Tablebase;
+use super::engine::Engine;
+use super::{load_eval_params, load_network, load_tablebase, BOT_NAME, MAX_DEPTH, MAX_TIME, STARTING_POS, TABLE_SIZE};
+
+/*
+a minimal uci interface, enough to play the engine in a gui or against other engines and to test evaluation changes
+without recompiling: every evaluation parameter outside the piece square tables is a spin option, see params.rs, and
+the EvalFile option loads a whole parameter file. the EvalNetwork option loads a network file and switches to the
+network evaluation, an empty EvalNetwork switches back to the classical evaluation. UCI_Chess960 writes castles as the
+king taking its own rook, the engine still only plays positions of standard chess.
+
+supported commands: uci, isready, ucinewgame, setoption, position (startpos or fen, with moves), go (depth, movetime,
+wtime/btime/winc/binc/movestogo) and quit. the search runs on the thread reading the commands, so stop is not supported.
+eval is not part of uci, it prints every term of the evaluation of the position, see trace.rs.
+*/
+
+const OPTION_RANGE:i32 = 10000; //evaluation parameters can be set between -OPTION_RANGE and OPTION_RANGE
+const MAX_SEARCH_DEPTH:i64 = 64; //depth of go without a depth, the time limit ends the search before
+const MOVES_TO_GO:u128 = 30; //a clock is spread over this many moves when the gui does not say how many are left
+
+//an engine without anything from earlier games in its transposition table
+fn new_engine(params:&EvalParams, tablebase:&Option<Arc<Tablebase>>, network:&Option<Arc<Network>>) -> Engine{
+    let mut engine = Engine::new(MAX_SEARCH_DEPTH, MAX_DEPTH, TABLE_SIZE, None, Arc::new(params.clone()));
+    engine.set_tablebase(tablebase.clone());
+    engine.set_network(network.clone());
+    engine
+}
+
+pub struct UciSession{
+    engine: Engine,
+    tablebase: Option<Arc<Tablebase>>,
+    network: Option<Arc<Network>>,
+    params: EvalParams,
+    castle_notation: CastleNotation,
+    state: BitBoardState,
+    game_history: Vec<u64>, //hashes of every position of the game, up to and including state
+}
+
+impl UciSession{
+    pub fn new(params:EvalParams, network:Option<Arc<Network>>) -> Self{
+        let state = BitBoardState::from_fen(STARTING_POS).unwrap();
+        let tablebase = load_tablebase();
+        let engine = new_engine(&params, &tablebase, &network);
+        Self { engine, tablebase, network, params, castle_notation: CastleNotation::Standard, state, game_history: vec![state.hash()] }
+    }
+
+    #[cfg(test)]
+    pub fn params(&self) -> &EvalParams{
+        &self.params
+    }
+
+    //the answer to a line from the gui, None when the gui wants the engine to quit
+    pub fn handle(&mut self, line:&str) -> Option<Vec<String>>{
+        let mut words = line.split_whitespace();
+        let output = match words.next() {
+            Some("uci") => self.identify(),
+            Some("isready") => vec!["readyok".to_string()],
+            Some("ucinewgame") => {
+                self.engine = new_engine(&self.params, &self.tablebase, &self.network);
+                vec![]
+            }
+            Some("setoption") => self.set_option(&words.collect::<Vec<&str>>()),
+            Some("position") => self.set_position(&words.collect::<Vec<&str>>()),
+            Some("go") => self.go(&words.collect::<Vec<&str>>()),
+            Some("eval") => self.eval(),
+            Some("quit") => return None,
+            Some(command) => vec![format!("info string unknown command {command}")],
+            None => vec![],
+        };
+        Some(output)
+    }
+
+    fn identify(&mut self) -> Vec<String>{
+        let mut output = vec![
+            format!("id name {} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+            format!("id author {BOT_NAME}"),
+            "option name EvalFile type string default <empty>".to_string(),
+            "option name EvalNetwork type string default <empty>".to_string(),
+            "option name UCI_Chess960 type check default false".to_string(),
+        ];
+        for (name, value) in EvalParams::default().options_mut(){
+            output.push(format!("option name {name} type spin default {value} min {} max {OPTION_RANGE}", -OPTION_RANGE));
+        }
+        output.push("uciok".to_string());
+        output
+    }
+
+    //setoption name <name> value <value>, the name can have spaces
+    fn set_option(&mut self, words:&[&str]) -> Vec<String>{
+        let value_index = words.iter().position(|&word| word == "value");
+        if words.first() != Some(&"name") || value_index.is_none() {
+            return vec!["info string usage: setoption name <name> value <value>".to_string()];
+        }
+        let value_index = value_index.unwrap();
+        let name = words[1..value_index].join(" ");
+        let value = words[value_index + 1..].join(" ");
+
+        if name.eq_ignore_ascii_case("UCI_Chess960") {
+            self.castle_notation = match value.as_str() {
+                "true" => CastleNotation::Chess960,
+                "false" => CastleNotation::Standard,
+                _ => return vec![format!("info string '{value}' is not a value for {name}")],
+            };
+            return vec![];
+        }
+
+        if name.eq_ignore_ascii_case("EvalNetwork") {
+            let network = if value.is_empty() || value == "<empty>" {
+                None
+            }else{
+                match Network::from_file(&value) {
+                    Ok(network) => Some(Arc::new(network)),
+                    Err(e) => return vec![format!("info string {e}")],
+                }
+            };
+            self.engine.set_network(network.clone());
+            self.network = network;
+            return vec![];
+        }
+
+        let result = if name.eq_ignore_ascii_case("EvalFile") {
+            if value.is_empty() || value == "<empty>" {
+                Ok(EvalParams::default())
+            }else{
+                EvalParams::from_file(&value)
+            }
+        }else{
+            let mut params = self.params.clone();
+            match value.parse::<i32>() {
+                Ok(number) if number.abs() <= OPTION_RANGE => params.set_option(&name, number).map(|_| params),
+                _ => return vec![format!("info string '{value}' is not a value for {name}")],
+            }
+        };
+        match result {
+            Ok(params) => {
+                self.engine.set_eval_params(Arc::new(params.clone()));
+                self.params = params;
+                vec![]
+            }
+            Err(e) => vec![format!("info string {e}")],
+        }
+    }
+
+    //position startpos|fen <fen> [moves <move>...]
+    fn set_position(&mut self, words:&[&str]) -> Vec<String>{
+        let moves_index = words.iter().position(|&word| word == "moves").unwrap_or(words.len());
+        let state = match words.first() {
+            Some(&"startpos") => BitBoardState::from_fen(STARTING_POS),
+            Some(&"fen") => BitBoardState::from_fen(&words[1..moves_index].join(" ")),
+            _ => return vec!["info string usage: position startpos|fen <fen> [moves <move>...]".to_string()],
+        };
+        let mut state = match state {
+            Ok(state) => state,
+            Err(e) => return vec![format!("info string invalid fen, {e}")],
+        };
+
+        let mut game_history = vec![state.hash()];
+        for word in words.iter().skip(moves_index + 1){
+            match ChessMove::from_uci(word, &state) {
+                Ok(chess_move) => {
+                    state = state.perform_move(chess_move);
+                    game_history.push(state.hash());
+                }
+                Err(e) => return vec![format!("info string {e}")],
+            }
+        }
+        self.state = state;
+        self.game_history = game_history;
+        vec![]
+    }
+
+    //go [depth N] [movetime N] [wtime N] [btime N] [winc N] [binc N] [movestogo N]
+    fn go(&mut self, words:&[&str]) -> Vec<String>{
+        let value = |name:&str| -> Option<u128> {
+            let index = words.iter().position(|&word| word == name)?;
+            words.get(index + 1)?.parse().ok()
+        };
+        let depth = value("depth").map_or(MAX_SEARCH_DEPTH, |depth| (depth as i64).clamp(2, MAX_SEARCH_DEPTH));
+        let (time, increment) = if self.state.white_to_move() {(value("wtime"), value("winc"))} else {(value("btime"), value("binc"))};
+        let max_time = match (value("movetime"), time) {
+            (Some(movetime), _) => Some(movetime),
+            (None, Some(time)) => Some(time/value("movestogo").unwrap_or(MOVES_TO_GO).max(1) + increment.unwrap_or(0)/2),
+            //a depth searches until the depth, anything else as long as a move on lichess since stop is not supported
+            (None, None) if value("depth").is_some() => None,
+            (None, None) => MAX_TIME,
+        };
+
+        if self.state.gen_moves_legal().size() == 0 {
+            return vec!["bestmove 0000".to_string()];
+        }
+        self.engine.set_limits(depth, max_time);
+        let result = self.engine.get_move_bb(self.state, &self.game_history);
+
+        //scores of the engine are from whites side, uci scores are from the side to move
+        let sign = if self.state.white_to_move() {1} else {-1};
+        let score = match result.eval() {
+            Score::Cp(cp) => format!("cp {}", cp*sign),
+            Score::Mate(moves) => format!("mate {}", moves*sign),
+        };
+        let best_move = uci_move(*result.chess_move(), self.castle_notation);
+        vec![
+            format!("info depth {} seldepth {} score {} nodes {} time {} pv {}",
+                result.depth_reached(), result.max_depth_reached(), score, result.num_pos(), result.time(), best_move),
+            format!("bestmove {best_move}"),
+        ]
+    }
+
+    //the terms of the classical evaluation of the position, and the evaluation of the network if there is one
+    fn eval(&self) -> Vec<String>{
+        let trace = trace(&self.state, &self.params, &mut PawnTable::default());
+        let mut output:Vec<String> = trace.to_string().lines().map(str::to_string).collect();
+        if let Some(network) = &self.network {
+            let mut accumulator = Accumulator::default();
+            network.refresh(&self.state, &mut accumulator);
+            output.push(format!("network eval {} from whites side", network.evaluate(&accumulator, self.state.white_to_move())));
+        }
+        output
+    }
+}
+
+//reads commands from standard input until quit or the end of the input
+pub fn run(){
+    let mut session = UciSession::new(load_eval_params().as_ref().clone(), load_network());
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+    for line in stdin.lock().lines(){
+        let Ok(line) = line else {
+            break;
+        };
+        match session.handle(&line) {
+            Some(output) => {
+                for output_line in output{
+                    writeln!(stdout, "{output_line}").ok();
+                }
+                stdout.flush().ok();
+            }
+            None => break,
+        }
+    }
+}
@@ -0,0 +1,118 @@
+#[cfg(test)]
+mod tests {
+
+    use crate::client::game::engine::eval::params::EvalParams;
+    use crate::client::game::engine::eval::tapered;
+    use crate::client::game::engine::move_string::{uci_move, CastleNotation};
+    use crate::client::game::engine::state_bitboard::BitBoardState;
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+    use crate::client::game::uci::UciSession;
+
+    #[test]
+    fn identify(){
+        setup_sliding_magics();
+        let mut session = UciSession::new(EvalParams::default(), None);
+        let output = session.handle("uci").unwrap();
+        assert!(output[0].starts_with("id name"));
+        assert_eq!(output.last().unwrap(), "uciok");
+        assert!(output.contains(&"option name EvalFile type string default <empty>".to_string()));
+        assert!(output.contains(&"option name UCI_Chess960 type check default false".to_string()));
+        assert!(output.contains(&"option name pawn_value mg type spin default 100 min -10000 max 10000".to_string()));
+        assert!(output.contains(&"option name passed_pawn eg 6 type spin default 120 min -10000 max 10000".to_string()));
+        //the piece square tables are only changed by files
+        assert!(!output.iter().any(|line| line.contains("knight mg")));
+
+        assert_eq!(session.handle("isready").unwrap(), vec!["readyok"]);
+        assert!(session.handle("quit").is_none());
+    }
+
+    #[test]
+    fn options(){
+        setup_sliding_magics();
+        let mut session = UciSession::new(EvalParams::default(), None);
+        assert!(session.handle("setoption name pawn_value mg value 90").unwrap().is_empty());
+        assert!(session.handle("setoption name Passed_Pawn EG 6 value 200").unwrap().is_empty());
+        assert_eq!(session.params().pawn_value, tapered(90, 120));
+        assert_eq!(session.params().passed_pawn[6], tapered(60, 200));
+
+        //bad options leave the parameters as they were
+        assert!(session.handle("setoption name dragon value 1").unwrap()[0].starts_with("info string"));
+        assert!(session.handle("setoption name pawn_value mg value x").unwrap()[0].starts_with("info string"));
+        assert!(session.handle("setoption name EvalFile value /nonexistent/params.txt").unwrap()[0].starts_with("info string"));
+        assert_eq!(session.params().pawn_value, tapered(90, 120));
+
+        assert!(session.handle("setoption name EvalFile value <empty>").unwrap().is_empty());
+        assert_eq!(session.params(), &EvalParams::default());
+    }
+
+    #[test]
+    fn search(){
+        setup_sliding_magics();
+        let mut session = UciSession::new(EvalParams::default(), None);
+        assert!(session.handle("position startpos moves e2e4 e7e5 g1f3").unwrap().is_empty());
+        let output = session.handle("go depth 3").unwrap();
+        assert!(output[0].starts_with("info depth 3"));
+        assert!(output[1].starts_with("bestmove "));
+
+        //scores are from the side to move, black is a queen up
+        session.handle("position fen 3qk3/8/8/8/8/8/8/4K3 w - - 0 1");
+        let output = session.handle("go depth 3").unwrap();
+        assert!(output[0].contains("score cp -"), "{}", output[0]);
+
+        //mated
+        session.handle("position fen 7k/6Q1/6K1/8/8/8/8/8 b - - 0 1");
+        assert_eq!(session.handle("go depth 3").unwrap(), vec!["bestmove 0000"]);
+
+        assert!(session.handle("position fen 8/8/8 w - - 0 1").unwrap()[0].starts_with("info string"));
+        assert!(session.handle("position startpos moves e2e5").unwrap()[0].starts_with("info string"));
+    }
+
+    //castles are written as the king taking its own rook with UCI_Chess960, both notations are read
+    #[test]
+    fn chess960_castles(){
+        setup_sliding_magics();
+        let mut session = UciSession::new(EvalParams::default(), None);
+        session.handle("position fen 4k3/8/8/8/8/8/5PPP/4K2R w K - 0 1 moves e1h1");
+        let output = session.handle("eval").unwrap();
+        session.handle("position fen 4k3/8/8/8/8/8/5PPP/4K2R w K - 0 1 moves e1g1");
+        assert_eq!(session.handle("eval").unwrap(), output);
+
+        //the only mate is castling, the king covers c2 from c1
+        let fen = "8/8/8/8/2ppp3/2pkp1N1/8/R3K3 w Q - 0 1";
+        session.handle(&format!("position fen {fen}"));
+        assert_eq!(session.handle("go depth 3").unwrap().last().unwrap(), "bestmove e1c1");
+        assert!(session.handle("setoption name UCI_Chess960 value true").unwrap().is_empty());
+        assert_eq!(session.handle("go depth 3").unwrap().last().unwrap(), "bestmove e1a1");
+        assert!(session.handle("setoption name UCI_Chess960 value maybe").unwrap()[0].starts_with("info string"));
+        assert!(session.handle("setoption name UCI_Chess960 value false").unwrap().is_empty());
+        assert_eq!(session.handle("go depth 3").unwrap().last().unwrap(), "bestmove e1c1");
+    }
+
+    #[test]
+    fn eval(){
+        setup_sliding_magics();
+        let mut session = UciSession::new(EvalParams::default(), None);
+        session.handle("position fen 3qk3/8/8/8/8/8/8/4K3 w - - 0 1");
+        let output = session.handle("eval").unwrap();
+        assert!(output[0].starts_with("term"));
+        assert!(output.iter().any(|line| line.starts_with("material")));
+        let eval:i32 = output.last().unwrap().split_whitespace().nth(1).unwrap().parse().unwrap();
+        assert!(eval < -500);
+    }
+
+    //without time the first iteration still finishes, the move is legal
+    #[test]
+    fn no_time(){
+        setup_sliding_magics();
+        let mut session = UciSession::new(EvalParams::default(), None);
+        let fen = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+        let legal_moves:Vec<String> = BitBoardState::from_fen(fen).unwrap().gen_moves_legal().moves_vec().into_iter()
+            .map(|chess_move| uci_move(chess_move, CastleNotation::Standard)).collect();
+        session.handle(&format!("position fen {fen}"));
+        for go in ["go movetime 0", "go wtime 0 btime 0"]{
+            let output = session.handle(go).unwrap();
+            let best_move = output.last().unwrap().strip_prefix("bestmove ").unwrap().to_string();
+            assert!(legal_moves.contains(&best_move), "{go}: {best_move}");
+        }
+    }
+}
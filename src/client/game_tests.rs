@@ -0,0 +1,21 @@
+#[cfg(test)]
+mod tests {
+
+    use licheszter::models::game::VariantMode;
+
+    use crate::client::game::engine::test_setup::setup_sliding_magics;
+    use crate::client::game::unplayable_reason;
+
+    #[test]
+    fn playable_games(){
+        setup_sliding_magics();
+        assert_eq!(unplayable_reason(&VariantMode::Standard, None), None);
+        assert_eq!(unplayable_reason(&VariantMode::Standard, Some("startpos")), None);
+        assert_eq!(unplayable_reason(&VariantMode::FromPosition, Some("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1")), None);
+
+        assert!(unplayable_reason(&VariantMode::Chess960, None).is_some());
+        assert!(unplayable_reason(&VariantMode::Atomic, Some("startpos")).is_some());
+        //chess960 castling rights look invalid to the standard rules
+        assert!(unplayable_reason(&VariantMode::FromPosition, Some("bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1")).is_some());
+    }
+}
@@ -1,10 +1,43 @@
 mod client;
 
 
-use crate::client::li_bot;
+use crate::client::{build_book, check_pgn, eval_position, li_bot, self_play, tune_eval, uci};
 
 
 fn main() {
+    //pgn <file> checks that every game of a pgn file can be read, book builds an opening book from pgn files,
+    //tune tunes the evaluation parameters on positions labelled with results, selfplay writes positions from games of
+    //the engine against itself for the tuner, eval <fen> prints the terms of the evaluation of a position,
+    //uci plays through the uci protocol
+    //without arguments the bot is started
+    let args:Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("pgn") => {
+            check_pgn(&args[2..]);
+            return;
+        }
+        Some("book") => {
+            build_book(&args[2..]);
+            return;
+        }
+        Some("selfplay") => {
+            self_play(&args[2..]);
+            return;
+        }
+        Some("eval") => {
+            eval_position(&args[2..]);
+            return;
+        }
+        Some("uci") => {
+            uci();
+            return;
+        }
+        Some("tune") => {
+            tune_eval(&args[2..]);
+            return;
+        }
+        _ => {}
+    }
 
     let rt = tokio::runtime::Builder::new_current_thread()
         .enable_all()